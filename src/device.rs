@@ -1,16 +1,69 @@
+use std::ffi::CStr;
 use std::sync::Arc;
 
+use ash::prelude::VkResult;
 use ash::vk;
 
 use crate::init::EnabledFeatures;
-use crate::instance::InstanceContext;
+use crate::init::device::{DeviceInfo, VulkanQueue};
+use crate::init::rosella_features::{DeviceQueues, SurfacePresentQueues};
+use crate::instance::{InstanceContext, VulkanVersion};
+use crate::objects::barrier::{self, ImageBarrierBuilder};
+use crate::objects::buffer::{self, BufferViewCreateDesc, BufferViewError};
+use crate::objects::fence::{self, FenceHandle};
+use crate::objects::readback;
+use crate::objects::sampler::{self, SamplerCreateDesc};
+use crate::objects::semaphore;
+use crate::objects::AccessInfo;
+use crate::objects::ImageSubresourceRange;
+use crate::objects::query::{OcclusionQueryPool, PipelineStatisticsQueryPool, QueryError};
+use crate::shader::{DescriptorPoolHandle, DescriptorSetLayoutHandle, DescriptorWriter, GraphicsContext, PipelineLayoutHandle};
 use crate::util::extensions::{AsRefOption, ExtensionFunctionSet, VkExtensionInfo, VkExtensionFunctions};
 use crate::UUID;
 
+/// Information about the physical device and driver backing a [`DeviceContext`].
+#[derive(Clone, Debug, Default)]
+pub struct DriverInfo {
+    pub device_name: String,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    pub device_type: vk::PhysicalDeviceType,
+    /// The vendor specific driver version
+    pub driver_version: u32,
+    /// The driver id. Only available on vulkan 1.2 devices.
+    pub driver_id: Option<vk::DriverId>,
+    /// The human readable driver name. Only available on vulkan 1.2 devices.
+    pub driver_name: Option<String>,
+    /// Additional driver information like the human readable version. Only available on vulkan 1.2
+    /// devices.
+    pub driver_info: Option<String>,
+}
+
+impl DriverInfo {
+    pub fn from_device_info(info: &DeviceInfo) -> Self {
+        let properties = info.get_device_1_0_properties();
+        let properties_1_2 = info.get_device_1_2_properties();
+
+        Self {
+            device_name: unsafe { CStr::from_ptr(properties.device_name.as_ptr()) }.to_string_lossy().into_owned(),
+            vendor_id: properties.vendor_id,
+            device_id: properties.device_id,
+            device_type: properties.device_type,
+            driver_version: properties.driver_version,
+            driver_id: properties_1_2.map(|properties| properties.driver_id),
+            driver_name: properties_1_2.map(|properties| unsafe { CStr::from_ptr(properties.driver_name.as_ptr()) }.to_string_lossy().into_owned()),
+            driver_info: properties_1_2.map(|properties| unsafe { CStr::from_ptr(properties.driver_info.as_ptr()) }.to_string_lossy().into_owned()),
+        }
+    }
+}
+
 pub struct DeviceContextImpl {
     instance: InstanceContext,
     device: ash::Device,
     physical_device: vk::PhysicalDevice,
+    /// The lower of the instance version and the api version of the physical device
+    version: VulkanVersion,
+    driver_info: DriverInfo,
     extensions: ExtensionFunctionSet,
     features: EnabledFeatures,
 }
@@ -18,6 +71,10 @@ pub struct DeviceContextImpl {
 impl Drop for DeviceContextImpl {
     fn drop(&mut self) {
         unsafe {
+            // Make sure no work is still executing before the device is destroyed
+            if let Err(err) = self.device.device_wait_idle() {
+                log::error!("Failed to wait for device idle before destruction: {:?}", err);
+            }
             self.device.destroy_device(None);
         }
     }
@@ -27,16 +84,39 @@ impl Drop for DeviceContextImpl {
 pub struct DeviceContext(Arc<DeviceContextImpl>);
 
 impl DeviceContext {
-    pub fn new(instance: InstanceContext, device: ash::Device, physical_device: vk::PhysicalDevice, extensions: ExtensionFunctionSet, features: EnabledFeatures) -> Self {
+    pub fn new(instance: InstanceContext, device: ash::Device, physical_device: vk::PhysicalDevice, version: VulkanVersion, driver_info: DriverInfo, extensions: ExtensionFunctionSet, features: EnabledFeatures) -> Self {
         Self(Arc::new(DeviceContextImpl{
             instance,
             device,
             physical_device,
+            version,
+            driver_info,
             extensions,
             features,
         }))
     }
 
+    /// Creates a device context which is not backed by any vulkan implementation.
+    ///
+    /// Only creating, cloning and dropping the context, [`DeviceContext::wait_idle`], creating an
+    /// object manager, creating and destroying semaphores and creating object sets containing
+    /// buffers are supported. Any other vulkan call aborts the process. This is intended for tests
+    /// of logic which needs a context but no gpu.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn new_mock() -> Self {
+        let (entry, instance, device) = crate::util::mock::make_mock_vk();
+
+        let instance = InstanceContext::new(
+            VulkanVersion::VK_1_2,
+            entry,
+            instance,
+            ExtensionFunctionSet::new(),
+            EnabledFeatures::new(std::iter::empty())
+        );
+
+        Self::new(instance, device, <vk::PhysicalDevice as vk::Handle>::from_raw(1), VulkanVersion::VK_1_2, DriverInfo::default(), ExtensionFunctionSet::new(), EnabledFeatures::new(std::iter::empty()))
+    }
+
     pub fn get_entry(&self) -> &ash::Entry {
         self.0.instance.get_entry()
     }
@@ -53,6 +133,17 @@ impl DeviceContext {
         &self.0.physical_device
     }
 
+    /// Returns the name of the physical device.
+    pub fn get_device_name(&self) -> &str {
+        &self.0.driver_info.device_name
+    }
+
+    /// Returns information about the physical device and driver collected when the device was
+    /// created.
+    pub fn get_driver_info(&self) -> &DriverInfo {
+        &self.0.driver_info
+    }
+
     pub fn get_extension<T: VkExtensionInfo>(&self) -> Option<&T> where VkExtensionFunctions: AsRefOption<T> {
         self.0.extensions.get()
     }
@@ -64,4 +155,266 @@ impl DeviceContext {
     pub fn get_enabled_features(&self) -> &EnabledFeatures {
         &self.0.features
     }
+
+    /// Returns the vulkan version usable with the device. This is the lower of the instance version
+    /// and the api version of the physical device.
+    pub fn get_effective_version(&self) -> VulkanVersion {
+        self.0.version
+    }
+
+    /// Blocks until all queues of the device are idle
+    pub fn wait_idle(&self) -> VkResult<()> {
+        unsafe { self.0.device.device_wait_idle() }
+    }
+
+    /// Returns the graphics queue acquired during device creation
+    pub fn get_graphics_queue(&self) -> Option<VulkanQueue> {
+        DeviceQueues::get(self).and_then(DeviceQueues::get_graphics_queue).cloned()
+    }
+
+    /// Returns the compute queue acquired during device creation. This may be the graphics queue.
+    pub fn get_compute_queue(&self) -> Option<VulkanQueue> {
+        DeviceQueues::get(self).and_then(DeviceQueues::get_compute_queue).cloned()
+    }
+
+    /// Returns the transfer queue acquired during device creation. This may be the graphics or
+    /// compute queue.
+    pub fn get_transfer_queue(&self) -> Option<VulkanQueue> {
+        DeviceQueues::get(self).and_then(DeviceQueues::get_transfer_queue).cloned()
+    }
+
+    /// Returns the queue that should be used to present to `surface`. The surface must have been
+    /// registered using [`SurfacePresentQueues`] during device creation.
+    pub fn get_present_queue(&self, surface: vk::SurfaceKHR) -> Option<VulkanQueue> {
+        SurfacePresentQueues::get_present_queue(self, surface).cloned()
+    }
+
+    /// Creates a descriptor pool large enough to allocate `copies` instances of every descriptor set
+    /// of all provided contexts.
+    pub fn create_descriptor_pool_for(&self, contexts: &[&GraphicsContext], copies: u32) -> VkResult<vk::DescriptorPool> {
+        let pool_sizes = GraphicsContext::combined_descriptor_pool_sizes(contexts, copies);
+        let max_sets = contexts.iter().map(|context| context.set_count()).sum::<u32>() * copies;
+
+        let create_info = vk::DescriptorPoolCreateInfo::builder()
+            .max_sets(max_sets)
+            .pool_sizes(pool_sizes.as_slice());
+
+        unsafe { self.0.device.create_descriptor_pool(&create_info, None) }
+    }
+
+    /// Like [`DeviceContext::create_descriptor_pool_for`] but returns a pool which is destroyed
+    /// when dropped.
+    pub fn create_descriptor_pool_handle_for(&self, contexts: &[&GraphicsContext], copies: u32) -> VkResult<DescriptorPoolHandle> {
+        Ok(DescriptorPoolHandle::from_raw(self.clone(), self.create_descriptor_pool_for(contexts, copies)?))
+    }
+
+    /// Creates a descriptor pool which is destroyed when dropped.
+    pub fn create_descriptor_pool(&self, create_info: &vk::DescriptorPoolCreateInfo) -> VkResult<DescriptorPoolHandle> {
+        DescriptorPoolHandle::new(self.clone(), create_info)
+    }
+
+    /// Creates a descriptor set layout which is destroyed when dropped.
+    pub fn create_descriptor_set_layout(&self, create_info: &vk::DescriptorSetLayoutCreateInfo) -> VkResult<DescriptorSetLayoutHandle> {
+        DescriptorSetLayoutHandle::new(self.clone(), create_info)
+    }
+
+    /// Creates a pipeline layout which is destroyed when dropped.
+    pub fn create_pipeline_layout(&self, create_info: &vk::PipelineLayoutCreateInfo) -> VkResult<PipelineLayoutHandle> {
+        PipelineLayoutHandle::new(self.clone(), create_info)
+    }
+
+    /// Creates a new fence which must be destroyed by the caller.
+    pub fn create_fence(&self, signaled: bool) -> VkResult<vk::Fence> {
+        fence::create_fence(self, signaled)
+    }
+
+    /// Creates a new fence which is destroyed when the returned handle is dropped.
+    pub fn create_fence_handle(&self, signaled: bool) -> VkResult<FenceHandle> {
+        FenceHandle::new(self.clone(), signaled)
+    }
+
+    /// Waits for the fences. Returns false if the timeout (in nanoseconds) expired.
+    pub fn wait_for_fences(&self, fences: &[vk::Fence], wait_all: bool, timeout: u64) -> VkResult<bool> {
+        fence::wait_for_fences(self, fences, wait_all, timeout)
+    }
+
+    pub fn reset_fences(&self, fences: &[vk::Fence]) -> VkResult<()> {
+        fence::reset_fences(self, fences)
+    }
+
+    /// Returns true if timeline semaphores are provided by vulkan 1.2 core instead of the
+    /// VK_KHR_timeline_semaphore extension.
+    pub fn is_timeline_semaphore_core(&self) -> bool {
+        semaphore::is_timeline_semaphore_core(self)
+    }
+
+    pub fn get_semaphore_counter_value(&self, semaphore: vk::Semaphore) -> VkResult<u64> {
+        semaphore::get_semaphore_counter_value(self, semaphore)
+    }
+
+    /// Waits for the timeline semaphores. Returns false if the timeout (in nanoseconds) expired.
+    pub fn wait_semaphores(&self, semaphores: &[vk::Semaphore], values: &[u64], timeout: u64) -> VkResult<bool> {
+        semaphore::wait_semaphores(self, semaphores, values, timeout)
+    }
+
+    pub fn signal_semaphore(&self, semaphore: vk::Semaphore, value: u64) -> VkResult<()> {
+        semaphore::signal_semaphore(self, semaphore, value)
+    }
+
+    /// Creates a texel buffer view which must be destroyed by the caller. See
+    /// [`buffer::create_buffer_view`].
+    pub fn create_buffer_view(&self, buffer: vk::Buffer, usage_flags: vk::BufferUsageFlags, desc: &BufferViewCreateDesc) -> Result<vk::BufferView, BufferViewError> {
+        buffer::create_buffer_view(self, buffer, usage_flags, desc)
+    }
+
+    /// Creates a sampler which must be destroyed by the caller. See [`sampler::create_sampler`].
+    pub fn create_sampler(&self, desc: &SamplerCreateDesc) -> VkResult<vk::Sampler> {
+        sampler::create_sampler(self, desc)
+    }
+
+    /// Sets the debug name of a vulkan object. Does nothing if VK_EXT_debug_utils is not enabled.
+    pub fn set_debug_name<T: vk::Handle>(&self, object: T, name: &str) {
+        let debug_utils = match self.get_instance().get_extension::<ash::extensions::ext::DebugUtils>() {
+            Some(debug_utils) => debug_utils,
+            None => return,
+        };
+        let name = match std::ffi::CString::new(name) {
+            Ok(name) => name,
+            Err(_) => return,
+        };
+
+        let name_info = vk::DebugUtilsObjectNameInfoEXT::builder()
+            .object_type(T::TYPE)
+            .object_handle(object.as_raw())
+            .object_name(&name);
+
+        if let Err(err) = unsafe { debug_utils.debug_utils_set_object_name(self.vk().handle(), &name_info) } {
+            log::warn!("Failed to set debug name {:?}: {:?}", name, err);
+        }
+    }
+
+    /// Records commands into a temporary command buffer, submits it to `queue` and waits for the
+    /// execution to complete.
+    ///
+    /// A transient command pool is created for every call so this should only be used for setup
+    /// work like initial layout transitions or one-off copies.
+    pub fn immediate_submit<F: FnOnce(vk::CommandBuffer)>(&self, queue: &VulkanQueue, record: F) -> VkResult<()> {
+        self.immediate_submit_impl(queue, None, record)
+    }
+
+    /// Like [`DeviceContext::immediate_submit`] but waits for the access to begin before executing
+    /// and signals the end of the access once the commands have completed.
+    pub fn immediate_submit_synchronized<F: FnOnce(vk::CommandBuffer)>(&self, queue: &VulkanQueue, access: &AccessInfo, record: F) -> VkResult<()> {
+        self.immediate_submit_impl(queue, Some(access), record)
+    }
+
+    /// Downloads `len` bytes starting at `offset` from `buffer`. See [`readback::download_from_buffer`].
+    pub fn download_from_buffer(&self, queue: &VulkanQueue, buffer: vk::Buffer, offset: vk::DeviceSize, len: vk::DeviceSize, access: Option<&AccessInfo>) -> VkResult<Vec<u8>> {
+        readback::download_from_buffer(self, queue, buffer, offset, len, access)
+    }
+
+    /// Downloads `count` elements of `T` starting at `offset` from `buffer`.
+    pub fn download_from_buffer_typed<T: bytemuck::Pod>(&self, queue: &VulkanQueue, buffer: vk::Buffer, offset: vk::DeviceSize, count: usize, access: Option<&AccessInfo>) -> VkResult<Vec<T>> {
+        readback::download_from_buffer_typed(self, queue, buffer, offset, count, access)
+    }
+
+    fn immediate_submit_impl<F: FnOnce(vk::CommandBuffer)>(&self, queue: &VulkanQueue, access: Option<&AccessInfo>, record: F) -> VkResult<()> {
+        let pool_info = vk::CommandPoolCreateInfo::builder()
+            .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+            .queue_family_index(queue.get_family());
+        let pool = unsafe { self.vk().create_command_pool(&pool_info, None) }?;
+
+        let result = self.immediate_submit_with_pool(queue, pool, access, record);
+        unsafe { self.vk().destroy_command_pool(pool, None) };
+
+        result
+    }
+
+    fn immediate_submit_with_pool<F: FnOnce(vk::CommandBuffer)>(&self, queue: &VulkanQueue, pool: vk::CommandPool, access: Option<&AccessInfo>, record: F) -> VkResult<()> {
+        let allocate_info = vk::CommandBufferAllocateInfo::builder()
+            .command_pool(pool)
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_buffer_count(1);
+        let command_buffer = unsafe { self.vk().allocate_command_buffers(&allocate_info) }?[0];
+
+        let begin_info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        unsafe { self.vk().begin_command_buffer(command_buffer, &begin_info) }?;
+        record(command_buffer);
+        unsafe { self.vk().end_command_buffer(command_buffer) }?;
+
+        let fence = self.create_fence_handle(false)?;
+        let command_buffers = [command_buffer];
+        let semaphores: Vec<_> = access.iter().map(|access| access.semaphore).collect();
+        let wait_values: Vec<_> = access.iter().map(|access| access.begin_access).collect();
+        let signal_values: Vec<_> = access.iter().map(|access| access.end_access).collect();
+        let wait_stages: Vec<_> = access.iter().map(|_| vk::PipelineStageFlags::ALL_COMMANDS).collect();
+
+        let mut timeline_info = vk::TimelineSemaphoreSubmitInfo::builder()
+            .wait_semaphore_values(&wait_values)
+            .signal_semaphore_values(&signal_values);
+        let mut submit_info = vk::SubmitInfo::builder()
+            .command_buffers(&command_buffers)
+            .wait_semaphores(&semaphores)
+            .wait_dst_stage_mask(&wait_stages)
+            .signal_semaphores(&semaphores);
+        if access.is_some() {
+            submit_info = submit_info.push_next(&mut timeline_info);
+        }
+
+        queue.queue_submit(self.vk().clone(), std::slice::from_ref(&submit_info), fence.get_handle())?;
+        fence.wait(u64::MAX)?;
+
+        Ok(())
+    }
+
+    /// Creates a pool of `count` occlusion queries.
+    pub fn create_occlusion_query_pool(&self, count: u32) -> VkResult<OcclusionQueryPool> {
+        OcclusionQueryPool::new(self.clone(), count)
+    }
+
+    /// Creates a pool of `count` pipeline statistics queries. Returns an error if the
+    /// pipelineStatisticsQuery feature is not enabled.
+    pub fn create_pipeline_statistics_query_pool(&self, count: u32, statistics: vk::QueryPipelineStatisticFlags) -> Result<PipelineStatisticsQueryPool, QueryError> {
+        PipelineStatisticsQueryPool::new(self.clone(), count, statistics)
+    }
+
+    /// Begins a dynamic render pass. Requires the
+    /// [`crate::init::rosella_features::DynamicRenderingDevice`] feature.
+    pub fn begin_rendering(&self, command_buffer: vk::CommandBuffer, rendering_info: &vk::RenderingInfoKHR) -> VkResult<()> {
+        let dynamic_rendering = self.get_extension::<ash::extensions::khr::DynamicRendering>()
+            .ok_or(vk::Result::ERROR_EXTENSION_NOT_PRESENT)?;
+
+        unsafe { dynamic_rendering.cmd_begin_rendering(command_buffer, rendering_info) };
+        Ok(())
+    }
+
+    /// Ends a dynamic render pass started with [`DeviceContext::begin_rendering`].
+    pub fn end_rendering(&self, command_buffer: vk::CommandBuffer) -> VkResult<()> {
+        let dynamic_rendering = self.get_extension::<ash::extensions::khr::DynamicRendering>()
+            .ok_or(vk::Result::ERROR_EXTENSION_NOT_PRESENT)?;
+
+        unsafe { dynamic_rendering.cmd_end_rendering(command_buffer) };
+        Ok(())
+    }
+
+    /// Pushes the writes of `writer` into descriptor set `set` of the pipeline layout. Requires the
+    /// [`crate::init::rosella_features::PushDescriptorDevice`] feature.
+    pub fn push_descriptor_set(&self, command_buffer: vk::CommandBuffer, bind_point: vk::PipelineBindPoint, layout: vk::PipelineLayout, set: u32, writer: &DescriptorWriter) -> VkResult<()> {
+        writer.push(self, command_buffer, bind_point, layout, set)
+    }
+
+    /// Records a barrier making compute shader storage writes to `image` available for sampling in
+    /// fragment shaders. Requires the [`crate::init::rosella_features::Synchronization2Device`]
+    /// feature.
+    pub fn record_compute_to_sample_barrier(&self, command_buffer: vk::CommandBuffer, image: vk::Image, range: ImageSubresourceRange) -> VkResult<()> {
+        barrier::image_barrier2(self, command_buffer, ImageBarrierBuilder::compute_write_to_sample(image, range))
+    }
+
+    /// Records a barrier allowing compute shaders to write to `image` after it has been sampled in
+    /// fragment shaders. Requires the [`crate::init::rosella_features::Synchronization2Device`]
+    /// feature.
+    pub fn record_sample_to_compute_barrier(&self, command_buffer: vk::CommandBuffer, image: vk::Image, range: ImageSubresourceRange) -> VkResult<()> {
+        barrier::image_barrier2(self, command_buffer, ImageBarrierBuilder::sample_to_compute_write(image, range))
+    }
 }
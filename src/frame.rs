@@ -0,0 +1,518 @@
+//! Simple per frame rendering to a window surface.
+//!
+//! The [`FrameRenderer`] owns a swapchain together with the command buffers and synchronization
+//! objects required to render to it. Each call to [`FrameRenderer::render_frame`] acquires an image,
+//! lets the application record commands and then submits and presents the image.
+
+use ash::extensions::khr::{Surface, Swapchain};
+use ash::prelude::VkResult;
+use ash::vk;
+
+use crate::init::device::VulkanQueue;
+use crate::objects::swapchain;
+use crate::objects::{Format, SurfaceFormatPolicy, SwapchainSharing};
+use crate::objects::swapchain::{SwapchainCreateDesc, SwapchainImageSpec, SwapchainImageState};
+use crate::rosella::DeviceContext;
+
+/// The number of frames that may be processed by the gpu at the same time
+const FRAMES_IN_FLIGHT: usize = 2;
+
+#[derive(Debug)]
+pub enum FrameError {
+    Vulkan(vk::Result),
+    /// The device has no graphics queue or no queue that can present to the surface.
+    NoQueue,
+    /// The surface does not report any supported formats known to rosella.
+    NoSurfaceFormat,
+    /// The surface does not support the requested swapchain image usage. Contains the unsupported
+    /// flags.
+    UnsupportedUsage(vk::ImageUsageFlags),
+    /// The requested swapchain image usage contains neither `COLOR_ATTACHMENT`, `STORAGE` nor
+    /// `TRANSFER_DST` so the images can not be written to.
+    NoWritableUsage(vk::ImageUsageFlags),
+    /// The swapchain extension is not enabled on the device.
+    SwapchainNotEnabled,
+}
+
+impl From<vk::Result> for FrameError {
+    fn from(err: vk::Result) -> Self {
+        FrameError::Vulkan(err)
+    }
+}
+
+/// Information about the frame currently being recorded.
+///
+/// The image is in the layout returned by [`FrameContext::get_image_layout`] when the callback is
+/// invoked and must be left in that layout once all commands have been recorded. The layout depends
+/// on the image usage of the renderer, see [`SwapchainImageState::for_usage`].
+pub struct FrameContext<'a> {
+    device: &'a DeviceContext,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    image_state: SwapchainImageState,
+    image_view: vk::ImageView,
+    image_index: u32,
+    frame_index: u64,
+    format: vk::Format,
+    extent: vk::Extent2D,
+}
+
+impl<'a> FrameContext<'a> {
+    pub fn get_device(&self) -> &DeviceContext {
+        self.device
+    }
+
+    /// Returns the command buffer commands for this frame should be recorded into. The command
+    /// buffer is already in the recording state.
+    pub fn get_command_buffer(&self) -> vk::CommandBuffer {
+        self.command_buffer
+    }
+
+    pub fn get_image(&self) -> vk::Image {
+        self.image
+    }
+
+    pub fn get_image_view(&self) -> vk::ImageView {
+        self.image_view
+    }
+
+    /// Returns the layout of the image while the frame is recorded
+    pub fn get_image_layout(&self) -> vk::ImageLayout {
+        self.image_state.layout
+    }
+
+    /// Returns the layout of the image together with the stages and accesses the acquire barrier
+    /// makes it available to
+    pub fn get_image_state(&self) -> &SwapchainImageState {
+        &self.image_state
+    }
+
+    /// Returns the index of the image in the swapchain
+    pub fn get_image_index(&self) -> u32 {
+        self.image_index
+    }
+
+    /// Returns the number of frames rendered before this frame
+    pub fn get_frame_index(&self) -> u64 {
+        self.frame_index
+    }
+
+    pub fn get_format(&self) -> vk::Format {
+        self.format
+    }
+
+    pub fn get_extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+}
+
+/// Command recording resources for a single frame in flight
+struct FrameResources {
+    command_pool: vk::CommandPool,
+    command_buffer: vk::CommandBuffer,
+    image_available: vk::Semaphore,
+    in_flight: vk::Fence,
+}
+
+impl FrameResources {
+    fn new(device: &DeviceContext, queue_family: u32) -> VkResult<Self> {
+        let pool_info = vk::CommandPoolCreateInfo::builder()
+            .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+            .queue_family_index(queue_family);
+        let command_pool = unsafe { device.vk().create_command_pool(&pool_info, None) }?;
+
+        let mut resources = Self {
+            command_pool,
+            command_buffer: vk::CommandBuffer::null(),
+            image_available: vk::Semaphore::null(),
+            in_flight: vk::Fence::null(),
+        };
+        if let Err(err) = resources.init(device) {
+            resources.destroy(device);
+            return Err(err);
+        }
+
+        Ok(resources)
+    }
+
+    fn init(&mut self, device: &DeviceContext) -> VkResult<()> {
+        let allocate_info = vk::CommandBufferAllocateInfo::builder()
+            .command_pool(self.command_pool)
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_buffer_count(1);
+        self.command_buffer = unsafe { device.vk().allocate_command_buffers(&allocate_info) }?[0];
+        self.image_available = unsafe { device.vk().create_semaphore(&vk::SemaphoreCreateInfo::default(), None) }?;
+        self.in_flight = device.create_fence(true)?;
+
+        Ok(())
+    }
+
+    fn destroy(&mut self, device: &DeviceContext) {
+        unsafe {
+            device.vk().destroy_fence(self.in_flight, None);
+            device.vk().destroy_semaphore(self.image_available, None);
+            device.vk().destroy_command_pool(self.command_pool, None);
+        }
+    }
+}
+
+/// The swapchain and the resources depending on its images
+struct SwapchainState {
+    swapchain: vk::SwapchainKHR,
+    format: vk::Format,
+    extent: vk::Extent2D,
+    /// The state the images are written in between acquire and present
+    image_state: SwapchainImageState,
+    images: Vec<vk::Image>,
+    views: Vec<vk::ImageView>,
+    /// Signaled when rendering to the image with the same index has finished
+    render_finished: Vec<vk::Semaphore>,
+}
+
+/// Renders frames to a window surface.
+pub struct FrameRenderer {
+    device: DeviceContext,
+    surface_loader: Surface,
+    surface: vk::SurfaceKHR,
+    swapchain_loader: Swapchain,
+    graphics_queue: VulkanQueue,
+    present_queue: VulkanQueue,
+    format_policy: SurfaceFormatPolicy,
+    image_usage: vk::ImageUsageFlags,
+    frames: Vec<FrameResources>,
+    swapchain: Option<SwapchainState>,
+    /// The size of the window used if the surface does not define its extent
+    window_extent: vk::Extent2D,
+    outdated: bool,
+    frame_index: u64,
+}
+
+impl FrameRenderer {
+    /// Creates a new renderer for the surface. The surface must have been registered using
+    /// [`crate::init::rosella_features::SurfacePresentQueues`] during device creation.
+    ///
+    /// The swapchain is created when the first frame is rendered.
+    pub fn new(device: DeviceContext, surface_loader: Surface, surface: vk::SurfaceKHR, window_extent: vk::Extent2D) -> Result<Self, FrameError> {
+        let swapchain_loader = device.get_extension::<Swapchain>().ok_or(FrameError::SwapchainNotEnabled)?.clone();
+        let graphics_queue = device.get_graphics_queue().ok_or(FrameError::NoQueue)?;
+        let present_queue = device.get_present_queue(surface).ok_or(FrameError::NoQueue)?;
+
+        let mut frames = Vec::with_capacity(FRAMES_IN_FLIGHT);
+        for _ in 0..FRAMES_IN_FLIGHT {
+            match FrameResources::new(&device, graphics_queue.get_family()) {
+                Ok(frame) => frames.push(frame),
+                Err(err) => {
+                    frames.iter_mut().for_each(|frame| frame.destroy(&device));
+                    return Err(err.into());
+                }
+            }
+        }
+
+        Ok(Self {
+            device,
+            surface_loader,
+            surface,
+            swapchain_loader,
+            graphics_queue,
+            present_queue,
+            format_policy: SurfaceFormatPolicy::default(),
+            image_usage: vk::ImageUsageFlags::COLOR_ATTACHMENT,
+            frames,
+            swapchain: None,
+            window_extent,
+            outdated: true,
+            frame_index: 0,
+        })
+    }
+
+    /// Sets the policy used to select the surface format the next time the swapchain is created.
+    pub fn set_format_policy(&mut self, policy: SurfaceFormatPolicy) {
+        self.format_policy = policy;
+        self.outdated = true;
+    }
+
+    /// Sets the usage flags of the swapchain images the next time the swapchain is created. Defaults
+    /// to `COLOR_ATTACHMENT`. Compute or post processing renderers may for example request
+    /// `STORAGE` or `TRANSFER_DST` usage. The layout of the image while a frame is recorded is
+    /// derived from the usage, see [`SwapchainImageState::for_usage`].
+    pub fn set_image_usage(&mut self, usage: vk::ImageUsageFlags) {
+        self.image_usage = usage;
+        self.outdated = true;
+    }
+
+    /// Marks the swapchain for recreation with a new window size before the next frame.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.window_extent = vk::Extent2D { width, height };
+        self.outdated = true;
+    }
+
+    /// Returns the number of frames rendered so far
+    pub fn get_frame_index(&self) -> u64 {
+        self.frame_index
+    }
+
+    /// Acquires a swapchain image, runs `record` to record commands for the frame and then submits
+    /// and presents the image. Blocks if too many frames are already being processed by the gpu.
+    ///
+    /// Returns false without calling `record` if no image could be acquired, for example because
+    /// the window is minimized or the swapchain had to be recreated.
+    pub fn render_frame<F: FnOnce(&FrameContext)>(&mut self, record: F) -> Result<bool, FrameError> {
+        if self.outdated && !self.recreate_swapchain()? {
+            return Ok(false);
+        }
+
+        let frame = &self.frames[(self.frame_index % FRAMES_IN_FLIGHT as u64) as usize];
+        self.device.wait_for_fences(std::slice::from_ref(&frame.in_flight), true, u64::MAX)?;
+
+        let swapchain = self.swapchain.as_ref().unwrap();
+        let image_index = match unsafe { self.swapchain_loader.acquire_next_image(swapchain.swapchain, u64::MAX, frame.image_available, vk::Fence::null()) } {
+            Ok((index, _)) => index,
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                self.outdated = true;
+                return Ok(false);
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        if let Err(err) = self.submit_frame(frame, swapchain, image_index, record) {
+            // The acquired image is released by recreating the swapchain
+            self.outdated = true;
+            self.recover_frame(frame)?;
+            return Err(err);
+        }
+
+        let signal_semaphores = [swapchain.render_finished[image_index as usize]];
+        let swapchains = [swapchain.swapchain];
+        let image_indices = [image_index];
+        let present_info = vk::PresentInfoKHR::builder()
+            .wait_semaphores(&signal_semaphores)
+            .swapchains(&swapchains)
+            .image_indices(&image_indices);
+        match self.present_queue.queue_present_khr(self.swapchain_loader.clone(), &present_info) {
+            Ok(suboptimal) => self.outdated |= suboptimal,
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => self.outdated = true,
+            Err(err) => return Err(err.into()),
+        }
+
+        self.frame_index += 1;
+        Ok(true)
+    }
+
+    /// Records the commands for the frame and submits them. The fence of the frame is only reset
+    /// right before the submission.
+    fn submit_frame<F: FnOnce(&FrameContext)>(&self, frame: &FrameResources, swapchain: &SwapchainState, image_index: u32, record: F) -> Result<(), FrameError> {
+        let image = swapchain.images[image_index as usize];
+
+        unsafe {
+            self.device.vk().reset_command_pool(frame.command_pool, vk::CommandPoolResetFlags::empty())?;
+            let begin_info = vk::CommandBufferBeginInfo::builder()
+                .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+            self.device.vk().begin_command_buffer(frame.command_buffer, &begin_info)?;
+        }
+
+        // The previous contents of the image are discarded
+        swapchain::record_acquire_barrier(&self.device, frame.command_buffer, image, &swapchain.image_state);
+
+        record(&FrameContext {
+            device: &self.device,
+            command_buffer: frame.command_buffer,
+            image,
+            image_state: swapchain.image_state,
+            image_view: swapchain.views[image_index as usize],
+            image_index,
+            frame_index: self.frame_index,
+            format: swapchain.format,
+            extent: swapchain.extent,
+        });
+
+        swapchain::record_present_barrier(&self.device, frame.command_buffer, image, &swapchain.image_state);
+        unsafe { self.device.vk().end_command_buffer(frame.command_buffer) }?;
+
+        let wait_semaphores = [frame.image_available];
+        let wait_stages = [swapchain.image_state.stage_mask];
+        let command_buffers = [frame.command_buffer];
+        let signal_semaphores = [swapchain.render_finished[image_index as usize]];
+        let submit_info = vk::SubmitInfo::builder()
+            .wait_semaphores(&wait_semaphores)
+            .wait_dst_stage_mask(&wait_stages)
+            .command_buffers(&command_buffers)
+            .signal_semaphores(&signal_semaphores);
+
+        self.device.reset_fences(std::slice::from_ref(&frame.in_flight))?;
+        self.graphics_queue.queue_submit(self.device.vk().clone(), std::slice::from_ref(&submit_info), frame.in_flight)?;
+        Ok(())
+    }
+
+    /// Restores the synchronization objects of a frame after [`FrameRenderer::submit_frame`]
+    /// failed. Submits an empty batch which consumes the pending signal of the acquire semaphore and
+    /// signals the fence so the next use of the frame does not block forever.
+    fn recover_frame(&self, frame: &FrameResources) -> Result<(), FrameError> {
+        let wait_semaphores = [frame.image_available];
+        let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
+        let submit_info = vk::SubmitInfo::builder()
+            .wait_semaphores(&wait_semaphores)
+            .wait_dst_stage_mask(&wait_stages);
+
+        self.device.reset_fences(std::slice::from_ref(&frame.in_flight))?;
+        self.graphics_queue.queue_submit(self.device.vk().clone(), std::slice::from_ref(&submit_info), frame.in_flight)?;
+        Ok(())
+    }
+
+    /// Creates a new swapchain replacing the current one. Returns false if the surface currently
+    /// has a zero extent.
+    fn recreate_swapchain(&mut self) -> Result<bool, FrameError> {
+        // The old swapchain images may still be in use
+        self.device.wait_idle()?;
+
+        let physical_device = *self.device.get_physical_device();
+        let capabilities = unsafe { self.surface_loader.get_physical_device_surface_capabilities(physical_device, self.surface) }?;
+        let extent = Self::select_extent(&capabilities, self.window_extent);
+        if extent.width == 0 || extent.height == 0 {
+            return Ok(false);
+        }
+
+        let image_state = SwapchainImageState::for_usage(self.image_usage).ok_or(FrameError::NoWritableUsage(self.image_usage))?;
+
+        let formats = unsafe { self.surface_loader.get_physical_device_surface_formats(physical_device, self.surface) }?;
+        let format = self.format_policy.select(&formats).ok_or(FrameError::NoSurfaceFormat)?;
+        let rosella_format = Format::ALL_FORMATS.iter().find(|known| known.get_format() == format.format)
+            .ok_or(FrameError::NoSurfaceFormat)?;
+
+        let mut desc = SwapchainCreateDesc::new(SwapchainImageSpec::make_extent(rosella_format, format.color_space, extent), vk::PresentModeKHR::FIFO)
+            .with_usage(self.image_usage)
+            .with_composite_alpha(SwapchainCreateDesc::select_composite_alpha(capabilities.supported_composite_alpha));
+        desc.pre_transform = capabilities.current_transform;
+        desc.validate_usage(&capabilities).map_err(FrameError::UnsupportedUsage)?;
+
+        let mut image_count = capabilities.min_image_count + 1;
+        if capabilities.max_image_count != 0 {
+            image_count = image_count.min(capabilities.max_image_count);
+        }
+
+        let sharing = SwapchainSharing::new(self.graphics_queue.get_family(), self.present_queue.get_family());
+        let old_swapchain = self.swapchain.as_ref().map(|state| state.swapchain).unwrap_or_else(vk::SwapchainKHR::null);
+        let create_info = desc.make_create_info(self.surface, image_count, &sharing, old_swapchain);
+        let swapchain = unsafe { self.swapchain_loader.create_swapchain(&create_info, None) }?;
+
+        if let Some(old) = self.swapchain.take() {
+            self.destroy_swapchain(old);
+        }
+
+        let mut state = SwapchainState {
+            swapchain,
+            format: format.format,
+            extent,
+            image_state,
+            images: Vec::new(),
+            views: Vec::new(),
+            render_finished: Vec::new(),
+        };
+        if let Err(err) = self.create_image_resources(&mut state) {
+            self.destroy_swapchain(state);
+            return Err(err.into());
+        }
+
+        log::debug!("Created swapchain with {} {:?} images of size {}x{}", state.images.len(), state.format, extent.width, extent.height);
+        self.swapchain = Some(state);
+        self.outdated = false;
+        Ok(true)
+    }
+
+    fn create_image_resources(&self, state: &mut SwapchainState) -> VkResult<()> {
+        state.images = unsafe { self.swapchain_loader.get_swapchain_images(state.swapchain) }?;
+
+        for image in &state.images {
+            let view_info = vk::ImageViewCreateInfo::builder()
+                .image(*image)
+                .view_type(vk::ImageViewType::TYPE_2D)
+                .format(state.format)
+                .subresource_range(vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    base_mip_level: 0,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                });
+            state.views.push(unsafe { self.device.vk().create_image_view(&view_info, None) }?);
+            state.render_finished.push(unsafe { self.device.vk().create_semaphore(&vk::SemaphoreCreateInfo::default(), None) }?);
+        }
+
+        Ok(())
+    }
+
+    fn destroy_swapchain(&self, state: SwapchainState) {
+        unsafe {
+            for semaphore in state.render_finished {
+                self.device.vk().destroy_semaphore(semaphore, None);
+            }
+            for view in state.views {
+                self.device.vk().destroy_image_view(view, None);
+            }
+            self.swapchain_loader.destroy_swapchain(state.swapchain, None);
+        }
+    }
+
+    /// Returns the swapchain extent. The window size is only used if the surface does not define
+    /// its current extent.
+    fn select_extent(capabilities: &vk::SurfaceCapabilitiesKHR, window_extent: vk::Extent2D) -> vk::Extent2D {
+        if capabilities.current_extent.width != u32::MAX {
+            return capabilities.current_extent;
+        }
+
+        vk::Extent2D {
+            width: window_extent.width.clamp(capabilities.min_image_extent.width, capabilities.max_image_extent.width),
+            height: window_extent.height.clamp(capabilities.min_image_extent.height, capabilities.max_image_extent.height),
+        }
+    }
+}
+
+impl Drop for FrameRenderer {
+    fn drop(&mut self) {
+        if let Err(err) = self.device.wait_idle() {
+            log::error!("Failed to wait for device idle before destroying frame resources: {:?}", err);
+        }
+
+        if let Some(state) = self.swapchain.take() {
+            self.destroy_swapchain(state);
+        }
+        let device = self.device.clone();
+        self.frames.iter_mut().for_each(|frame| frame.destroy(&device));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swapchain_extent() {
+        let mut capabilities = vk::SurfaceCapabilitiesKHR {
+            current_extent: vk::Extent2D { width: 800, height: 600 },
+            min_image_extent: vk::Extent2D { width: 1, height: 1 },
+            max_image_extent: vk::Extent2D { width: 4096, height: 4096 },
+            ..Default::default()
+        };
+        let window = vk::Extent2D { width: 1024, height: 8192 };
+
+        assert_eq!(FrameRenderer::select_extent(&capabilities, window), vk::Extent2D { width: 800, height: 600 });
+
+        capabilities.current_extent = vk::Extent2D { width: u32::MAX, height: u32::MAX };
+        assert_eq!(FrameRenderer::select_extent(&capabilities, window), vk::Extent2D { width: 1024, height: 4096 });
+    }
+
+    #[test]
+    fn storage_only_usage() {
+        let state = SwapchainImageState::for_usage(vk::ImageUsageFlags::STORAGE).unwrap();
+        assert_eq!(state.layout, vk::ImageLayout::GENERAL);
+        assert_eq!(state.stage_mask, vk::PipelineStageFlags::COMPUTE_SHADER);
+
+        let image = <vk::Image as vk::Handle>::from_raw(1);
+        assert_eq!(swapchain::make_acquire_barrier(image, &state).new_layout, vk::ImageLayout::GENERAL);
+        assert_eq!(swapchain::make_present_barrier(image, &state).old_layout, vk::ImageLayout::GENERAL);
+
+        let transfer = SwapchainImageState::for_usage(vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::TRANSFER_SRC).unwrap();
+        assert_eq!(transfer.layout, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
+        assert_eq!(SwapchainImageState::for_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::STORAGE), Some(SwapchainImageState::COLOR_ATTACHMENT));
+        assert_eq!(SwapchainImageState::for_usage(vk::ImageUsageFlags::SAMPLED), None);
+    }
+}
@@ -21,6 +21,18 @@ pub enum InitResult {
     Disable,
 }
 
+/// A object that can generate [`ApplicationInstanceFeature`] instances used during the instance
+/// creation process.
+///
+/// A new feature instance is generated every time an instance is created from a registry.
+///
+/// See [`crate::init::instance`] for more information.
+pub trait ApplicationInstanceFeatureGenerator {
+
+    /// Creates a new instance
+    fn make_instance(&self) -> Box<dyn ApplicationInstanceFeature>;
+}
+
 /// A feature that controls instance creation
 ///
 /// See [`crate::init::instance`] for more information.
@@ -24,14 +24,13 @@
 //! already completed processing the respective stage these dependencies must be declared when
 //! registering the feature into the [`InitializationRegistry`].
 
-use std::any::Any;
+use std::any::{Any, TypeId};
 use std::borrow::BorrowMut;
-use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
-use std::rc::Rc;
+use std::ffi::CStr;
 use std::sync::{Arc, Mutex};
 
-use ash::extensions::khr::Swapchain;
+use ash::extensions::khr::{Surface, Swapchain};
 use ash::prelude::VkResult;
 
 use ash::vk;
@@ -41,22 +40,31 @@ use crate::init::initialization_registry::InitializationRegistry;
 use crate::init::utils::{ExtensionProperties, Feature, FeatureProcessor};
 use crate::{NamedUUID, UUID};
 use crate::init::EnabledFeatures;
-use crate::util::extensions::{DeviceExtensionLoader, DeviceExtensionLoaderFn, ExtensionFunctionSet, VkExtensionInfo};
-use crate::rosella::{DeviceContext, InstanceContext, VulkanVersion};
+use crate::util::extensions::{resolve_extension_dependencies, DeviceExtensionLoader, DeviceExtensionLoaderFn, ExtensionFunctionSet, VkExtensionInfo};
+use crate::rosella::{DeviceContext, DriverInfo, InstanceContext, VulkanVersion};
 
 /// Internal implementation of the [`VulkanQueue`] struct
 struct VulkanQueueImpl {
     queue: Mutex<vk::Queue>,
     family: u32,
+    index: u32,
+    capabilities: vk::QueueFlags,
+    physical_device: vk::PhysicalDevice,
+    /// [`None`] if the surface extension is not enabled on the instance
+    surface_loader: Option<Surface>,
 }
 
 /// A wrapper around vulkan queues which provides thread safe access to a queue.
+///
+/// Exactly one instance is created for every queue during device creation. All clones share the
+/// same lock so external synchronization requirements are always upheld as long as the queue is
+/// only accessed through this struct.
 #[derive(Clone)]
 pub struct VulkanQueue(Arc<VulkanQueueImpl>);
 
 impl VulkanQueue {
-    fn new(queue: vk::Queue, family: u32) -> Self {
-        Self(Arc::new(VulkanQueueImpl{ queue: Mutex::new(queue), family }))
+    fn new(queue: vk::Queue, family: u32, index: u32, capabilities: vk::QueueFlags, physical_device: vk::PhysicalDevice, surface_loader: Option<Surface>) -> Self {
+        Self(Arc::new(VulkanQueueImpl{ queue: Mutex::new(queue), family, index, capabilities, physical_device, surface_loader }))
     }
 
     /// Returns the family index of the queue
@@ -64,6 +72,26 @@ impl VulkanQueue {
         self.0.family
     }
 
+    /// Returns the index of the queue within its family
+    pub fn get_queue_index(&self) -> u32 {
+        self.0.index
+    }
+
+    /// Returns the capabilities of the queue family
+    pub fn get_capabilities(&self) -> vk::QueueFlags {
+        self.0.capabilities
+    }
+
+    /// Tests if the queue can present to a surface. Returns false if the query fails or the surface
+    /// extension is not enabled on the instance.
+    pub fn supports_present(&self, surface: vk::SurfaceKHR) -> bool {
+        match &self.0.surface_loader {
+            Some(surface_loader) => unsafe { surface_loader.get_physical_device_surface_support(self.0.physical_device, self.0.family, surface) }
+                .unwrap_or(false),
+            None => false,
+        }
+    }
+
     /// Returns the mutex that protects the queue
     pub fn access_queue(&self) -> &Mutex<vk::Queue> {
         &self.0.queue
@@ -71,16 +99,40 @@ impl VulkanQueue {
 
     /// Performs a thread safe vkQueueSubmit call
     pub fn queue_submit(&self, device: ash::Device, submits: &[vk::SubmitInfo], fence: vk::Fence) -> VkResult<()> {
+        log::trace!("Submitting {} batch(es) to queue {}:{}", submits.len(), self.0.family, self.0.index);
         let guard = self.0.queue.lock().unwrap();
         unsafe { device.queue_submit(*guard, submits, fence) }
     }
 
+    /// Performs a thread safe vkQueueSubmit2KHR call
+    pub fn queue_submit2(&self, synchronization_2: &ash::extensions::khr::Synchronization2, submits: &[vk::SubmitInfo2KHR], fence: vk::Fence) -> VkResult<()> {
+        if log::log_enabled!(log::Level::Trace) {
+            for submit in submits {
+                let waits = unsafe { std::slice::from_raw_parts(submit.p_wait_semaphore_infos, submit.wait_semaphore_info_count as usize) };
+                let signals = unsafe { std::slice::from_raw_parts(submit.p_signal_semaphore_infos, submit.signal_semaphore_info_count as usize) };
+                log::trace!("Submitting to queue {}:{} waiting on {:?} signaling {:?}",
+                    self.0.family, self.0.index,
+                    waits.iter().map(|info| (info.semaphore, info.value)).collect::<Vec<_>>(),
+                    signals.iter().map(|info| (info.semaphore, info.value)).collect::<Vec<_>>(),
+                );
+            }
+        }
+        let guard = self.0.queue.lock().unwrap();
+        unsafe { synchronization_2.queue_submit2(*guard, submits, fence) }
+    }
+
     /// Performs a thread safe vkQueueBindSparse call
     pub fn queue_bind_sparse(&self, device: ash::Device, submits: &[vk::BindSparseInfo], fence: vk::Fence) -> VkResult<()> {
         let guard = self.0.queue.lock().unwrap();
         unsafe { device.queue_bind_sparse(*guard, submits, fence) }
     }
 
+    /// Performs a thread safe vkQueueWaitIdle call
+    pub fn queue_wait_idle(&self, device: &ash::Device) -> VkResult<()> {
+        let guard = self.0.queue.lock().unwrap();
+        unsafe { device.queue_wait_idle(*guard) }
+    }
+
     /// Performs a thread safe vkQueuePresentKHR call
     pub fn queue_present_khr(&self, swapchain: Swapchain, present_info: &vk::PresentInfoKHR) -> VkResult<bool> {
         let guard = self.0.queue.lock().unwrap();
@@ -93,9 +145,16 @@ impl VulkanQueue {
 pub enum DeviceCreateError {
     VulkanError(vk::Result),
     RequiredFeatureNotSupported(NamedUUID),
+    /// Some required features did not end up enabled after the enable pass.
+    RequiredFeaturesNotEnabled(Vec<NamedUUID>),
     Utf8Error(std::str::Utf8Error),
     NulError(std::ffi::NulError),
     ExtensionNotSupported,
+    /// A extension enabled as a dependency of another extension is not supported.
+    ExtensionDependencyNotSupported {
+        extension: String,
+        dependency: String,
+    },
     NoSuitableDeviceFound,
 }
 
@@ -119,12 +178,75 @@ impl From<std::ffi::NulError> for DeviceCreateError {
 
 /// Creates a single new device based on the features declared in the provided registry.
 ///
-/// This function will consume the device features stored in the registry.
+/// A new instance of every feature is generated from the registry so it can be reused.
 ///
 /// All discovered physical devices will be processed and the most suitable device will be selected.
 /// (TODO not implemented yet)
-pub fn create_device(registry: &mut InitializationRegistry, instance: InstanceContext) -> Result<DeviceContext, DeviceCreateError> {
-    let (graph, features) : (Vec<_>, Vec<_>) = registry.take_device_features().into_iter().map(
+pub fn create_device(registry: &InitializationRegistry, instance: InstanceContext) -> Result<DeviceContext, DeviceCreateError> {
+    let mut last_error = None;
+    let mut devices : Vec<_> = make_device_builders(registry, instance)?.into_iter().filter_map(|mut device| {
+        match device.run_selection_passes() {
+            Ok(_) => Some(device),
+            Err(err) => {
+                log::info!("Physical device {:?} is not suitable: {:?}", device.get_device_name(), err);
+                last_error = Some(err);
+                None
+            }
+        }
+    }).collect();
+
+    if devices.is_empty() {
+        return Err(last_error.unwrap_or(DeviceCreateError::NoSuitableDeviceFound));
+    }
+
+    let device = devices.remove(0);
+    log::info!("Selected physical device {:?}", device.get_device_name());
+    let device = device.build()?;
+
+    Ok(device)
+}
+
+/// Creates a new device on the physical device at `index` in the list returned by
+/// [`enumerate_devices`].
+///
+/// Returns [`DeviceCreateError::NoSuitableDeviceFound`] if the index is out of range or the error
+/// that made the physical device unsuitable.
+pub fn create_device_with_index(registry: &InitializationRegistry, instance: InstanceContext, index: usize) -> Result<DeviceContext, DeviceCreateError> {
+    let mut device = make_device_builders(registry, instance)?.into_iter().nth(index)
+        .ok_or(DeviceCreateError::NoSuitableDeviceFound)?;
+
+    device.run_selection_passes()?;
+    log::info!("Selected physical device {:?}", device.get_device_name());
+
+    device.build()
+}
+
+/// Runs the init and enable passes of all features in the provided registry on every physical
+/// device without creating any device.
+///
+/// The returned list is in the order reported by the vulkan implementation and can be used to
+/// select a device for [`create_device_with_index`].
+pub fn enumerate_devices(registry: &InitializationRegistry, instance: InstanceContext) -> Result<Vec<PhysicalDeviceSummary>, DeviceCreateError> {
+    Ok(make_device_builders(registry, instance)?.into_iter().enumerate().map(|(index, mut device)| {
+        let properties = unsafe { device.instance.vk().get_physical_device_properties(device.physical_device) };
+        let result = device.run_selection_passes();
+        let enabled_features = if result.is_ok() { device.get_enabled_features() } else { Vec::new() };
+
+        PhysicalDeviceSummary {
+            index,
+            name: unsafe { CStr::from_ptr(properties.device_name.as_ptr()) }.to_string_lossy().into_owned(),
+            device_type: properties.device_type,
+            driver_version: properties.driver_version,
+            api_version: VulkanVersion::from_raw(properties.api_version),
+            enabled_features,
+            error: result.err(),
+        }
+    }).collect())
+}
+
+/// Generates a [`DeviceBuilder`] with fresh feature instances for every physical device.
+fn make_device_builders(registry: &InitializationRegistry, instance: InstanceContext) -> Result<Vec<DeviceBuilder>, DeviceCreateError> {
+    let (graph, features) : (Vec<_>, Vec<_>) = registry.get_device_features().into_iter().map(
         |(name, dependencies, feature, required)| {
             ((name.clone(), dependencies), (name, feature, required))
         }).unzip();
@@ -143,32 +265,68 @@ pub fn create_device(registry: &mut InitializationRegistry, instance: InstanceCo
         .collect();
 
     let devices = unsafe { instance.vk().enumerate_physical_devices() }?;
-    let devices : Vec<_> = devices.into_iter().map(|device| {
+    Ok(devices.into_iter().map(|device| {
         let feature_instances : Vec<_> = features.iter().map(
             |(name, feature, required)| {
                 (name.clone(), feature.make_instance(), *required)
             }).collect();
 
         DeviceBuilder::new(instance.clone(), device, ordering.clone().into_boxed_slice(), feature_instances)
-    }).collect();
+    }).collect())
+}
 
-    let mut devices : Vec<_> = devices.into_iter().filter_map(|mut device| {
-        if device.run_init_pass().is_err() {
-            return None;
-        }
-        if device.run_enable_pass().is_err() {
-            return None;
-        }
-        Some(device)
-    }).collect();
+/// Information about a physical device collected by [`enumerate_devices`].
+#[derive(Debug)]
+pub struct PhysicalDeviceSummary {
+    index: usize,
+    name: String,
+    device_type: vk::PhysicalDeviceType,
+    driver_version: u32,
+    api_version: VulkanVersion,
+    enabled_features: Vec<NamedUUID>,
+    error: Option<DeviceCreateError>,
+}
 
-    if devices.is_empty() {
-        return Err(DeviceCreateError::NoSuitableDeviceFound);
+impl PhysicalDeviceSummary {
+    /// Returns the index to be passed to [`create_device_with_index`]
+    pub fn get_index(&self) -> usize {
+        self.index
     }
 
-    let device = devices.remove(0).build()?;
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
 
-    Ok(device)
+    pub fn get_device_type(&self) -> vk::PhysicalDeviceType {
+        self.device_type
+    }
+
+    /// Returns the vendor specific driver version
+    pub fn get_driver_version(&self) -> u32 {
+        self.driver_version
+    }
+
+    /// Returns the vulkan version supported by the device
+    pub fn get_api_version(&self) -> VulkanVersion {
+        self.api_version
+    }
+
+    /// Returns all features that would be enabled on this device.
+    ///
+    /// Empty if the device is not suitable.
+    pub fn get_enabled_features(&self) -> &[NamedUUID] {
+        &self.enabled_features
+    }
+
+    /// Returns true if all required features of the registry are supported by this device
+    pub fn is_suitable(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Returns the reason the device is not suitable if any
+    pub fn get_error(&self) -> Option<&DeviceCreateError> {
+        self.error.as_ref()
+    }
 }
 
 /// Represents the current state of some feature in the device initialization process
@@ -248,6 +406,13 @@ impl DeviceBuilder {
         }
     }
 
+    /// Returns the name of the physical device or an empty string if the init pass has not run yet.
+    fn get_device_name(&self) -> String {
+        self.info.as_ref().map(|info| {
+            unsafe { CStr::from_ptr(info.get_device_1_0_properties().device_name.as_ptr()) }.to_string_lossy().into_owned()
+        }).unwrap_or_default()
+    }
+
     /// Runs the init pass.
     ///
     /// First collects information about the capabilities of the physical device and then calls
@@ -260,6 +425,12 @@ impl DeviceBuilder {
         }
         self.info = Some(DeviceInfo::new(self.instance.clone(), self.physical_device)?);
         let info = self.info.as_ref().unwrap();
+        log::debug!("Processing physical device {:?} ({:?}) with effective vulkan version {}.{}",
+            self.get_device_name(),
+            info.get_device_1_0_properties().device_type,
+            info.get_effective_version().get_major(),
+            info.get_effective_version().get_minor()
+        );
 
         self.processor.run_pass::<DeviceCreateError, _>(
             DeviceFeatureState::Initialized,
@@ -297,11 +468,11 @@ impl DeviceBuilder {
         if self.config.is_some() {
             panic!("Called run enable pass but config is already some");
         }
-        self.config = Some(DeviceConfigurator::new());
-        let config = self.config.as_mut().unwrap();
-
         let info = self.info.as_ref().expect("Called run enable pass but info is none");
 
+        self.config = Some(DeviceConfigurator::new(info.get_effective_version()));
+        let config = self.config.as_mut().unwrap();
+
         self.processor.run_pass::<DeviceCreateError, _>(
             DeviceFeatureState::Enabled,
             |feature, access| {
@@ -313,6 +484,7 @@ impl DeviceBuilder {
                 }
                 feature.feature.enable(access, info, config);
                 feature.state = DeviceFeatureState::Enabled;
+                log::debug!("Enabled feature {:?}", feature.name);
                 Ok(())
             }
         )?;
@@ -320,6 +492,52 @@ impl DeviceBuilder {
         Ok(())
     }
 
+    /// Runs the init and enable passes and verifies that all required features have been enabled.
+    fn run_selection_passes(&mut self) -> Result<(), DeviceCreateError> {
+        self.run_init_pass()
+            .and_then(|_| self.run_enable_pass())
+            .and_then(|_| self.verify_required_features())
+    }
+
+    /// Returns the names of all features in the enabled state.
+    fn get_enabled_features(&mut self) -> Vec<NamedUUID> {
+        let mut enabled = Vec::new();
+        self.processor.run_pass::<DeviceCreateError, _>(
+            DeviceFeatureState::Enabled,
+            |feature, _| {
+                if feature.state == DeviceFeatureState::Enabled {
+                    enabled.push(feature.name.clone());
+                }
+                Ok(())
+            }
+        ).unwrap();
+
+        enabled
+    }
+
+    /// Verifies that all required features have been enabled.
+    ///
+    /// Returns a list of all required features which are not in the enabled state.
+    fn verify_required_features(&mut self) -> Result<(), DeviceCreateError> {
+        let mut missing = Vec::new();
+        self.processor.run_pass::<DeviceCreateError, _>(
+            DeviceFeatureState::Enabled,
+            |feature, _| {
+                if feature.required && feature.state != DeviceFeatureState::Enabled {
+                    log::warn!("Required feature {:?} is not enabled", feature.name);
+                    missing.push(feature.name.clone());
+                }
+                Ok(())
+            }
+        )?;
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(DeviceCreateError::RequiredFeaturesNotEnabled(missing))
+        }
+    }
+
     /// Creates the vulkan device
     fn build(self) -> Result<DeviceContext, DeviceCreateError> {
         let instance = self.instance;
@@ -330,10 +548,14 @@ impl DeviceBuilder {
 
         let features = EnabledFeatures::new(self.processor.into_iter().filter_map(
             |mut info| {
+                if info.state != DeviceFeatureState::Enabled {
+                    return None;
+                }
                 Some((info.name.get_uuid(), info.feature.as_mut().finish(&instance, &device, &function_set)))
             }));
 
-        Ok(DeviceContext::new(instance, device, self.physical_device, function_set, features))
+        let driver_info = DriverInfo::from_device_info(&info);
+        Ok(DeviceContext::new(instance, device, self.physical_device, info.get_effective_version(), driver_info, function_set, features))
     }
 }
 
@@ -345,7 +567,7 @@ pub struct QueueFamilyInfo {
 
 impl QueueFamilyInfo {
     /// Collects information from a VK1.0 vkQueueFamilyProperties struct
-    fn new(index: u32, properties: vk::QueueFamilyProperties) -> Self {
+    pub(crate) fn new(index: u32, properties: vk::QueueFamilyProperties) -> Self {
         Self {
             index,
             properties,
@@ -377,6 +599,7 @@ impl QueueFamilyInfo {
 pub struct DeviceInfo {
     instance: InstanceContext,
     physical_device: vk::PhysicalDevice,
+    effective_version: VulkanVersion,
     features_1_0: vk::PhysicalDeviceFeatures,
     features_1_1: Option<vk::PhysicalDeviceVulkan11Features>,
     features_1_2: Option<vk::PhysicalDeviceVulkan12Features>,
@@ -387,6 +610,8 @@ pub struct DeviceInfo {
 
     /// Temporary hack until extension feature management is implemented
     timeline_semaphore_features: Option<vk::PhysicalDeviceTimelineSemaphoreFeatures>,
+    /// Additional feature structs that were queried through VkPhysicalDeviceFeatures2
+    feature_structs: Vec<(TypeId, Box<dyn Any>)>,
     queue_families: Box<[QueueFamilyInfo]>,
     extensions: HashMap<UUID, ExtensionProperties>,
 }
@@ -404,14 +629,33 @@ impl DeviceInfo {
         let memory_properties_1_0;
 
         let mut timeline_semaphore = None;
+        let mut descriptor_indexing = None;
+        let mut acceleration_structure = None;
+        let mut ray_tracing_pipeline = None;
+        let mut dynamic_rendering = None;
+        let mut synchronization_2 = None;
 
         let queue_families;
 
-        let vk_1_1 = instance.get_version().is_supported(VulkanVersion::VK_1_1);
-        let vk_1_2 = instance.get_version().is_supported(VulkanVersion::VK_1_2);
+        let extensions_raw = unsafe { instance.vk().enumerate_device_extension_properties(physical_device) }?;
+        let mut extensions = HashMap::new();
+        for extension in extensions_raw {
+            let extension = ExtensionProperties::new(&extension)?;
+            let uuid = NamedUUID::uuid_for(extension.get_name().as_str());
+
+            extensions.insert(uuid, extension);
+        }
+
+        let device_version = VulkanVersion::from_raw(unsafe { instance.vk().get_physical_device_properties(physical_device) }.api_version);
+        let effective_version = std::cmp::min(instance.get_version(), device_version);
+
+        // The core vkGetPhysicalDevice*2 functions only depend on the instance version
+        let vk_1_1_instance = instance.get_version().is_supported(VulkanVersion::VK_1_1);
+        let vk_1_1 = effective_version.is_supported(VulkanVersion::VK_1_1);
+        let vk_1_2 = effective_version.is_supported(VulkanVersion::VK_1_2);
         let get_physical_device_properties_2 = instance.get_extension::<ash::extensions::khr::GetPhysicalDeviceProperties2>();
 
-        if vk_1_1 || get_physical_device_properties_2.is_some() {
+        if vk_1_1_instance || get_physical_device_properties_2.is_some() {
             // Use the newer VK_KHR_get_physical_device_properties2 functions
             let mut features2 = vk::PhysicalDeviceFeatures2::builder();
             let mut properties2 = vk::PhysicalDeviceProperties2::builder();
@@ -438,7 +682,34 @@ impl DeviceInfo {
                 features2 = features2.push_next(timeline_semaphore.as_mut().unwrap());
             }
 
-            if vk_1_1 {
+            let extension_supported = |name: &str| extensions.contains_key(&NamedUUID::uuid_for(name));
+
+            if vk_1_2 || extension_supported("VK_EXT_descriptor_indexing") {
+                descriptor_indexing = Some(vk::PhysicalDeviceDescriptorIndexingFeatures::default());
+                features2 = features2.push_next(descriptor_indexing.as_mut().unwrap());
+            }
+
+            if extension_supported("VK_KHR_acceleration_structure") {
+                acceleration_structure = Some(vk::PhysicalDeviceAccelerationStructureFeaturesKHR::default());
+                features2 = features2.push_next(acceleration_structure.as_mut().unwrap());
+            }
+
+            if extension_supported("VK_KHR_ray_tracing_pipeline") {
+                ray_tracing_pipeline = Some(vk::PhysicalDeviceRayTracingPipelineFeaturesKHR::default());
+                features2 = features2.push_next(ray_tracing_pipeline.as_mut().unwrap());
+            }
+
+            if extension_supported("VK_KHR_dynamic_rendering") {
+                dynamic_rendering = Some(vk::PhysicalDeviceDynamicRenderingFeaturesKHR::default());
+                features2 = features2.push_next(dynamic_rendering.as_mut().unwrap());
+            }
+
+            if extension_supported("VK_KHR_synchronization2") {
+                synchronization_2 = Some(vk::PhysicalDeviceSynchronization2FeaturesKHR::default());
+                features2 = features2.push_next(synchronization_2.as_mut().unwrap());
+            }
+
+            if vk_1_1_instance {
                 unsafe { instance.vk().get_physical_device_features2(physical_device, &mut features2) };
             } else {
                 unsafe { get_physical_device_properties_2.unwrap().get_physical_device_features2(physical_device, features2.borrow_mut()) };
@@ -446,7 +717,7 @@ impl DeviceInfo {
             features_1_0 = Some(features2.features);
             drop(features2); // Get rid of mut references
 
-            if vk_1_1 {
+            if vk_1_1_instance {
                 unsafe { instance.vk().get_physical_device_properties2(physical_device, &mut properties2) };
             } else {
                 unsafe { get_physical_device_properties_2.unwrap().get_physical_device_properties2(physical_device, properties2.borrow_mut()) };
@@ -454,7 +725,7 @@ impl DeviceInfo {
             properties_1_0 = Some(properties2.properties);
             drop(properties2); // Get rid of mut references
 
-            if vk_1_1 {
+            if vk_1_1_instance {
                 unsafe { instance.vk().get_physical_device_memory_properties2(physical_device, &mut memory_properties2) };
             } else {
                 unsafe { get_physical_device_properties_2.unwrap().get_physical_device_memory_properties2(physical_device, memory_properties2.borrow_mut()) };
@@ -464,7 +735,7 @@ impl DeviceInfo {
 
 
             let mut queue_properties2 = Vec::new();
-            if vk_1_1 {
+            if vk_1_1_instance {
                 let count = unsafe { instance.vk().get_physical_device_queue_family_properties2_len(physical_device) };
 
                 queue_properties2.resize(count, vk::QueueFamilyProperties2::default());
@@ -499,18 +770,27 @@ impl DeviceInfo {
                 .into_boxed_slice());
         }
 
-        let extensions_raw = unsafe { instance.vk().enumerate_device_extension_properties(physical_device) }?;
-        let mut extensions = HashMap::new();
-        for extension in extensions_raw {
-            let extension = ExtensionProperties::new(&extension)?;
-            let uuid = NamedUUID::uuid_for(extension.get_name().as_str());
-
-            extensions.insert(uuid, extension);
+        let mut feature_structs: Vec<(TypeId, Box<dyn Any>)> = Vec::new();
+        if let Some(features) = descriptor_indexing {
+            feature_structs.push((TypeId::of::<vk::PhysicalDeviceDescriptorIndexingFeatures>(), Box::new(features)));
+        }
+        if let Some(features) = acceleration_structure {
+            feature_structs.push((TypeId::of::<vk::PhysicalDeviceAccelerationStructureFeaturesKHR>(), Box::new(features)));
+        }
+        if let Some(features) = ray_tracing_pipeline {
+            feature_structs.push((TypeId::of::<vk::PhysicalDeviceRayTracingPipelineFeaturesKHR>(), Box::new(features)));
+        }
+        if let Some(features) = dynamic_rendering {
+            feature_structs.push((TypeId::of::<vk::PhysicalDeviceDynamicRenderingFeaturesKHR>(), Box::new(features)));
+        }
+        if let Some(features) = synchronization_2 {
+            feature_structs.push((TypeId::of::<vk::PhysicalDeviceSynchronization2FeaturesKHR>(), Box::new(features)));
         }
 
         Ok(Self {
             instance,
             physical_device,
+            effective_version,
             features_1_0: features_1_0.unwrap(),
             features_1_1,
             features_1_2,
@@ -519,6 +799,7 @@ impl DeviceInfo {
             properties_1_2,
             memory_properties_1_0: memory_properties_1_0.unwrap(),
             timeline_semaphore_features: timeline_semaphore,
+            feature_structs,
             queue_families: queue_families.unwrap(),
             extensions,
         })
@@ -534,10 +815,33 @@ impl DeviceInfo {
         &self.physical_device
     }
 
+    /// Returns the vulkan version usable with the device. This is the lower of the instance version
+    /// and the api version of the physical device.
+    pub fn get_effective_version(&self) -> VulkanVersion {
+        self.effective_version
+    }
+
+    /// Returns the vulkan 1.0 features supported by the physical device
+    pub fn get_features(&self) -> &vk::PhysicalDeviceFeatures {
+        &self.features_1_0
+    }
+
     pub fn get_device_1_0_features(&self) -> &vk::PhysicalDeviceFeatures {
         &self.features_1_0
     }
 
+    /// Returns a feature struct that was queried through VkPhysicalDeviceFeatures2.
+    ///
+    /// Currently the descriptor indexing, acceleration structure, ray tracing pipeline, dynamic
+    /// rendering and synchronization2 feature structs are queried if the physical device supports
+    /// them. Returns [`None`] for any other struct or if the struct is not supported.
+    pub fn get_feature_struct<T: vk::ExtendsPhysicalDeviceFeatures2 + 'static>(&self) -> Option<&T> {
+        let type_id = TypeId::of::<T>();
+        self.feature_structs.iter()
+            .find(|(id, _)| *id == type_id)
+            .and_then(|(_, features)| features.downcast_ref())
+    }
+
     pub fn get_device_1_1_features(&self) -> Option<&vk::PhysicalDeviceVulkan11Features> {
         self.features_1_1.as_ref()
     }
@@ -582,6 +886,13 @@ impl DeviceInfo {
         self.extensions.contains_key(&uuid)
     }
 
+    /// Queries if all dependencies of a device extension which are not part of the effective vulkan
+    /// version are supported
+    pub fn are_extension_dependencies_supported<T: VkExtensionInfo>(&self) -> bool {
+        resolve_extension_dependencies(T::UUID.get_name(), self.effective_version).iter()
+            .all(|(dependency, _)| self.is_extension_supported_str(dependency))
+    }
+
     /// Queries if a device extension is supported
     pub fn is_extension_supported_uuid(&self, uuid: &UUID) -> bool {
         self.extensions.contains_key(uuid)
@@ -618,7 +929,7 @@ struct QueueRequestImpl {
 impl QueueRequestImpl {
     /// Generates a new queue request for a specific family
     fn new(family: u32) -> (QueueRequest, QueueRequestResolver) {
-        let cell = Rc::new(RefCell::new(QueueRequestImpl{ result: None }));
+        let cell = Arc::new(Mutex::new(QueueRequestImpl{ result: None }));
         (QueueRequest(cell.clone()), QueueRequestResolver{ request: cell, family, index: None })
     }
 }
@@ -627,7 +938,8 @@ impl QueueRequestImpl {
 ///
 /// During the enable pass features may request queues. A [`QueueRequest`] will be returned in such
 /// a case. [`QueueRequests`] can be accessed to retrieve a [`VulkanQueue`] during the finish pass.
-pub struct QueueRequest(Rc<RefCell<QueueRequestImpl>>);
+/// Requests can be stored inside of features.
+pub struct QueueRequest(Arc<Mutex<QueueRequestImpl>>);
 
 impl QueueRequest {
     /// Returns the [`VulkanQueue`] to fulfill this request.
@@ -636,12 +948,12 @@ impl QueueRequest {
     /// Will panic if the request has not yet been resolved. Or in other words if this function is
     /// called before the finish pass.
     pub fn get(&self) -> VulkanQueue {
-        self.0.borrow().result.as_ref().unwrap().clone()
+        self.0.lock().unwrap().result.as_ref().unwrap().clone()
     }
 }
 
 struct QueueRequestResolver {
-    request: Rc<RefCell<QueueRequestImpl>>,
+    request: Arc<Mutex<QueueRequestImpl>>,
     family: u32,
     index: Option<u32>,
 }
@@ -649,7 +961,7 @@ struct QueueRequestResolver {
 impl QueueRequestResolver {
     /// Resolves the queue request
     fn resolve(&mut self, queue: VulkanQueue) {
-        (*self.request).borrow_mut().result = Some(queue);
+        self.request.lock().unwrap().result = Some(queue);
     }
 
     fn get_family(&self) -> u32 {
@@ -659,25 +971,42 @@ impl QueueRequestResolver {
 
 pub struct DeviceConfigurator {
     enabled_extensions: HashMap<UUID, Option<&'static DeviceExtensionLoaderFn>>,
+    /// Maps extensions enabled as a dependency to the (dependency, extension) names that required them
+    dependency_sources: HashMap<UUID, (&'static str, String)>,
+    /// The effective vulkan version of the device. Dependencies which are core in this version are not enabled
+    version: VulkanVersion,
     queue_requests: Vec<QueueRequestResolver>,
 
-    /// Temporary hack until extension feature management is implemented
-    enable_timeline_semaphores: bool,
+    features_1_0: vk::PhysicalDeviceFeatures,
+    /// Feature structs chained into a VkPhysicalDeviceFeatures2. The pointer points into the box.
+    feature_structs: Vec<(TypeId, Box<dyn Any>, *mut vk::BaseOutStructure)>,
 }
 
 impl DeviceConfigurator {
-    fn new() -> Self {
+    fn new(version: VulkanVersion) -> Self {
         Self{
             enabled_extensions: HashMap::new(),
+            dependency_sources: HashMap::new(),
+            version,
             queue_requests: Vec::new(),
-            enable_timeline_semaphores: false,
+            features_1_0: vk::PhysicalDeviceFeatures::default(),
+            feature_structs: Vec::new(),
         }
     }
 
     /// Enables a device extension and registers the extension for automatic function loading
+    ///
+    /// Any dependencies of the extension which are not part of the effective vulkan version of the
+    /// device will be enabled as well.
     pub fn enable_extension<EXT: VkExtensionInfo + DeviceExtensionLoader + 'static>(&mut self) {
         let uuid = EXT::UUID.get_uuid();
         self.enabled_extensions.insert(uuid, Some(&EXT::load_extension));
+
+        for (dependency, source) in resolve_extension_dependencies(EXT::UUID.get_name(), self.version) {
+            self.enable_extension_str_no_load(dependency);
+            self.dependency_sources.entry(NamedUUID::uuid_for(dependency))
+                .or_insert_with(|| (dependency, source.to_string()));
+        }
     }
 
     /// Enables a device extension without automatic function loading
@@ -697,9 +1026,29 @@ impl DeviceConfigurator {
         request
     }
 
-    /// Temporary hack until extension feature management is implemented
-    pub fn enable_timeline_semaphore(&mut self) {
-        self.enable_timeline_semaphores = true;
+    /// Returns the vulkan 1.0 features which will be enabled on the device
+    pub fn features_1_0_mut(&mut self) -> &mut vk::PhysicalDeviceFeatures {
+        &mut self.features_1_0
+    }
+
+    /// Returns a feature struct which will be chained into the VkPhysicalDeviceFeatures2 used to
+    /// create the device. If no struct of this type has been requested yet a default one is
+    /// inserted, so multiple features can enable flags in the same struct.
+    ///
+    /// Chained feature structs require vulkan 1.1 or VK_KHR_get_physical_device_properties2.
+    pub fn feature_struct_mut<T: vk::ExtendsPhysicalDeviceFeatures2 + Default + 'static>(&mut self) -> &mut T {
+        let type_id = TypeId::of::<T>();
+        let index = match self.feature_structs.iter().position(|(id, _, _)| *id == type_id) {
+            Some(index) => index,
+            None => {
+                let mut feature_struct = Box::<T>::default();
+                let ptr = feature_struct.as_mut() as *mut T as *mut vk::BaseOutStructure;
+                self.feature_structs.push((type_id, feature_struct, ptr));
+                self.feature_structs.len() - 1
+            }
+        };
+
+        self.feature_structs[index].1.downcast_mut().unwrap()
     }
 
     /// Generates queue assignments to fulfill requests
@@ -711,7 +1060,7 @@ impl DeviceConfigurator {
         families.resize_with(info.get_queue_family_infos().len(), || 0u32);
 
         for request in &mut self.queue_requests {
-            *families.get_mut(request.get_family() as usize).unwrap() += 1u32;
+            *families.get_mut(request.get_family() as usize).unwrap() = 1u32;
             request.index = Some(0);
         }
 
@@ -726,14 +1075,23 @@ impl DeviceConfigurator {
     fn build_device(mut self, info: &DeviceInfo) -> Result<(ash::Device, ExtensionFunctionSet), DeviceCreateError> {
         let mut extensions = Vec::with_capacity(self.enabled_extensions.len());
         for (uuid, _) in &self.enabled_extensions {
-            extensions.push(
-                info.get_extension_properties_uuid(uuid)
-                    .ok_or(DeviceCreateError::ExtensionNotSupported)?
-                    .get_c_name().as_ptr()
-            )
+            let extension = info.get_extension_properties_uuid(uuid).ok_or_else(|| {
+                match self.dependency_sources.get(uuid) {
+                    Some((dependency, extension)) => DeviceCreateError::ExtensionDependencyNotSupported {
+                        extension: extension.clone(),
+                        dependency: dependency.to_string(),
+                    },
+                    None => DeviceCreateError::ExtensionNotSupported,
+                }
+            })?;
+            log::debug!("Enabling device extension \"{}\"", extension.get_name());
+            extensions.push(extension.get_c_name().as_ptr())
         }
 
         let queue_assignments = self.generate_queue_assignments(info);
+        for (family, priorities) in queue_assignments.iter() {
+            log::debug!("Creating {} queue(s) in family {}", priorities.len(), family);
+        }
         let mut queue_create_infos = Vec::with_capacity(queue_assignments.len());
         for (family, priorities) in queue_assignments.iter() {
             let create_info = vk::DeviceQueueCreateInfo::builder()
@@ -746,31 +1104,39 @@ impl DeviceConfigurator {
             .enabled_extension_names(extensions.as_slice())
             .queue_create_infos(queue_create_infos.as_slice());
 
-        // Temporary hack until extension feature management is implemented
-        let mut timeline_semaphore_info;
-        if self.enable_timeline_semaphores {
-            timeline_semaphore_info = vk::PhysicalDeviceTimelineSemaphoreFeatures::builder()
-                .timeline_semaphore(true);
-            create_info = create_info.push_next(&mut timeline_semaphore_info);
+        let mut features2 = vk::PhysicalDeviceFeatures2::builder()
+            .features(self.features_1_0);
+        if self.feature_structs.is_empty() {
+            create_info = create_info.enabled_features(&self.features_1_0);
+        } else {
+            for (_, _, next) in &self.feature_structs {
+                // All vulkan structs start with sType and pNext
+                unsafe { (**next).p_next = features2.p_next as *mut vk::BaseOutStructure };
+                features2.p_next = *next as *mut std::ffi::c_void;
+            }
+            create_info = create_info.push_next(&mut features2);
         }
 
         let device = unsafe {
             info.get_instance().vk().create_device(info.physical_device, &create_info, None)
         }?;
 
+        let surface_loader = info.get_instance().get_extension::<Surface>().cloned();
         let mut queues = Vec::with_capacity(queue_assignments.len());
         for (family, priorities) in queue_assignments.iter() {
+            let capabilities = info.get_queue_family_infos()[*family as usize].get_properties().queue_flags;
             let mut family_queues = Vec::with_capacity(priorities.len());
             for i in 0u32..(priorities.len() as u32) {
                 let queue = unsafe { device.get_device_queue(*family, i) };
-                family_queues.push(VulkanQueue::new(queue, *family));
+                family_queues.push(VulkanQueue::new(queue, *family, i, capabilities, info.physical_device, surface_loader.clone()));
             }
             queues.push(family_queues);
         }
         let queues = queues;
 
         for request in &mut self.queue_requests {
-            request.resolve(queues.get(request.family as usize).unwrap().get(request.index.unwrap() as usize).unwrap().clone());
+            let assignment = queue_assignments.iter().position(|(family, _)| *family == request.family).unwrap();
+            request.resolve(queues.get(assignment).unwrap().get(request.index.unwrap() as usize).unwrap().clone());
         }
 
         let mut function_set = ExtensionFunctionSet::new();
@@ -782,4 +1148,58 @@ impl DeviceConfigurator {
 
         Ok((device, function_set))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extension_dependencies() {
+        let mut config = DeviceConfigurator::new(VulkanVersion::VK_1_0);
+        config.enable_extension::<ash::extensions::khr::RayTracingPipeline>();
+
+        assert!(config.enabled_extensions.get(&ash::extensions::khr::RayTracingPipeline::UUID.get_uuid()).unwrap().is_some());
+        for name in ["VK_KHR_acceleration_structure", "VK_KHR_spirv_1_4"] {
+            assert!(config.enabled_extensions.contains_key(&NamedUUID::uuid_for(name)));
+        }
+
+        // Enabling the dependency explicitly must register its loader and pull in its own dependencies
+        config.enable_extension::<ash::extensions::khr::AccelerationStructure>();
+        assert!(config.enabled_extensions.get(&ash::extensions::khr::AccelerationStructure::UUID.get_uuid()).unwrap().is_some());
+        assert!(config.enabled_extensions.contains_key(&NamedUUID::uuid_for("VK_KHR_deferred_host_operations")));
+
+        // Dependencies of dependencies are enabled as well
+        let (dependency, source) = config.dependency_sources.get(&NamedUUID::uuid_for("VK_KHR_shader_float_controls")).unwrap();
+        assert_eq!(*dependency, "VK_KHR_shader_float_controls");
+        assert_eq!(source, "VK_KHR_spirv_1_4");
+        assert!(config.enabled_extensions.contains_key(&NamedUUID::uuid_for("VK_KHR_maintenance3")));
+    }
+
+    #[test]
+    fn core_extension_dependencies() {
+        let mut config = DeviceConfigurator::new(VulkanVersion::VK_1_2);
+        config.enable_extension::<ash::extensions::khr::DynamicRendering>();
+        assert_eq!(config.enabled_extensions.len(), 1);
+        assert!(config.dependency_sources.is_empty());
+
+        let mut config = DeviceConfigurator::new(VulkanVersion::VK_1_1);
+        config.enable_extension::<ash::extensions::khr::DynamicRendering>();
+        assert!(config.enabled_extensions.contains_key(&NamedUUID::uuid_for("VK_KHR_create_renderpass2")));
+        assert!(!config.enabled_extensions.contains_key(&NamedUUID::uuid_for("VK_KHR_multiview")));
+    }
+
+    #[test]
+    fn feature_structs() {
+        let mut config = DeviceConfigurator::new(VulkanVersion::VK_1_0);
+        config.feature_struct_mut::<vk::PhysicalDeviceTimelineSemaphoreFeatures>().timeline_semaphore = vk::TRUE;
+        config.feature_struct_mut::<vk::PhysicalDeviceDescriptorIndexingFeatures>().runtime_descriptor_array = vk::TRUE;
+
+        // Requesting the same struct again must return the existing one
+        assert_eq!(config.feature_struct_mut::<vk::PhysicalDeviceTimelineSemaphoreFeatures>().timeline_semaphore, vk::TRUE);
+        assert_eq!(config.feature_structs.len(), 2);
+
+        let (_, _, ptr) = config.feature_structs[0];
+        assert_eq!(unsafe { (*ptr).s_type }, vk::StructureType::PHYSICAL_DEVICE_TIMELINE_SEMAPHORE_FEATURES);
+    }
+}
@@ -1,15 +1,20 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use crate::init::application_feature::{ApplicationDeviceFeatureGenerator, ApplicationInstanceFeature};
+use crate::init::application_feature::{ApplicationDeviceFeatureGenerator, ApplicationInstanceFeature, ApplicationInstanceFeatureGenerator};
 
 use crate::{ NamedUUID, UUID };
 
 ///
 /// A class used to collect any callbacks and settings that are used for device and instance initialization.
 ///
+/// Features are registered as generators, so a registry can be used (or cloned) to create multiple
+/// instances and devices. Each creation generates fresh feature instances.
+///
+#[derive(Clone)]
 pub struct InitializationRegistry {
-    instance_features: HashMap<UUID, (NamedUUID, Box<[NamedUUID]>, Box<dyn ApplicationInstanceFeature>, bool)>,
-    device_features: HashMap<UUID, (NamedUUID, Box<[NamedUUID]>, Box<dyn ApplicationDeviceFeatureGenerator>, bool)>,
+    instance_features: HashMap<UUID, (NamedUUID, Box<[NamedUUID]>, Arc<dyn ApplicationInstanceFeatureGenerator>, bool)>,
+    device_features: HashMap<UUID, (NamedUUID, Box<[NamedUUID]>, Arc<dyn ApplicationDeviceFeatureGenerator>, bool)>,
 }
 
 impl InitializationRegistry {
@@ -20,26 +25,27 @@ impl InitializationRegistry {
         }
     }
 
-    pub fn register_instance_feature(&mut self, name: NamedUUID, dependencies: Box<[NamedUUID]>, feature: Box<dyn ApplicationInstanceFeature>, required: bool) {
-        if self.instance_features.insert(name.get_uuid(), (name, dependencies, feature, required)).is_some() {
+    pub fn register_instance_feature(&mut self, name: NamedUUID, dependencies: Box<[NamedUUID]>, feature: Box<dyn ApplicationInstanceFeatureGenerator>, required: bool) {
+        if self.instance_features.insert(name.get_uuid(), (name, dependencies, Arc::from(feature), required)).is_some() {
             panic!("Feature is already present in registry");
         }
     }
 
     pub fn register_device_feature(&mut self, name: NamedUUID, dependencies: Box<[NamedUUID]>, feature: Box<dyn ApplicationDeviceFeatureGenerator>, required: bool) {
-        if self.device_features.insert(name.get_uuid(), (name, dependencies, feature, required)).is_some() {
+        if self.device_features.insert(name.get_uuid(), (name, dependencies, Arc::from(feature), required)).is_some() {
             panic!("Feature is already present in registry");
         }
     }
 
-    pub(super) fn take_instance_features(&mut self) -> Vec<(NamedUUID, Box<[NamedUUID]>, Box<dyn ApplicationInstanceFeature>, bool)> {
-        let features = std::mem::replace(&mut self.instance_features, HashMap::new());
-        features.into_values().collect()
+    /// Generates new instances of all registered instance features
+    pub(super) fn make_instance_features(&self) -> Vec<(NamedUUID, Box<[NamedUUID]>, Box<dyn ApplicationInstanceFeature>, bool)> {
+        self.instance_features.values().map(|(name, dependencies, generator, required)| {
+            (name.clone(), dependencies.clone(), generator.make_instance(), *required)
+        }).collect()
     }
 
-    pub(super) fn take_device_features(&mut self) -> Vec<(NamedUUID, Box<[NamedUUID]>, Box<dyn ApplicationDeviceFeatureGenerator>, bool)> {
-        let features = std::mem::replace(&mut self.device_features, HashMap::new());
-        features.into_values().collect()
+    pub(super) fn get_device_features(&self) -> Vec<(NamedUUID, Box<[NamedUUID]>, Arc<dyn ApplicationDeviceFeatureGenerator>, bool)> {
+        self.device_features.values().cloned().collect()
     }
 }
 
@@ -47,4 +53,20 @@ impl Default for InitializationRegistry {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cloned_registry_generates_features() {
+        let mut registry = InitializationRegistry::new();
+        crate::init::rosella_features::register_rosella_headless(&mut registry);
+
+        let cloned = registry.clone();
+        assert!(!registry.make_instance_features().is_empty());
+        assert_eq!(registry.make_instance_features().len(), cloned.make_instance_features().len());
+        assert_eq!(registry.get_device_features().len(), cloned.get_device_features().len());
+    }
+}
@@ -33,7 +33,7 @@ use crate::init::utils::{ExtensionProperties, Feature, FeatureProcessor, LayerPr
 use ash::vk;
 use ash::vk::{DebugUtilsMessageSeverityFlagsEXT, DebugUtilsMessageTypeFlagsEXT};
 use crate::init::EnabledFeatures;
-use crate::util::extensions::{ExtensionFunctionSet, InstanceExtensionLoader, InstanceExtensionLoaderFn, VkExtensionInfo};
+use crate::util::extensions::{resolve_extension_dependencies, ExtensionFunctionSet, InstanceExtensionLoader, InstanceExtensionLoaderFn, VkExtensionInfo};
 use crate::rosella::{InstanceContext, VulkanVersion};
 
 /// An error that may occur during the instance initialization process.
@@ -45,6 +45,11 @@ pub enum InstanceCreateError {
     RequiredFeatureNotSupported(NamedUUID),
     LayerNotSupported,
     ExtensionNotSupported,
+    /// A extension enabled as a dependency of another extension is not supported.
+    ExtensionDependencyNotSupported {
+        extension: String,
+        dependency: String,
+    },
 }
 
 impl From<vk::Result> for InstanceCreateError {
@@ -67,24 +72,43 @@ impl From<std::ffi::NulError> for InstanceCreateError {
 
 /// Creates a new instance based on the features declared in the provided registry.
 ///
-/// This function will consume the instance features stored in the registry.
-pub fn create_instance(registry: &mut InitializationRegistry, application_name: &str, application_version: u32) -> Result<InstanceContext, InstanceCreateError> {
-    let application_info = ApplicationInfo{
-        application_name: CString::new(application_name)?,
-        application_version,
-        engine_name: CString::new("Rosella")?,
-        engine_version: 0, // TODO
-        api_version: vk::API_VERSION_1_2
-    };
+/// A new instance of every feature is generated from the registry so it can be reused.
+pub fn create_instance(registry: &InitializationRegistry, application_name: &str, application_version: u32) -> Result<InstanceContext, InstanceCreateError> {
+    let application_info = ApplicationInfo::new(application_name, application_version)?;
 
     log::info!("Creating instance for \"{}\" {}", application_name, application_version);
 
-    let mut builder = InstanceBuilder::new(application_info, registry.take_instance_features());
+    let mut builder = InstanceBuilder::new(application_info, registry.make_instance_features());
     builder.run_init_pass()?;
     builder.run_enable_pass()?;
     builder.build()
 }
 
+/// Creates a vulkan instance with exactly the specified layers and extensions enabled without
+/// running any features.
+///
+/// No extension functions are loaded and no features are enabled on the returned context. Returns
+/// an error if any layer or extension is not supported.
+pub fn create_instance_raw(application_name: &str, application_version: u32, layers: &[&str], extensions: &[&str]) -> Result<InstanceContext, InstanceCreateError> {
+    let application_info = ApplicationInfo::new(application_name, application_version)?;
+
+    log::info!("Creating raw instance for \"{}\" {}", application_name, application_version);
+
+    let info = InstanceInfo::new(ash::Entry::new())?;
+    let version = std::cmp::min(info.get_vulkan_version(), VulkanVersion::from_raw(application_info.api_version));
+    let mut config = InstanceConfigurator::new(version);
+    for layer in layers {
+        config.enable_layer(layer);
+    }
+    for extension in extensions {
+        config.enable_extension_str_no_load(extension);
+    }
+
+    let (instance, function_set) = config.build_instance(&info, &application_info.make_vk_info())?;
+
+    Ok(InstanceContext::new(version, info.entry, instance, function_set, EnabledFeatures::new(std::iter::empty())))
+}
+
 struct ApplicationInfo {
     application_name: CString,
     application_version: u32,
@@ -93,6 +117,27 @@ struct ApplicationInfo {
     api_version: u32,
 }
 
+impl ApplicationInfo {
+    fn new(application_name: &str, application_version: u32) -> Result<Self, InstanceCreateError> {
+        Ok(Self {
+            application_name: CString::new(application_name)?,
+            application_version,
+            engine_name: CString::new("Rosella")?,
+            engine_version: 0, // TODO
+            api_version: vk::API_VERSION_1_2
+        })
+    }
+
+    fn make_vk_info(&self) -> vk::ApplicationInfoBuilder<'_> {
+        vk::ApplicationInfo::builder()
+            .application_name(self.application_name.as_c_str())
+            .application_version(self.application_version)
+            .engine_name(self.engine_name.as_c_str())
+            .engine_version(self.engine_version)
+            .api_version(self.api_version)
+    }
+}
+
 /// Represents the current state of some feature in the instance initialization process
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum InstanceFeatureState {
@@ -218,11 +263,12 @@ impl InstanceBuilder {
         if self.config.is_some() {
             panic!("Called run enable pass but config is already some");
         }
-        self.config = Some(InstanceConfigurator::new());
-        let config = self.config.as_mut().unwrap();
-
         let info = self.info.as_ref().expect("Called run enable pass but info is none");
 
+        let version = std::cmp::min(info.get_vulkan_version(), VulkanVersion::from_raw(self.application_info.api_version));
+        self.config = Some(InstanceConfigurator::new(version));
+        let config = self.config.as_mut().unwrap();
+
         self.processor.run_pass::<InstanceCreateError, _>(
             InstanceFeatureState::Enabled,
             |feature, access| {
@@ -245,23 +291,21 @@ impl InstanceBuilder {
     fn build(self) -> Result<InstanceContext, InstanceCreateError> {
         log::debug!("Building instance");
 
-        let app_info = vk::ApplicationInfo::builder()
-            .application_name(self.application_info.application_name.as_c_str())
-            .application_version(self.application_info.application_version)
-            .engine_name(self.application_info.engine_name.as_c_str())
-            .engine_version(self.application_info.engine_version)
-            .api_version(self.application_info.api_version);
-
         let info = self.info.expect("Called build but info is none");
         let (instance, function_set) = self.config.expect("Called build but config is none")
-            .build_instance(&info, &app_info.build())?;
+            .build_instance(&info, &self.application_info.make_vk_info())?;
 
         let features = EnabledFeatures::new(self.processor.into_iter().filter_map(
             |mut info| {
+                if info.state != InstanceFeatureState::Enabled {
+                    return None;
+                }
                 Some((info.name.get_uuid(), info.feature.as_mut().finish(&instance, &function_set)))
             }));
 
-        Ok(InstanceContext::new(info.get_vulkan_version(), info.entry, instance, function_set, features))
+        let version = std::cmp::min(info.get_vulkan_version(), VulkanVersion::from_raw(self.application_info.api_version));
+        log::info!("Created instance with vulkan version {}.{}", version.get_major(), version.get_minor());
+        Ok(InstanceContext::new(version, info.entry, instance, function_set, features))
     }
 }
 
@@ -382,16 +426,26 @@ impl InstanceInfo {
 pub struct InstanceConfigurator {
     enabled_layers: HashSet<UUID>,
     enabled_extensions: HashMap<UUID, Option<&'static InstanceExtensionLoaderFn>>,
+    /// Maps extensions enabled as a dependency to the (dependency, extension) names that required them
+    dependency_sources: HashMap<UUID, (&'static str, String)>,
+    /// The effective vulkan version of the instance. Dependencies which are core in this version are not enabled
+    version: VulkanVersion,
+
+    /// Structs pushed into the pNext chain of the instance create info
+    next_structs: Vec<Box<dyn vk::ExtendsInstanceCreateInfo>>,
 
     /// Temporary hack until extensions can be properly handled
     debug_util_messenger: vk::PFN_vkDebugUtilsMessengerCallbackEXT, // TODO Make this flexible somehow, probably requires general overhaul of p_next pushing
 }
 
 impl InstanceConfigurator {
-    fn new() -> Self {
+    fn new(version: VulkanVersion) -> Self {
         Self{
             enabled_layers: HashSet::new(),
             enabled_extensions: HashMap::new(),
+            dependency_sources: HashMap::new(),
+            version,
+            next_structs: Vec::new(),
             debug_util_messenger: None,
         }
     }
@@ -408,15 +462,31 @@ impl InstanceConfigurator {
     }
 
     /// Enables a instance extension and registers the extension for automatic function loading
+    ///
+    /// Any dependencies of the extension which are not part of the effective vulkan version of the
+    /// instance will be enabled as well.
     pub fn enable_extension<EXT: VkExtensionInfo + InstanceExtensionLoader + 'static>(&mut self) {
         let uuid = EXT::UUID.get_uuid();
         self.enabled_extensions.insert(uuid, Some(&EXT::load_extension));
+        self.enable_dependencies::<EXT>();
     }
 
     /// Enables a instance extension without automatic function loading
+    ///
+    /// Any dependencies of the extension which are not part of the effective vulkan version of the
+    /// instance will be enabled as well.
     pub fn enable_extension_no_load<EXT: VkExtensionInfo>(&mut self) {
         let uuid = EXT::UUID.get_uuid();
         self.enabled_extensions.insert(uuid, None);
+        self.enable_dependencies::<EXT>();
+    }
+
+    fn enable_dependencies<EXT: VkExtensionInfo>(&mut self) {
+        for (dependency, source) in resolve_extension_dependencies(EXT::UUID.get_name(), self.version) {
+            self.enable_extension_str_no_load(dependency);
+            self.dependency_sources.entry(NamedUUID::uuid_for(dependency))
+                .or_insert_with(|| (dependency, source.to_string()));
+        }
     }
 
     /// Enables a instance extension without automatic function loading
@@ -429,6 +499,14 @@ impl InstanceConfigurator {
         }
     }
 
+    /// Pushes a struct into the pNext chain of the instance create info.
+    ///
+    /// The struct is owned by the configurator and kept alive until the instance has been created.
+    /// Any pointers stored inside the struct must stay valid until then as well.
+    pub fn push_next<T: vk::ExtendsInstanceCreateInfo + 'static>(&mut self, next: T) {
+        self.next_structs.push(Box::new(next));
+    }
+
     /// Sets the debug messenger for VK_EXT_debug_utils
     ///
     /// This is a temporary hack until extension configuration can be properly handled.
@@ -437,7 +515,7 @@ impl InstanceConfigurator {
     }
 
     /// Creates a vulkan instance based on the configuration stored in this InstanceConfigurator
-    fn build_instance(self, info: &InstanceInfo, application_info: &vk::ApplicationInfo) -> Result<(ash::Instance, ExtensionFunctionSet), InstanceCreateError> {
+    fn build_instance(mut self, info: &InstanceInfo, application_info: &vk::ApplicationInfo) -> Result<(ash::Instance, ExtensionFunctionSet), InstanceCreateError> {
         let mut layers = Vec::with_capacity(self.enabled_layers.len());
         for layer in &self.enabled_layers {
             let layer = info.get_layer_properties_uuid(layer)
@@ -450,8 +528,15 @@ impl InstanceConfigurator {
 
         let mut extensions = Vec::with_capacity(self.enabled_extensions.len());
         for (uuid, loader) in &self.enabled_extensions {
-            let extension = info.get_extension_properties_uuid(uuid)
-                .ok_or(InstanceCreateError::ExtensionNotSupported)?;
+            let extension = info.get_extension_properties_uuid(uuid).ok_or_else(|| {
+                match self.dependency_sources.get(uuid) {
+                    Some((dependency, extension)) => InstanceCreateError::ExtensionDependencyNotSupported {
+                        extension: extension.clone(),
+                        dependency: dependency.to_string(),
+                    },
+                    None => InstanceCreateError::ExtensionNotSupported,
+                }
+            })?;
 
             if loader.is_some() {
                 log::debug!("Enabling extension \"{}\"", extension.get_name());
@@ -477,6 +562,15 @@ impl InstanceConfigurator {
             create_info = create_info.push_next(&mut messenger);
         }
 
+        for next in &mut self.next_structs {
+            // All vulkan structs start with sType and pNext
+            let next = next.as_mut() as *mut dyn vk::ExtendsInstanceCreateInfo as *mut vk::BaseOutStructure;
+            unsafe {
+                (*next).p_next = create_info.p_next as *mut vk::BaseOutStructure;
+            }
+            create_info.p_next = next as *const std::ffi::c_void;
+        }
+
         let instance = unsafe {
             info.get_entry().create_instance(&create_info, None)
         }?;
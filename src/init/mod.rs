@@ -7,6 +7,7 @@ mod utils;
 
 pub use rosella_features::register_rosella_headless;
 pub use rosella_features::register_rosella_debug;
+pub use rosella_features::register_rosella_gpu_assisted_validation;
 
 pub use initialization_registry::InitializationRegistry;
 
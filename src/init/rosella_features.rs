@@ -1,17 +1,21 @@
 //! Common vulkan and rosella instance and device
 
 use std::any::Any;
+use std::collections::HashMap;
 use std::ffi::{c_void, CStr};
+use ash::extensions::khr::Surface;
 use ash::vk;
 use paste::paste;
-use crate::init::application_feature::{ApplicationDeviceFeatureGenerator, ApplicationDeviceFeature, ApplicationInstanceFeature, InitResult};
+use crate::init::application_feature::{ApplicationDeviceFeatureGenerator, ApplicationDeviceFeature, ApplicationInstanceFeature, ApplicationInstanceFeatureGenerator, InitResult};
 use crate::init::instance::{InstanceConfigurator, InstanceInfo};
 use crate::init::application_feature::FeatureBase;
-use crate::init::device::{DeviceConfigurator, DeviceInfo};
+use crate::init::device::{DeviceConfigurator, DeviceInfo, QueueFamilyInfo, QueueRequest, VulkanQueue};
 use crate::init::initialization_registry::InitializationRegistry;
 use crate::init::application_feature::FeatureAccess;
 use crate::NamedUUID;
-use crate::rosella::VulkanVersion;
+use crate::device::DeviceContext;
+use crate::rosella::{InstanceContext, VulkanVersion};
+use crate::util::extensions::ExtensionFunctionSet;
 
 /// Registers all instance and device features required for rosella to work in headless mode
 pub fn register_rosella_headless(registry: &mut InitializationRegistry) {
@@ -20,6 +24,10 @@ pub fn register_rosella_headless(registry: &mut InitializationRegistry) {
     RosellaInstanceBase::register_into(registry, true);
 
     KHRTimelineSemaphoreDevice::register_into(registry, false);
+    DescriptorIndexingDevice::register_into(registry, false);
+    PipelineStatisticsQueryDevice::register_into(registry, false);
+    SamplerAnisotropyDevice::register_into(registry, false);
+    Synchronization2Device::register_into(registry, false);
     RosellaDeviceBase::register_into(registry, true);
 }
 
@@ -28,11 +36,38 @@ pub fn register_rosella_debug(registry: &mut InitializationRegistry, required: b
     RosellaDebug::register_into(registry, required);
 }
 
+/// Registers the device features needed to render without render pass and framebuffer objects
+pub fn register_rosella_dynamic_rendering(registry: &mut InitializationRegistry, required: bool) {
+    DynamicRenderingDevice::register_into(registry, required);
+}
+
+/// Registers the device features needed to push descriptors directly into command buffers
+pub fn register_rosella_push_descriptor(registry: &mut InitializationRegistry, required: bool) {
+    PushDescriptorDevice::register_into(registry, required);
+}
+
+/// Registers the debug features and additionally enables gpu assisted validation
+pub fn register_rosella_gpu_assisted_validation(registry: &mut InitializationRegistry, required: bool) {
+    RosellaDebug::register_into(registry, required);
+    RosellaGpuAssistedValidation::register_into(registry, required);
+}
+
 /// Utility macro that generates common implementations for instance features which can be default
 /// created.
 #[macro_export]
 macro_rules! const_instance_feature{
-    ($struct_name:ty, $name:literal, [$($dependency:expr),*]) => {
+    ($struct_name:ident, $name:literal, [$($dependency:expr),*]) => {
+        paste! {
+            #[derive(Default)]
+            pub struct [<$struct_name Generator>];
+
+            impl ApplicationInstanceFeatureGenerator for [<$struct_name Generator>] {
+                fn make_instance(&self) -> Box<dyn ApplicationInstanceFeature> {
+                    Box::new($struct_name::default())
+                }
+            }
+        }
+
         impl $struct_name {
             const NAME: NamedUUID = NamedUUID::new_const($name);
             const DEPENDENCIES: &'static [NamedUUID] = &[$($dependency,)*];
@@ -41,7 +76,7 @@ macro_rules! const_instance_feature{
                 registry.register_instance_feature(
                     Self::NAME,
                     Self::DEPENDENCIES.to_vec().into_boxed_slice(),
-                    Box::new(Self::default()),
+                    paste! { Box::new([<$struct_name Generator>]::default()) },
                     required
                 )
             }
@@ -181,6 +216,41 @@ impl ApplicationInstanceFeature for RosellaDebug {
     }
 }
 
+/// Instance feature which enables gpu assisted validation in the validation layers
+#[derive(Default)]
+pub struct RosellaGpuAssistedValidation;
+const_instance_feature!(RosellaGpuAssistedValidation, "rosella:instance_gpu_assisted_validation", [RosellaDebug::NAME]);
+
+/// Must be static since the pushed create info stores a pointer to it
+static GPU_ASSISTED_VALIDATION_ENABLES: [vk::ValidationFeatureEnableEXT; 2] = [
+    vk::ValidationFeatureEnableEXT::GPU_ASSISTED,
+    vk::ValidationFeatureEnableEXT::GPU_ASSISTED_RESERVE_BINDING_SLOT,
+];
+
+impl ApplicationInstanceFeature for RosellaGpuAssistedValidation {
+    fn init(&mut self, features: &mut dyn FeatureAccess, info: &InstanceInfo) -> InitResult {
+        if !features.is_supported(&RosellaDebug::NAME.get_uuid()) {
+            return InitResult::Disable;
+        }
+
+        if !info.is_extension_supported_str("VK_EXT_validation_features") {
+            log::warn!("VK_EXT_validation_features not found! Gpu assisted validation will be disabled.");
+            return InitResult::Disable;
+        }
+
+        InitResult::Ok
+    }
+
+    fn enable(&mut self, _: &mut dyn FeatureAccess, _: &InstanceInfo, config: &mut InstanceConfigurator) {
+        config.enable_extension_str_no_load("VK_EXT_validation_features");
+
+        let validation_features = vk::ValidationFeaturesEXT::builder()
+            .enabled_validation_features(&GPU_ASSISTED_VALIDATION_ENABLES)
+            .build();
+        config.push_next(validation_features);
+    }
+}
+
 /// Instance feature representing the VK_KHR_get_physical_device_properties2 feature set.
 /// If the instance version is below 1.1 it will load the extension.
 #[derive(Default)]
@@ -244,7 +314,7 @@ const_device_feature!(KHRTimelineSemaphoreDevice, "rosella:device_khr_timeline_s
 
 impl ApplicationDeviceFeature for KHRTimelineSemaphoreDevice {
     fn init(&mut self, _: &mut dyn FeatureAccess, info: &DeviceInfo) -> InitResult {
-        if info.get_instance().get_version().is_supported(VulkanVersion::VK_1_2) {
+        if info.get_effective_version().is_supported(VulkanVersion::VK_1_2) {
             if info.get_device_1_2_features().unwrap().timeline_semaphore == vk::TRUE {
                 InitResult::Ok
             } else {
@@ -268,10 +338,192 @@ impl ApplicationDeviceFeature for KHRTimelineSemaphoreDevice {
     }
 
     fn enable(&mut self, _: &mut dyn FeatureAccess, info: &DeviceInfo, config: &mut DeviceConfigurator) {
-        if !info.get_instance().get_version().is_supported(VulkanVersion::VK_1_2) {
+        if !info.get_effective_version().is_supported(VulkanVersion::VK_1_2) {
             config.enable_extension::<ash::extensions::khr::TimelineSemaphore>();
         }
-        config.enable_timeline_semaphore()
+        config.feature_struct_mut::<vk::PhysicalDeviceTimelineSemaphoreFeatures>().timeline_semaphore = vk::TRUE;
+    }
+}
+
+/// Device feature representing the descriptor indexing features needed for variable sized
+/// descriptor arrays. Currently only provided on vulkan 1.2 devices.
+#[derive(Default)]
+pub struct DescriptorIndexingDevice;
+const_device_feature!(DescriptorIndexingDevice, "rosella:device_descriptor_indexing", []);
+
+impl DescriptorIndexingDevice {
+    /// Returns true if the feature has been enabled on the device
+    pub fn is_enabled(device: &DeviceContext) -> bool {
+        device.get_enabled_features().is_feature_enabled(&Self::NAME.get_uuid())
+    }
+}
+
+impl ApplicationDeviceFeature for DescriptorIndexingDevice {
+    fn init(&mut self, _: &mut dyn FeatureAccess, info: &DeviceInfo) -> InitResult {
+        if !info.get_effective_version().is_supported(VulkanVersion::VK_1_2) {
+            return InitResult::Disable;
+        }
+
+        match info.get_feature_struct::<vk::PhysicalDeviceDescriptorIndexingFeatures>() {
+            Some(features) => {
+                if features.runtime_descriptor_array == vk::TRUE
+                    && features.descriptor_binding_partially_bound == vk::TRUE
+                    && features.descriptor_binding_variable_descriptor_count == vk::TRUE {
+                    InitResult::Ok
+                } else {
+                    InitResult::Disable
+                }
+            }
+            None => InitResult::Disable,
+        }
+    }
+
+    fn enable(&mut self, _: &mut dyn FeatureAccess, _: &DeviceInfo, config: &mut DeviceConfigurator) {
+        let features = config.feature_struct_mut::<vk::PhysicalDeviceDescriptorIndexingFeatures>();
+        features.runtime_descriptor_array = vk::TRUE;
+        features.descriptor_binding_partially_bound = vk::TRUE;
+        features.descriptor_binding_variable_descriptor_count = vk::TRUE;
+    }
+}
+
+/// Device feature enabling the pipelineStatisticsQuery feature.
+#[derive(Default)]
+pub struct PipelineStatisticsQueryDevice;
+const_device_feature!(PipelineStatisticsQueryDevice, "rosella:device_pipeline_statistics_query", []);
+
+impl PipelineStatisticsQueryDevice {
+    /// Returns true if the feature has been enabled on the device
+    pub fn is_enabled(device: &DeviceContext) -> bool {
+        device.get_enabled_features().is_feature_enabled(&Self::NAME.get_uuid())
+    }
+}
+
+impl ApplicationDeviceFeature for PipelineStatisticsQueryDevice {
+    fn init(&mut self, _: &mut dyn FeatureAccess, info: &DeviceInfo) -> InitResult {
+        if info.get_device_1_0_features().pipeline_statistics_query == vk::TRUE {
+            InitResult::Ok
+        } else {
+            InitResult::Disable
+        }
+    }
+
+    fn enable(&mut self, _: &mut dyn FeatureAccess, _: &DeviceInfo, config: &mut DeviceConfigurator) {
+        config.features_1_0_mut().pipeline_statistics_query = vk::TRUE;
+    }
+}
+
+/// Device feature enabling the samplerAnisotropy feature.
+#[derive(Default)]
+pub struct SamplerAnisotropyDevice;
+const_device_feature!(SamplerAnisotropyDevice, "rosella:device_sampler_anisotropy", []);
+
+impl SamplerAnisotropyDevice {
+    /// Returns true if the feature has been enabled on the device
+    pub fn is_enabled(device: &DeviceContext) -> bool {
+        device.get_enabled_features().is_feature_enabled(&Self::NAME.get_uuid())
+    }
+}
+
+impl ApplicationDeviceFeature for SamplerAnisotropyDevice {
+    fn init(&mut self, _: &mut dyn FeatureAccess, info: &DeviceInfo) -> InitResult {
+        if info.get_device_1_0_features().sampler_anisotropy == vk::TRUE {
+            InitResult::Ok
+        } else {
+            InitResult::Disable
+        }
+    }
+
+    fn enable(&mut self, _: &mut dyn FeatureAccess, _: &DeviceInfo, config: &mut DeviceConfigurator) {
+        config.features_1_0_mut().sampler_anisotropy = vk::TRUE;
+    }
+}
+
+/// Device feature representing the VK_KHR_dynamic_rendering feature set.
+///
+/// Requires vulkan 1.1. Extension dependencies which are not part of the effective vulkan version
+/// must be supported as well.
+#[derive(Default)]
+pub struct DynamicRenderingDevice;
+const_device_feature!(DynamicRenderingDevice, "rosella:device_dynamic_rendering", []);
+
+impl DynamicRenderingDevice {
+    /// Returns true if the feature has been enabled on the device
+    pub fn is_enabled(device: &DeviceContext) -> bool {
+        device.get_enabled_features().is_feature_enabled(&Self::NAME.get_uuid())
+    }
+}
+
+impl ApplicationDeviceFeature for DynamicRenderingDevice {
+    fn init(&mut self, _: &mut dyn FeatureAccess, info: &DeviceInfo) -> InitResult {
+        if !info.get_effective_version().is_supported(VulkanVersion::VK_1_1) {
+            return InitResult::Disable;
+        }
+        if !info.are_extension_dependencies_supported::<ash::extensions::khr::DynamicRendering>() {
+            return InitResult::Disable;
+        }
+
+        match info.get_feature_struct::<vk::PhysicalDeviceDynamicRenderingFeaturesKHR>() {
+            Some(features) if features.dynamic_rendering == vk::TRUE => InitResult::Ok,
+            _ => InitResult::Disable,
+        }
+    }
+
+    fn enable(&mut self, _: &mut dyn FeatureAccess, _: &DeviceInfo, config: &mut DeviceConfigurator) {
+        config.enable_extension::<ash::extensions::khr::DynamicRendering>();
+        config.feature_struct_mut::<vk::PhysicalDeviceDynamicRenderingFeaturesKHR>().dynamic_rendering = vk::TRUE;
+    }
+}
+
+/// Device feature representing the VK_KHR_synchronization2 feature set. Required to record
+/// barriers with [`crate::objects::barrier::pipeline_barrier2`].
+#[derive(Default)]
+pub struct Synchronization2Device;
+const_device_feature!(Synchronization2Device, "rosella:device_synchronization_2", []);
+
+impl Synchronization2Device {
+    /// Returns true if the feature has been enabled on the device
+    pub fn is_enabled(device: &DeviceContext) -> bool {
+        device.get_enabled_features().is_feature_enabled(&Self::NAME.get_uuid())
+    }
+}
+
+impl ApplicationDeviceFeature for Synchronization2Device {
+    fn init(&mut self, _: &mut dyn FeatureAccess, info: &DeviceInfo) -> InitResult {
+        match info.get_feature_struct::<vk::PhysicalDeviceSynchronization2FeaturesKHR>() {
+            Some(features) if features.synchronization2 == vk::TRUE => InitResult::Ok,
+            _ => InitResult::Disable,
+        }
+    }
+
+    fn enable(&mut self, _: &mut dyn FeatureAccess, _: &DeviceInfo, config: &mut DeviceConfigurator) {
+        config.enable_extension::<ash::extensions::khr::Synchronization2>();
+        config.feature_struct_mut::<vk::PhysicalDeviceSynchronization2FeaturesKHR>().synchronization2 = vk::TRUE;
+    }
+}
+
+/// Device feature representing the VK_KHR_push_descriptor extension.
+#[derive(Default)]
+pub struct PushDescriptorDevice;
+const_device_feature!(PushDescriptorDevice, "rosella:device_push_descriptor", []);
+
+impl PushDescriptorDevice {
+    /// Returns true if the feature has been enabled on the device
+    pub fn is_enabled(device: &DeviceContext) -> bool {
+        device.get_enabled_features().is_feature_enabled(&Self::NAME.get_uuid())
+    }
+}
+
+impl ApplicationDeviceFeature for PushDescriptorDevice {
+    fn init(&mut self, _: &mut dyn FeatureAccess, info: &DeviceInfo) -> InitResult {
+        if info.is_extension_supported::<ash::extensions::khr::PushDescriptor>() {
+            InitResult::Ok
+        } else {
+            InitResult::Disable
+        }
+    }
+
+    fn enable(&mut self, _: &mut dyn FeatureAccess, _: &DeviceInfo, config: &mut DeviceConfigurator) {
+        config.enable_extension::<ash::extensions::khr::PushDescriptor>();
     }
 }
 
@@ -291,15 +543,26 @@ impl WindowSurface {
     }
 
     pub fn register_into(registry: &mut InitializationRegistry, window: &winit::window::Window, required: bool) -> NamedUUID {
-        let instance = Box::new(Self::new(window));
-        let name = instance.name.clone();
+        let generator = Box::new(WindowSurfaceGenerator(Self::new(window)));
+        let name = generator.0.name.clone();
 
-        registry.register_instance_feature(name.clone(), [].to_vec().into_boxed_slice(), instance, required);
+        registry.register_instance_feature(name.clone(), [].to_vec().into_boxed_slice(), generator, required);
 
         name
     }
 }
 
+struct WindowSurfaceGenerator(WindowSurface);
+
+impl ApplicationInstanceFeatureGenerator for WindowSurfaceGenerator {
+    fn make_instance(&self) -> Box<dyn ApplicationInstanceFeature> {
+        Box::new(WindowSurface {
+            name: self.0.name.clone(),
+            extensions: self.0.extensions.clone(),
+        })
+    }
+}
+
 impl FeatureBase for WindowSurface {
     fn as_any(&self) -> &dyn Any {
         self
@@ -321,15 +584,74 @@ impl ApplicationInstanceFeature for WindowSurface {
     }
 
     fn enable(&mut self, _: &mut dyn FeatureAccess, _: &InstanceInfo, config: &mut InstanceConfigurator) {
+        // Loaded so that queues can test for present support
+        config.enable_extension::<ash::extensions::khr::Surface>();
         for extension in &self.extensions {
             config.enable_extension_str_no_load(extension.to_str().unwrap())
         }
     }
 }
 
+/// The queues acquired by rosella during device creation.
+///
+/// The compute and transfer queues prefer dedicated queue families and fall back to the graphics
+/// family. Queues may be shared if they belong to the same family.
+pub struct DeviceQueues {
+    graphics: Option<VulkanQueue>,
+    compute: Option<VulkanQueue>,
+    transfer: Option<VulkanQueue>,
+}
+
+impl DeviceQueues {
+    /// Returns the queues acquired during device creation or [`None`] if the device was not created
+    /// by rosella.
+    pub fn get(device: &DeviceContext) -> Option<&DeviceQueues> {
+        device.get_enabled_features().get_feature_data_cast(&RosellaDeviceBase::NAME.get_uuid())
+    }
+
+    pub fn get_graphics_queue(&self) -> Option<&VulkanQueue> {
+        self.graphics.as_ref()
+    }
+
+    pub fn get_compute_queue(&self) -> Option<&VulkanQueue> {
+        self.compute.as_ref()
+    }
+
+    pub fn get_transfer_queue(&self) -> Option<&VulkanQueue> {
+        self.transfer.as_ref()
+    }
+}
+
+/// Selects the (graphics, compute, transfer) queue families
+fn select_queue_families(families: &[QueueFamilyInfo]) -> (Option<u32>, Option<u32>, Option<u32>) {
+    let find = |required: vk::QueueFlags, excluded: vk::QueueFlags| {
+        families.iter()
+            .find(|family| {
+                let flags = family.get_properties().queue_flags;
+                family.get_properties().queue_count > 0 && flags.contains(required) && !flags.intersects(excluded)
+            })
+            .map(QueueFamilyInfo::get_index)
+    };
+
+    let graphics = find(vk::QueueFlags::GRAPHICS, vk::QueueFlags::empty());
+    let compute = find(vk::QueueFlags::COMPUTE, vk::QueueFlags::GRAPHICS)
+        .or_else(|| find(vk::QueueFlags::COMPUTE | vk::QueueFlags::GRAPHICS, vk::QueueFlags::empty()))
+        .or_else(|| find(vk::QueueFlags::COMPUTE, vk::QueueFlags::empty()));
+    // Graphics and compute queues implicitly support transfer operations
+    let transfer = find(vk::QueueFlags::TRANSFER, vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE)
+        .or(compute)
+        .or(graphics);
+
+    (graphics, compute, transfer)
+}
+
 /// Device feature which provides all requirements needed for rosella to function in headless
 #[derive(Default)]
-struct RosellaDeviceBase;
+struct RosellaDeviceBase {
+    graphics: Option<QueueRequest>,
+    compute: Option<QueueRequest>,
+    transfer: Option<QueueRequest>,
+}
 const_device_feature!(RosellaDeviceBase, "rosella:device_base", [KHRTimelineSemaphoreDevice::NAME]);
 
 impl ApplicationDeviceFeature for RosellaDeviceBase {
@@ -341,7 +663,150 @@ impl ApplicationDeviceFeature for RosellaDeviceBase {
         InitResult::Ok
     }
 
-    fn enable(&mut self, _: &mut dyn FeatureAccess, _: &DeviceInfo, config: &mut DeviceConfigurator) {
-        config.add_queue_request(0); // TODO This is just to prevent validation errors
+    fn enable(&mut self, _: &mut dyn FeatureAccess, info: &DeviceInfo, config: &mut DeviceConfigurator) {
+        let (graphics, compute, transfer) = select_queue_families(info.get_queue_family_infos());
+        log::debug!("Selected queue families graphics: {:?} compute: {:?} transfer: {:?}", graphics, compute, transfer);
+
+        self.graphics = graphics.map(|family| config.add_queue_request(family));
+        self.compute = compute.map(|family| config.add_queue_request(family));
+        self.transfer = transfer.map(|family| config.add_queue_request(family));
+
+        if self.graphics.is_none() && self.compute.is_none() && self.transfer.is_none() {
+            config.add_queue_request(0); // TODO This is just to prevent validation errors
+        }
     }
-}
\ No newline at end of file
+
+    fn finish(&mut self, _: &InstanceContext, _: &ash::Device, _: &ExtensionFunctionSet) -> Option<Box<dyn Any>> {
+        Some(Box::new(DeviceQueues {
+            graphics: self.graphics.as_ref().map(QueueRequest::get),
+            compute: self.compute.as_ref().map(QueueRequest::get),
+            transfer: self.transfer.as_ref().map(QueueRequest::get),
+        }))
+    }
+}
+
+/// Device feature which acquires a queue that can present to each of a set of surfaces. If
+/// possible the graphics queue family is used.
+pub struct SurfacePresentQueues {
+    surfaces: Vec<vk::SurfaceKHR>,
+    requests: Vec<(vk::SurfaceKHR, QueueRequest)>,
+}
+
+impl SurfacePresentQueues {
+    const NAME: NamedUUID = NamedUUID::new_const("rosella:device_surface_present_queues");
+
+    pub fn register_into(registry: &mut InitializationRegistry, surfaces: &[vk::SurfaceKHR], required: bool) {
+        registry.register_device_feature(
+            Self::NAME,
+            [].to_vec().into_boxed_slice(),
+            Box::new(SurfacePresentQueuesGenerator{ surfaces: surfaces.to_vec() }),
+            required
+        )
+    }
+
+    /// Returns the queue acquired to present to `surface` or [`None`] if the surface was not
+    /// registered during device creation.
+    pub fn get_present_queue(device: &DeviceContext, surface: vk::SurfaceKHR) -> Option<&VulkanQueue> {
+        device.get_enabled_features()
+            .get_feature_data_cast::<HashMap<vk::SurfaceKHR, VulkanQueue>>(&Self::NAME.get_uuid())
+            .and_then(|queues| queues.get(&surface))
+    }
+
+    /// Selects a queue family that supports presenting to the surface
+    fn select_family(info: &DeviceInfo, surface_loader: &Surface, surface: vk::SurfaceKHR) -> Option<u32> {
+        let families = info.get_queue_family_infos();
+        let supported: Vec<_> = families.iter()
+            .filter(|family| unsafe {
+                surface_loader.get_physical_device_surface_support(*info.get_physical_device(), family.get_index(), surface)
+            }.unwrap_or(false))
+            .collect();
+
+        let graphics = select_queue_families(families).0;
+        supported.iter()
+            .find(|family| Some(family.get_index()) == graphics)
+            .or_else(|| supported.first())
+            .map(|family| family.get_index())
+    }
+}
+
+struct SurfacePresentQueuesGenerator {
+    surfaces: Vec<vk::SurfaceKHR>,
+}
+
+impl ApplicationDeviceFeatureGenerator for SurfacePresentQueuesGenerator {
+    fn make_instance(&self) -> Box<dyn ApplicationDeviceFeature> {
+        Box::new(SurfacePresentQueues{ surfaces: self.surfaces.clone(), requests: Vec::new() })
+    }
+}
+
+impl FeatureBase for SurfacePresentQueues {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl ApplicationDeviceFeature for SurfacePresentQueues {
+    fn init(&mut self, _: &mut dyn FeatureAccess, info: &DeviceInfo) -> InitResult {
+        if !info.is_extension_supported::<ash::extensions::khr::Swapchain>() {
+            return InitResult::Disable;
+        }
+
+        let surface_loader = match info.get_instance().get_extension::<Surface>() {
+            Some(surface_loader) => surface_loader,
+            None => return InitResult::Disable,
+        };
+        for surface in &self.surfaces {
+            if Self::select_family(info, surface_loader, *surface).is_none() {
+                return InitResult::Disable;
+            }
+        }
+
+        InitResult::Ok
+    }
+
+    fn enable(&mut self, _: &mut dyn FeatureAccess, info: &DeviceInfo, config: &mut DeviceConfigurator) {
+        config.enable_extension::<ash::extensions::khr::Swapchain>();
+
+        let surface_loader = info.get_instance().get_extension::<Surface>().unwrap();
+        for surface in &self.surfaces {
+            let family = Self::select_family(info, surface_loader, *surface).unwrap();
+            log::debug!("Selected queue family {} to present to surface {:?}", family, surface);
+            self.requests.push((*surface, config.add_queue_request(family)));
+        }
+    }
+
+    fn finish(&mut self, _: &InstanceContext, _: &ash::Device, _: &ExtensionFunctionSet) -> Option<Box<dyn Any>> {
+        let queues: HashMap<_, _> = self.requests.iter()
+            .map(|(surface, request)| (*surface, request.get()))
+            .collect();
+
+        Some(Box::new(queues))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn family(index: u32, queue_flags: vk::QueueFlags) -> QueueFamilyInfo {
+        QueueFamilyInfo::new(index, vk::QueueFamilyProperties{ queue_flags, queue_count: 1, ..Default::default() })
+    }
+
+    #[test]
+    fn queue_family_selection() {
+        let all = vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE | vk::QueueFlags::TRANSFER;
+        let families = [
+            family(0, all),
+            family(1, vk::QueueFlags::COMPUTE | vk::QueueFlags::TRANSFER),
+            family(2, vk::QueueFlags::TRANSFER),
+        ];
+
+        assert_eq!(select_queue_families(&families), (Some(0), Some(1), Some(2)));
+        assert_eq!(select_queue_families(&families[0..1]), (Some(0), Some(0), Some(0)));
+        assert_eq!(select_queue_families(&families[1..2]), (None, Some(1), Some(1)));
+    }
+}
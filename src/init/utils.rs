@@ -99,7 +99,7 @@ pub struct EnabledFeatures {
 }
 
 impl EnabledFeatures {
-    pub(super) fn new<T: Iterator<Item=(UUID, Option<Box<dyn Any>>)>>(data: T) -> Self {
+    pub(crate) fn new<T: Iterator<Item=(UUID, Option<Box<dyn Any>>)>>(data: T) -> Self {
         Self{ features: data.map(|(id, data)| (id, EnabledFeature{ data })).collect() }
     }
 
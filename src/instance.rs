@@ -3,9 +3,11 @@ use std::sync::Arc;
 use ash::vk;
 
 use crate::init::EnabledFeatures;
+use crate::init::instance::InstanceCreateError;
 use crate::util::extensions::{AsRefOption, ExtensionFunctionSet, VkExtensionInfo, VkExtensionFunctions};
 use crate::UUID;
 
+/// A vulkan api version. Versions are compared by their major, minor and patch number.
 #[derive(Copy, Clone, Debug)]
 pub struct VulkanVersion(u32);
 
@@ -22,8 +24,50 @@ impl VulkanVersion {
         Self(vk::make_api_version(variant, major, minor, patch))
     }
 
+    pub const fn get_raw(&self) -> u32 {
+        self.0
+    }
+
+    pub const fn get_major(&self) -> u32 {
+        vk::api_version_major(self.0)
+    }
+
+    pub const fn get_minor(&self) -> u32 {
+        vk::api_version_minor(self.0)
+    }
+
+    pub const fn get_patch(&self) -> u32 {
+        vk::api_version_patch(self.0)
+    }
+
+    /// Returns true if this version is at least the major and minor version of `version`.
     pub fn is_supported(&self, version: VulkanVersion) -> bool {
-        vk::api_version_major(self.0) >= vk::api_version_major(version.0)
+        (self.get_major(), self.get_minor()) >= (version.get_major(), version.get_minor())
+    }
+
+    fn as_tuple(&self) -> (u32, u32, u32) {
+        (self.get_major(), self.get_minor(), self.get_patch())
+    }
+}
+
+impl PartialEq for VulkanVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_tuple() == other.as_tuple()
+    }
+}
+
+impl Eq for VulkanVersion {
+}
+
+impl PartialOrd for VulkanVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for VulkanVersion {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_tuple().cmp(&other.as_tuple())
     }
 }
 
@@ -57,6 +101,12 @@ impl InstanceContext {
         }))
     }
 
+    /// Creates an instance with only the specified layers and extensions enabled. See
+    /// [`crate::init::instance::create_instance_raw`].
+    pub fn new_raw(application_name: &str, application_version: u32, layers: &[&str], extensions: &[&str]) -> Result<Self, InstanceCreateError> {
+        crate::init::instance::create_instance_raw(application_name, application_version, layers, extensions)
+    }
+
     pub fn get_entry(&self) -> &ash::Entry {
         &self.0.entry
     }
@@ -65,6 +115,8 @@ impl InstanceContext {
         &self.0.instance
     }
 
+    /// Returns the vulkan version usable with the instance. This is the lower of the loader version
+    /// and the api version requested during instance creation.
     pub fn get_version(&self) -> VulkanVersion {
         self.0.version
     }
@@ -80,4 +132,22 @@ impl InstanceContext {
     pub fn get_enabled_features(&self) -> &EnabledFeatures {
         &self.0.features
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_comparison() {
+        let version_1_1_5 = VulkanVersion::new(0, 1, 1, 5);
+
+        assert!(version_1_1_5.is_supported(VulkanVersion::VK_1_0));
+        assert!(version_1_1_5.is_supported(VulkanVersion::VK_1_1));
+        assert!(!version_1_1_5.is_supported(VulkanVersion::VK_1_2));
+        assert!(!VulkanVersion::VK_1_0.is_supported(VulkanVersion::VK_1_2));
+
+        assert!(version_1_1_5 > VulkanVersion::VK_1_1);
+        assert_eq!(std::cmp::min(VulkanVersion::VK_1_2, version_1_1_5), version_1_1_5);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod frame;
 pub mod init;
 pub mod rosella;
 pub mod shader;
@@ -0,0 +1,220 @@
+//! Helpers to manually record pipeline barriers.
+//!
+//! Rosella will insert barriers automatically once command recording is abstracted. These helpers
+//! exist as an escape hatch for code that records command buffers directly.
+
+use ash::vk;
+
+use crate::objects::buffer::BufferRange;
+use crate::objects::image::ImageSubresourceRange;
+use crate::rosella::DeviceContext;
+
+/// Builds a `VkImageMemoryBarrier2KHR`. Queue family ownership is ignored by default.
+pub struct ImageBarrierBuilder {
+    barrier: vk::ImageMemoryBarrier2KHR,
+}
+
+impl ImageBarrierBuilder {
+    pub fn new(image: vk::Image, subresource_range: ImageSubresourceRange) -> Self {
+        Self {
+            barrier: vk::ImageMemoryBarrier2KHR {
+                image,
+                subresource_range: subresource_range.as_vk_subresource_range(),
+                src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                ..Default::default()
+            }
+        }
+    }
+
+    pub fn layouts(mut self, old_layout: vk::ImageLayout, new_layout: vk::ImageLayout) -> Self {
+        self.barrier.old_layout = old_layout;
+        self.barrier.new_layout = new_layout;
+        self
+    }
+
+    pub fn src(mut self, stage_mask: vk::PipelineStageFlags2KHR, access_mask: vk::AccessFlags2KHR) -> Self {
+        self.barrier.src_stage_mask = stage_mask;
+        self.barrier.src_access_mask = access_mask;
+        self
+    }
+
+    pub fn dst(mut self, stage_mask: vk::PipelineStageFlags2KHR, access_mask: vk::AccessFlags2KHR) -> Self {
+        self.barrier.dst_stage_mask = stage_mask;
+        self.barrier.dst_access_mask = access_mask;
+        self
+    }
+
+    /// Transfers ownership between queue families. If both families are the same no transfer is
+    /// needed and the families stay ignored. The same barrier must be recorded on both queues.
+    pub fn queue_transfer(mut self, src_family: u32, dst_family: u32) -> Self {
+        (self.barrier.src_queue_family_index, self.barrier.dst_queue_family_index) = queue_transfer_families(src_family, dst_family);
+        self
+    }
+
+    pub fn build(self) -> vk::ImageMemoryBarrier2KHR {
+        self.barrier
+    }
+
+    /// Makes storage image writes of compute shaders visible to sampling in fragment shaders and
+    /// transitions the image from `GENERAL` to `SHADER_READ_ONLY_OPTIMAL`.
+    pub fn compute_write_to_sample(image: vk::Image, subresource_range: ImageSubresourceRange) -> Self {
+        Self::new(image, subresource_range)
+            .layouts(vk::ImageLayout::GENERAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .src(vk::PipelineStageFlags2KHR::COMPUTE_SHADER, vk::AccessFlags2KHR::SHADER_STORAGE_WRITE)
+            .dst(vk::PipelineStageFlags2KHR::FRAGMENT_SHADER, vk::AccessFlags2KHR::SHADER_SAMPLED_READ)
+    }
+
+    /// Waits for sampling in fragment shaders to complete before compute shaders write to the image
+    /// and transitions it from `SHADER_READ_ONLY_OPTIMAL` to `GENERAL`.
+    pub fn sample_to_compute_write(image: vk::Image, subresource_range: ImageSubresourceRange) -> Self {
+        Self::new(image, subresource_range)
+            .layouts(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, vk::ImageLayout::GENERAL)
+            .src(vk::PipelineStageFlags2KHR::FRAGMENT_SHADER, vk::AccessFlags2KHR::NONE)
+            .dst(vk::PipelineStageFlags2KHR::COMPUTE_SHADER, vk::AccessFlags2KHR::SHADER_STORAGE_WRITE)
+    }
+}
+
+/// Builds a `VkBufferMemoryBarrier2KHR`. Queue family ownership is ignored by default.
+pub struct BufferBarrierBuilder {
+    barrier: vk::BufferMemoryBarrier2KHR,
+}
+
+impl BufferBarrierBuilder {
+    pub fn new(buffer: vk::Buffer, range: BufferRange) -> Self {
+        Self {
+            barrier: vk::BufferMemoryBarrier2KHR {
+                buffer,
+                offset: range.offset,
+                size: range.length,
+                src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                ..Default::default()
+            }
+        }
+    }
+
+    /// Creates a barrier covering the whole buffer.
+    pub fn whole(buffer: vk::Buffer) -> Self {
+        Self::new(buffer, BufferRange { offset: 0, length: vk::WHOLE_SIZE })
+    }
+
+    pub fn src(mut self, stage_mask: vk::PipelineStageFlags2KHR, access_mask: vk::AccessFlags2KHR) -> Self {
+        self.barrier.src_stage_mask = stage_mask;
+        self.barrier.src_access_mask = access_mask;
+        self
+    }
+
+    pub fn dst(mut self, stage_mask: vk::PipelineStageFlags2KHR, access_mask: vk::AccessFlags2KHR) -> Self {
+        self.barrier.dst_stage_mask = stage_mask;
+        self.barrier.dst_access_mask = access_mask;
+        self
+    }
+
+    /// Transfers ownership between queue families. If both families are the same no transfer is
+    /// needed and the families stay ignored. The same barrier must be recorded on both queues.
+    pub fn queue_transfer(mut self, src_family: u32, dst_family: u32) -> Self {
+        (self.barrier.src_queue_family_index, self.barrier.dst_queue_family_index) = queue_transfer_families(src_family, dst_family);
+        self
+    }
+
+    pub fn build(self) -> vk::BufferMemoryBarrier2KHR {
+        self.barrier
+    }
+}
+
+fn queue_transfer_families(src_family: u32, dst_family: u32) -> (u32, u32) {
+    if src_family == dst_family {
+        (vk::QUEUE_FAMILY_IGNORED, vk::QUEUE_FAMILY_IGNORED)
+    } else {
+        (src_family, dst_family)
+    }
+}
+
+/// Records a `vkCmdPipelineBarrier2KHR` into a command buffer.
+///
+/// Requires the [`crate::init::rosella_features::Synchronization2Device`] feature. Returns
+/// `ERROR_EXTENSION_NOT_PRESENT` if the feature is not enabled on the device.
+pub fn pipeline_barrier2(device: &DeviceContext, command_buffer: vk::CommandBuffer, dependency_info: &vk::DependencyInfoKHR) -> Result<(), vk::Result> {
+    let synchronization_2 = device.get_extension::<ash::extensions::khr::Synchronization2>()
+        .ok_or(vk::Result::ERROR_EXTENSION_NOT_PRESENT)?;
+
+    unsafe { synchronization_2.cmd_pipeline_barrier2(command_buffer, dependency_info) };
+    Ok(())
+}
+
+/// Records a single image barrier using [`pipeline_barrier2`].
+pub fn image_barrier2(device: &DeviceContext, command_buffer: vk::CommandBuffer, barrier: ImageBarrierBuilder) -> Result<(), vk::Result> {
+    let barriers = [barrier.build()];
+    let dependency_info = vk::DependencyInfoKHR::builder()
+        .image_memory_barriers(&barriers);
+
+    pipeline_barrier2(device, command_buffer, &dependency_info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ash::vk::Handle;
+
+    #[test]
+    fn image_barrier() {
+        let image = vk::Image::from_raw(1);
+        let range = ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            mip_level_count: 1,
+            base_array_layer: 0,
+            array_layer_count: 1,
+        };
+
+        let barrier = ImageBarrierBuilder::new(image, range)
+            .layouts(vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .src(vk::PipelineStageFlags2KHR::NONE, vk::AccessFlags2KHR::NONE)
+            .dst(vk::PipelineStageFlags2KHR::TRANSFER, vk::AccessFlags2KHR::TRANSFER_WRITE)
+            .build();
+
+        assert_eq!(barrier.image, image);
+        assert_eq!(barrier.new_layout, vk::ImageLayout::TRANSFER_DST_OPTIMAL);
+        assert_eq!(barrier.dst_access_mask, vk::AccessFlags2KHR::TRANSFER_WRITE);
+        assert_eq!(barrier.src_queue_family_index, vk::QUEUE_FAMILY_IGNORED);
+        assert_eq!(barrier.subresource_range.aspect_mask, vk::ImageAspectFlags::COLOR);
+    }
+
+    #[test]
+    fn compute_sample_barriers() {
+        let image = vk::Image::from_raw(1);
+        let range = ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            mip_level_count: 1,
+            base_array_layer: 0,
+            array_layer_count: 1,
+        };
+
+        let to_sample = ImageBarrierBuilder::compute_write_to_sample(image, range).build();
+        let to_write = ImageBarrierBuilder::sample_to_compute_write(image, range).build();
+
+        assert_eq!(to_sample.old_layout, to_write.new_layout);
+        assert_eq!(to_sample.new_layout, to_write.old_layout);
+        assert_eq!(to_sample.src_stage_mask, to_write.dst_stage_mask);
+        assert_eq!(to_sample.dst_stage_mask, to_write.src_stage_mask);
+    }
+
+    #[test]
+    fn queue_transfer() {
+        let buffer = vk::Buffer::from_raw(1);
+
+        let same_queue = BufferBarrierBuilder::whole(buffer).build();
+        assert_eq!(same_queue.src_queue_family_index, vk::QUEUE_FAMILY_IGNORED);
+        assert_eq!(same_queue.dst_queue_family_index, vk::QUEUE_FAMILY_IGNORED);
+
+        let same_family = BufferBarrierBuilder::whole(buffer).queue_transfer(1, 1).build();
+        assert_eq!(same_family.src_queue_family_index, vk::QUEUE_FAMILY_IGNORED);
+        assert_eq!(same_family.dst_queue_family_index, vk::QUEUE_FAMILY_IGNORED);
+
+        let cross_queue = BufferBarrierBuilder::whole(buffer).queue_transfer(0, 2).build();
+        assert_eq!(cross_queue.src_queue_family_index, 0);
+        assert_eq!(cross_queue.dst_queue_family_index, 2);
+    }
+}
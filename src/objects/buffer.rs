@@ -1,5 +1,8 @@
 use ash::vk;
 
+use crate::rosella::DeviceContext;
+
+/// The shape of a buffer. Usage flags are not part of the spec and are provided through [`BufferCreateDesc`].
 #[derive(Copy, Clone, Debug)]
 pub struct BufferSpec {
     pub size: u64,
@@ -13,29 +16,143 @@ impl BufferSpec {
     pub const fn get_size(&self) -> u64 {
         self.size
     }
+
+    pub const fn with_usage(self, usage_flags: vk::BufferUsageFlags) -> BufferCreateDesc {
+        BufferCreateDesc::new(self, usage_flags)
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
+/// A byte range inside a buffer. Ranges are ordered by their offset and then by their length.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct BufferRange {
     pub offset: u64,
     pub length: u64,
 }
 
+impl BufferRange {
+    pub const fn new(offset: u64, length: u64) -> Self {
+        BufferRange { offset, length }
+    }
+
+    /// Returns the first byte after the range.
+    pub const fn end(&self) -> u64 {
+        self.offset.saturating_add(self.length)
+    }
+
+    /// Returns true if the range contains the byte at `offset`.
+    pub const fn contains(&self, offset: u64) -> bool {
+        offset >= self.offset && offset < self.end()
+    }
+
+    /// Returns true if the two ranges share at least one byte. Ranges that only touch do not overlap.
+    pub const fn overlaps(&self, other: &BufferRange) -> bool {
+        self.offset < other.end() && other.offset < self.end()
+    }
+
+    /// Returns the range of bytes contained in both ranges or [`None`] if they do not overlap.
+    pub fn intersection(&self, other: &BufferRange) -> Option<BufferRange> {
+        if self.overlaps(other) {
+            let offset = self.offset.max(other.offset);
+            let end = self.end().min(other.end());
+            Some(BufferRange::new(offset, end - offset))
+        } else {
+            None
+        }
+    }
+}
+
 #[non_exhaustive]
 pub struct BufferMeta {
 
 }
 
+/// Combines the shape of a buffer with the capabilities it is created with.
 #[non_exhaustive]
 pub struct BufferCreateDesc {
-    pub size: u64,
+    pub spec: BufferSpec,
     pub usage_flags: vk::BufferUsageFlags,
 }
 
 impl BufferCreateDesc {
-    pub fn new_simple(size: u64, usage_flags: vk::BufferUsageFlags) -> Self {
-        BufferCreateDesc { size, usage_flags }
+    pub const fn new(spec: BufferSpec, usage_flags: vk::BufferUsageFlags) -> Self {
+        BufferCreateDesc { spec, usage_flags }
+    }
+
+    pub const fn new_simple(size: u64, usage_flags: vk::BufferUsageFlags) -> Self {
+        Self::new(BufferSpec::new(size), usage_flags)
+    }
+
+    /// Returns the format features the format of a texel buffer view of this buffer must support.
+    pub fn required_texel_format_features(&self) -> vk::FormatFeatureFlags {
+        texel_format_features(self.usage_flags)
+    }
+}
+
+/// Returns the format features needed to create texel buffer views for a buffer with the usage
+/// flags.
+pub fn texel_format_features(usage_flags: vk::BufferUsageFlags) -> vk::FormatFeatureFlags {
+    let mut features = vk::FormatFeatureFlags::empty();
+    if usage_flags.contains(vk::BufferUsageFlags::UNIFORM_TEXEL_BUFFER) {
+        features |= vk::FormatFeatureFlags::UNIFORM_TEXEL_BUFFER;
+    }
+    if usage_flags.contains(vk::BufferUsageFlags::STORAGE_TEXEL_BUFFER) {
+        features |= vk::FormatFeatureFlags::STORAGE_TEXEL_BUFFER;
+    }
+    features
+}
+
+/// An error returned when creating a texel buffer view.
+#[derive(Debug)]
+pub enum BufferViewError {
+    /// The buffer has neither `UNIFORM_TEXEL_BUFFER` nor `STORAGE_TEXEL_BUFFER` usage.
+    NotATexelBuffer,
+    /// The format does not support the texel buffer usage of the buffer.
+    UnsupportedFormat {
+        format: vk::Format,
+        missing: vk::FormatFeatureFlags,
+    },
+    Vulkan(vk::Result),
+}
+
+impl From<vk::Result> for BufferViewError {
+    fn from(err: vk::Result) -> Self {
+        BufferViewError::Vulkan(err)
+    }
+}
+
+/// Verifies that the texel buffer usage of a buffer with `usage_flags` is supported by the format
+/// given its buffer features.
+pub fn validate_texel_buffer_format(format: vk::Format, buffer_features: vk::FormatFeatureFlags, usage_flags: vk::BufferUsageFlags) -> Result<(), BufferViewError> {
+    let required = texel_format_features(usage_flags);
+    if required.is_empty() {
+        return Err(BufferViewError::NotATexelBuffer);
     }
+
+    let missing = required & !buffer_features;
+    if !missing.is_empty() {
+        return Err(BufferViewError::UnsupportedFormat { format, missing });
+    }
+    Ok(())
+}
+
+/// Creates a texel buffer view which must be destroyed by the caller.
+///
+/// `usage_flags` must be the usage flags the buffer was created with. The format is validated
+/// against the buffer features reported by the device.
+pub fn create_buffer_view(device: &DeviceContext, buffer: vk::Buffer, usage_flags: vk::BufferUsageFlags, desc: &BufferViewCreateDesc) -> Result<vk::BufferView, BufferViewError> {
+    let format = desc.format.get_format();
+    let properties = unsafe {
+        device.get_instance().vk().get_physical_device_format_properties(*device.get_physical_device(), format)
+    };
+    validate_texel_buffer_format(format, properties.buffer_features, usage_flags)?;
+
+    let create_info = vk::BufferViewCreateInfo::builder()
+        .buffer(buffer)
+        .format(format)
+        .offset(desc.range.offset)
+        .range(desc.range.length);
+
+    Ok(unsafe { device.vk().create_buffer_view(&create_info, None) }?)
 }
 
 #[non_exhaustive]
@@ -48,4 +165,68 @@ impl BufferViewCreateDesc {
     pub fn new_simple(range: BufferRange, format: &'static crate::objects::Format) -> Self {
         Self { range, format }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adjacent_ranges() {
+        let a = BufferRange::new(0, 16);
+        let b = BufferRange::new(16, 16);
+
+        assert!(!a.overlaps(&b));
+        assert!(!b.overlaps(&a));
+        assert_eq!(a.intersection(&b), None);
+        assert!(a.contains(15));
+        assert!(!a.contains(16));
+        assert!(b.contains(16));
+    }
+
+    #[test]
+    fn nested_ranges() {
+        let outer = BufferRange::new(8, 64);
+        let inner = BufferRange::new(16, 8);
+
+        assert!(outer.overlaps(&inner));
+        assert!(inner.overlaps(&outer));
+        assert_eq!(outer.intersection(&inner), Some(inner));
+        assert_eq!(inner.intersection(&outer), Some(inner));
+    }
+
+    #[test]
+    fn partial_overlap() {
+        let a = BufferRange::new(0, 32);
+        let b = BufferRange::new(24, 32);
+
+        assert_eq!(a.intersection(&b), Some(BufferRange::new(24, 8)));
+        assert!(a < b);
+    }
+
+    #[test]
+    fn texel_buffer_validation() {
+        let usage = vk::BufferUsageFlags::UNIFORM_TEXEL_BUFFER | vk::BufferUsageFlags::STORAGE_TEXEL_BUFFER;
+        let desc = BufferCreateDesc::new_simple(64, usage);
+        assert_eq!(desc.required_texel_format_features(), vk::FormatFeatureFlags::UNIFORM_TEXEL_BUFFER | vk::FormatFeatureFlags::STORAGE_TEXEL_BUFFER);
+
+        assert!(validate_texel_buffer_format(vk::Format::R32_SFLOAT, desc.required_texel_format_features(), usage).is_ok());
+        match validate_texel_buffer_format(vk::Format::R32_SFLOAT, vk::FormatFeatureFlags::UNIFORM_TEXEL_BUFFER, usage) {
+            Err(BufferViewError::UnsupportedFormat { missing, .. }) => assert_eq!(missing, vk::FormatFeatureFlags::STORAGE_TEXEL_BUFFER),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(matches!(
+            validate_texel_buffer_format(vk::Format::R32_SFLOAT, desc.required_texel_format_features(), vk::BufferUsageFlags::VERTEX_BUFFER),
+            Err(BufferViewError::NotATexelBuffer)
+        ));
+    }
+
+    #[test]
+    fn whole_size() {
+        let whole = BufferRange::new(0, vk::WHOLE_SIZE);
+        let range = BufferRange::new(128, 16);
+
+        assert!(whole.contains(u64::MAX - 1));
+        assert_eq!(whole.intersection(&range), Some(range));
+    }
+}
@@ -0,0 +1,69 @@
+use ash::prelude::VkResult;
+use ash::vk;
+
+use crate::rosella::DeviceContext;
+
+/// Creates a new fence which must be destroyed by the caller. See [`FenceHandle`] for a fence
+/// which is destroyed automatically.
+pub fn create_fence(device: &DeviceContext, signaled: bool) -> VkResult<vk::Fence> {
+    let flags = if signaled { vk::FenceCreateFlags::SIGNALED } else { vk::FenceCreateFlags::empty() };
+    let create_info = vk::FenceCreateInfo::builder().flags(flags);
+
+    unsafe { device.vk().create_fence(&create_info, None) }
+}
+
+/// Waits for the fences to be signaled.
+///
+/// Returns false if the timeout (in nanoseconds) expired before the wait condition was met.
+pub fn wait_for_fences(device: &DeviceContext, fences: &[vk::Fence], wait_all: bool, timeout: u64) -> VkResult<bool> {
+    match unsafe { device.vk().wait_for_fences(fences, wait_all, timeout) } {
+        Ok(_) => Ok(true),
+        Err(vk::Result::TIMEOUT) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+pub fn reset_fences(device: &DeviceContext, fences: &[vk::Fence]) -> VkResult<()> {
+    unsafe { device.vk().reset_fences(fences) }
+}
+
+/// A fence which is destroyed when dropped.
+pub struct FenceHandle {
+    device: DeviceContext,
+    fence: vk::Fence,
+}
+
+impl FenceHandle {
+    pub fn new(device: DeviceContext, signaled: bool) -> VkResult<Self> {
+        let fence = create_fence(&device, signaled)?;
+
+        Ok(Self {
+            device,
+            fence,
+        })
+    }
+
+    pub fn get_handle(&self) -> vk::Fence {
+        self.fence
+    }
+
+    /// Waits for the fence to be signaled. Returns false if the timeout expired.
+    pub fn wait(&self, timeout: u64) -> VkResult<bool> {
+        wait_for_fences(&self.device, std::slice::from_ref(&self.fence), true, timeout)
+    }
+
+    pub fn reset(&self) -> VkResult<()> {
+        reset_fences(&self.device, std::slice::from_ref(&self.fence))
+    }
+
+    /// Returns true if the fence is signaled.
+    pub fn is_signaled(&self) -> VkResult<bool> {
+        unsafe { self.device.vk().get_fence_status(self.fence) }
+    }
+}
+
+impl Drop for FenceHandle {
+    fn drop(&mut self) {
+        unsafe { self.device.vk().destroy_fence(self.fence, None) };
+    }
+}
@@ -30,6 +30,18 @@ impl ObjectType {
     pub const EVENT: u8 = 7u8;
 }
 
+/// Identifies a single object inside a object set.
+///
+/// Ids are compared, ordered and hashed by their underlying [`UUID`] only. A typed id and the
+/// [`GenericId`] returned by [`ObjectId::as_generic`] therefore hash identically, which means maps
+/// can be keyed on either as long as every lookup into the same map uses the same id type.
+/// Conversion into a [`GenericId`] is always possible using [`From`], the reverse is done using
+/// [`ObjectId::downcast`].
+///
+/// The global id of the owning object set is stored unmodified in the [`UUID`] and is never
+/// packed, so it can not wrap around (see [`GlobalId::new`]). The local id packs the object index
+/// into the lower [`ObjectId::INDEX_BITS`] bits and the object type into the upper 8 bits. Creating
+/// an id with an index above [`ObjectId::INDEX_MAX`] panics.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ObjectId<const TYPE: u8>(UUID);
 
@@ -157,4 +169,86 @@ pub type ImageId = ObjectId<{ ObjectType::IMAGE }>;
 pub type ImageViewId = ObjectId<{ ObjectType::IMAGE_VIEW }>;
 pub type BinarySemaphoreId = ObjectId<{ ObjectType::BINARY_SEMAPHORE }>;
 pub type TimelineSemaphoreId = ObjectId<{ ObjectType::TIMELINE_SEMAPHORE }>;
-pub type EventId = ObjectId<{ ObjectType::EVENT }>;
\ No newline at end of file
+pub type EventId = ObjectId<{ ObjectType::EVENT }>;
+
+macro_rules! impl_into_generic_id {
+    ($($id:ty),+) => {
+        $(
+        impl From<$id> for GenericId {
+            fn from(id: $id) -> Self {
+                id.as_generic()
+            }
+        }
+        )+
+    }
+}
+
+impl_into_generic_id!(BufferId, BufferViewId, ImageId, ImageViewId, BinarySemaphoreId, TimelineSemaphoreId, EventId);
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn generic_round_trip() {
+        let id = BufferId::new(GlobalId::new(), 3);
+        let generic = id.as_generic();
+
+        assert_eq!(generic.get_type(), ObjectType::BUFFER);
+        assert_eq!(generic.get_index(), 3);
+        assert_eq!(GenericId::from(id), generic);
+        assert_eq!(generic.downcast::<{ ObjectType::BUFFER }>(), Some(id));
+        assert_eq!(generic.downcast::<{ ObjectType::IMAGE }>(), None);
+    }
+
+    #[test]
+    fn generic_hash_eq() {
+        let id = ImageId::new(GlobalId::new(), 7);
+
+        assert_eq!(hash_of(&id), hash_of(&id.as_generic()));
+
+        let mut map = HashMap::new();
+        map.insert(GenericId::from(id), 1u32);
+        assert_eq!(map.get(&id.as_generic()), Some(&1u32));
+    }
+
+    #[test]
+    fn generic_ordering() {
+        let global = GlobalId::new();
+        let id1 = BufferId::new(global, 1);
+        let id2 = BufferId::new(global, 2);
+
+        assert!(id1 < id2);
+        assert!(id1.as_generic() < id2.as_generic());
+    }
+
+    #[test]
+    fn max_values_round_trip() {
+        let global = GlobalId::from_raw(u64::MAX / 2u64);
+        let id = ImageViewId::new(global, ImageViewId::INDEX_MAX);
+
+        assert_eq!(id.get_global_id(), global);
+        assert_eq!(id.get_index(), ImageViewId::INDEX_MAX);
+        assert_eq!(id.get_type(), ObjectType::IMAGE_VIEW);
+
+        let generic = id.as_generic();
+        assert_eq!(generic.get_global_id(), global);
+        assert_eq!(generic.get_index(), ImageViewId::INDEX_MAX);
+        assert_eq!(generic.downcast::<{ ObjectType::IMAGE_VIEW }>(), Some(id));
+    }
+
+    #[test]
+    #[should_panic(expected = "Local id out of range")]
+    fn index_out_of_range() {
+        BufferId::new(GlobalId::new(), BufferId::INDEX_MAX + 1);
+    }
+}
@@ -111,6 +111,7 @@ impl ImageSize {
     }
 }
 
+/// The shape of an image. Usage flags are not part of the spec and are provided through [`ImageCreateDesc`].
 #[derive(Copy, Clone)]
 pub struct ImageSpec {
     pub format: &'static crate::objects::Format,
@@ -142,9 +143,43 @@ impl ImageSpec {
     pub const fn get_sample_count(&self) -> ash::vk::SampleCountFlags {
         self.sample_count
     }
+
+    pub const fn with_usage(self, usage_flags: vk::ImageUsageFlags) -> ImageCreateDesc {
+        ImageCreateDesc::new(self, usage_flags)
+    }
+
+    /// Returns a range covering all mip levels and array layers of the image. The aspect mask is
+    /// inferred from the format.
+    pub fn full_subresource_range(&self) -> ImageSubresourceRange {
+        ImageSubresourceRange {
+            aspect_mask: self.format.aspect_flags(),
+            base_mip_level: 0,
+            mip_level_count: self.size.get_mip_levels(),
+            base_array_layer: 0,
+            array_layer_count: self.size.get_array_layers(),
+        }
+    }
+
+    /// Returns a range covering all array layers of a single mip level.
+    pub fn single_mip(&self, level: u32) -> ImageSubresourceRange {
+        ImageSubresourceRange {
+            base_mip_level: level,
+            mip_level_count: 1,
+            ..self.full_subresource_range()
+        }
+    }
+
+    /// Returns a range covering all mip levels of a single array layer.
+    pub fn single_layer(&self, layer: u32) -> ImageSubresourceRange {
+        ImageSubresourceRange {
+            base_array_layer: layer,
+            array_layer_count: 1,
+            ..self.full_subresource_range()
+        }
+    }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct ImageSubresourceRange {
     pub aspect_mask: ash::vk::ImageAspectFlags,
     pub base_mip_level: u32,
@@ -170,21 +205,199 @@ pub struct ImageMeta {
 
 }
 
+/// The queue families an image can be accessed from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImageSharing {
+    /// The image is owned by a single queue family at a time
+    Exclusive,
+    /// The image can be accessed concurrently from all listed queue families
+    Concurrent(Box<[u32]>),
+}
+
+impl ImageSharing {
+    pub fn get_sharing_mode(&self) -> vk::SharingMode {
+        match self {
+            ImageSharing::Exclusive => vk::SharingMode::EXCLUSIVE,
+            ImageSharing::Concurrent(_) => vk::SharingMode::CONCURRENT,
+        }
+    }
+
+    pub fn get_queue_family_indices(&self) -> &[u32] {
+        match self {
+            ImageSharing::Exclusive => &[],
+            ImageSharing::Concurrent(families) => families,
+        }
+    }
+}
+
+/// Combines the shape of an image with the capabilities it is created with.
 #[non_exhaustive]
 pub struct ImageCreateDesc {
     pub spec: ImageSpec,
     pub usage_flags: vk::ImageUsageFlags,
+    pub tiling: vk::ImageTiling,
+    pub sharing: ImageSharing,
 }
 
 impl ImageCreateDesc {
-    pub fn new_simple(spec: ImageSpec, usage: vk::ImageUsageFlags) -> Self {
-        Self{ spec, usage_flags: usage }
+    /// Creates a description with optimal tiling and exclusive sharing.
+    pub const fn new(spec: ImageSpec, usage_flags: vk::ImageUsageFlags) -> Self {
+        Self{ spec, usage_flags, tiling: vk::ImageTiling::OPTIMAL, sharing: ImageSharing::Exclusive }
+    }
+
+    pub const fn new_simple(spec: ImageSpec, usage: vk::ImageUsageFlags) -> Self {
+        Self::new(spec, usage)
+    }
+
+    pub const fn with_tiling(mut self, tiling: vk::ImageTiling) -> Self {
+        self.tiling = tiling;
+        self
+    }
+
+    /// Makes the image accessible concurrently from all provided queue families. If less than 2
+    /// unique families are provided exclusive sharing is used.
+    pub fn with_concurrent_sharing(mut self, queue_families: &[u32]) -> Self {
+        let mut families = queue_families.to_vec();
+        families.sort_unstable();
+        families.dedup();
+
+        self.sharing = if families.len() < 2 {
+            ImageSharing::Exclusive
+        } else {
+            ImageSharing::Concurrent(families.into_boxed_slice())
+        };
+        self
+    }
+
+    /// Returns the format features the image format must support for the usage flags of this
+    /// description.
+    pub fn required_format_features(&self) -> vk::FormatFeatureFlags {
+        let mut features = vk::FormatFeatureFlags::empty();
+        let usage = self.usage_flags;
+
+        if usage.contains(vk::ImageUsageFlags::SAMPLED) {
+            features |= vk::FormatFeatureFlags::SAMPLED_IMAGE;
+        }
+        if usage.contains(vk::ImageUsageFlags::STORAGE) {
+            features |= vk::FormatFeatureFlags::STORAGE_IMAGE;
+        }
+        if usage.contains(vk::ImageUsageFlags::COLOR_ATTACHMENT) {
+            features |= vk::FormatFeatureFlags::COLOR_ATTACHMENT;
+        }
+        if usage.contains(vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT) {
+            features |= vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT;
+        }
+        if usage.contains(vk::ImageUsageFlags::TRANSFER_SRC) {
+            features |= vk::FormatFeatureFlags::TRANSFER_SRC;
+        }
+        if usage.contains(vk::ImageUsageFlags::TRANSFER_DST) {
+            features |= vk::FormatFeatureFlags::TRANSFER_DST;
+        }
+
+        features
     }
 }
 
+#[derive(Copy, Clone)]
 pub struct ImageViewCreateDesc {
     pub view_type: vk::ImageViewType,
     pub format: &'static crate::objects::Format,
     pub components: vk::ComponentMapping,
     pub subresource_range: ImageSubresourceRange,
-}
\ No newline at end of file
+}
+
+impl ImageViewCreateDesc {
+    /// Creates a view description with the identity component mapping
+    pub fn new(view_type: vk::ImageViewType, format: &'static crate::objects::Format, subresource_range: ImageSubresourceRange) -> Self {
+        Self {
+            view_type,
+            format,
+            components: vk::ComponentMapping::default(),
+            subresource_range,
+        }
+    }
+
+    /// Creates a view description covering the entire image with the identity component mapping.
+    pub fn for_spec(view_type: vk::ImageViewType, spec: &ImageSpec) -> Self {
+        Self::new(view_type, spec.format, spec.full_subresource_range())
+    }
+
+    /// Creates a view description covering all mip levels and array layers with the identity
+    /// component mapping. The aspect mask is inferred from the format.
+    pub fn new_full(view_type: vk::ImageViewType, format: &'static crate::objects::Format, mip_level_count: u32, array_layer_count: u32) -> Self {
+        Self::new(view_type, format, ImageSubresourceRange {
+            aspect_mask: format.aspect_flags(),
+            base_mip_level: 0,
+            mip_level_count,
+            base_array_layer: 0,
+            array_layer_count,
+        })
+    }
+}
+
+impl PartialEq for ImageViewCreateDesc {
+    fn eq(&self, other: &Self) -> bool {
+        self.view_type == other.view_type &&
+            self.format == other.format &&
+            self.components.r == other.components.r &&
+            self.components.g == other.components.g &&
+            self.components.b == other.components.b &&
+            self.components.a == other.components.a &&
+            self.subresource_range == other.subresource_range
+    }
+}
+
+impl Eq for ImageViewCreateDesc {
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::Format;
+
+    #[test]
+    fn view_desc_infers_aspect() {
+        let color = ImageViewCreateDesc::new_full(vk::ImageViewType::TYPE_2D, &Format::R8G8B8A8_UNORM, 1, 1);
+        assert_eq!(color.subresource_range.aspect_mask, vk::ImageAspectFlags::COLOR);
+
+        let depth_stencil = ImageViewCreateDesc::new_full(vk::ImageViewType::TYPE_2D, &Format::D24_UNORM_S8_UINT, 1, 1);
+        assert_eq!(depth_stencil.subresource_range.aspect_mask, vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL);
+
+        assert!(color == ImageViewCreateDesc::new_full(vk::ImageViewType::TYPE_2D, &Format::R8G8B8A8_UNORM, 1, 1));
+        assert!(color != depth_stencil);
+    }
+
+    #[test]
+    fn spec_subresource_ranges() {
+        let spec = ImageSpec::new_single_sample(ImageSize::make_2d_array_mip(8, 8, 3, 4), &Format::D32_SFLOAT);
+
+        let full = spec.full_subresource_range();
+        assert_eq!(full.aspect_mask, vk::ImageAspectFlags::DEPTH);
+        assert_eq!((full.base_mip_level, full.mip_level_count), (0, 4));
+        assert_eq!((full.base_array_layer, full.array_layer_count), (0, 3));
+
+        let mip = spec.single_mip(2);
+        assert_eq!((mip.base_mip_level, mip.mip_level_count), (2, 1));
+        assert_eq!(mip.array_layer_count, 3);
+
+        let layer = spec.single_layer(1);
+        assert_eq!((layer.base_array_layer, layer.array_layer_count), (1, 1));
+        assert_eq!(layer.mip_level_count, 4);
+    }
+
+    #[test]
+    fn create_desc_sharing() {
+        let spec = ImageSpec::new_single_sample(ImageSize::make_2d(4, 4), &Format::R8G8B8A8_UNORM);
+
+        let desc = ImageCreateDesc::new(spec, vk::ImageUsageFlags::SAMPLED);
+        assert_eq!(desc.tiling, vk::ImageTiling::OPTIMAL);
+        assert_eq!(desc.sharing, ImageSharing::Exclusive);
+
+        let desc = desc.with_concurrent_sharing(&[2, 0, 2]);
+        assert_eq!(desc.sharing.get_sharing_mode(), vk::SharingMode::CONCURRENT);
+        assert_eq!(desc.sharing.get_queue_family_indices(), &[0, 2]);
+
+        let desc = desc.with_concurrent_sharing(&[1, 1]);
+        assert_eq!(desc.sharing, ImageSharing::Exclusive);
+    }
+}
@@ -1,23 +1,25 @@
-use std::mem::ManuallyDrop;
-use std::sync::Mutex;
+#[cfg(not(feature = "gpu-allocator-backend"))]
+compile_error!("No memory allocator backend selected. Enable the \"gpu-allocator-backend\" feature.");
 
-use ash::vk;
-use gpu_allocator::MemoryLocation;
-use gpu_allocator::vulkan::{AllocationCreateDesc, AllocatorCreateDesc};
-
-use crate::device::DeviceContext;
+#[cfg(feature = "gpu-allocator-backend")]
+pub use gpu_allocator_backend::Allocation;
+#[cfg(feature = "gpu-allocator-backend")]
+pub(super) use gpu_allocator_backend::Allocator;
 
 #[derive(Debug)]
 pub enum AllocationError {
+    #[cfg(feature = "gpu-allocator-backend")]
     GpuAllocator(gpu_allocator::AllocationError),
 }
 
+#[cfg(feature = "gpu-allocator-backend")]
 impl From<gpu_allocator::AllocationError> for AllocationError {
     fn from(err: gpu_allocator::AllocationError) -> Self {
         Self::GpuAllocator(err)
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum AllocationStrategy {
     /// Automatically select memory that is only used by the gpu
     AutoGpuOnly,
@@ -26,104 +28,146 @@ pub enum AllocationStrategy {
     AutoGpuCpu,
 }
 
-/// Manages memory allocation for vulkan object
-///
-/// Currently just uses the [`gpu_allocator::vulkan::Allocator`] struct.
-pub(super) struct Allocator {
-    device: DeviceContext,
+/// The allocator backend using the gpu-allocator crate
+#[cfg(feature = "gpu-allocator-backend")]
+mod gpu_allocator_backend {
+    use std::mem::ManuallyDrop;
+    use std::sync::Mutex;
 
-    // We need to ensure the allocator is dropped before the instance and device are
-    allocator: ManuallyDrop<Mutex<gpu_allocator::vulkan::Allocator>>
-}
+    use ash::vk;
+    use gpu_allocator::MemoryLocation;
+    use gpu_allocator::vulkan::{AllocationCreateDesc, AllocatorCreateDesc};
 
-impl Allocator {
-    pub fn new(device: DeviceContext) -> Self {
-        let allocator = gpu_allocator::vulkan::Allocator::new(&AllocatorCreateDesc{
-            instance: device.get_instance().vk().clone(),
-            device: device.vk().clone(),
-            physical_device: device.get_physical_device().clone(),
-            debug_settings: Default::default(),
-            buffer_device_address: false
-        }).unwrap();
-
-        Self {
-            device,
-            allocator: ManuallyDrop::new(Mutex::new(allocator)),
-        }
+    use crate::device::DeviceContext;
+
+    use super::{AllocationError, AllocationStrategy};
+
+    /// Manages memory allocation for vulkan object
+    ///
+    /// The backend is selected through cargo features. Currently only the `gpu-allocator-backend`
+    /// feature (enabled by default) is available which uses the [`gpu_allocator::vulkan::Allocator`]
+    /// struct.
+    pub(in crate::objects::manager) struct Allocator {
+        device: DeviceContext,
+
+        // We need to ensure the allocator is dropped before the instance and device are
+        allocator: ManuallyDrop<Mutex<gpu_allocator::vulkan::Allocator>>
     }
 
-    pub fn allocate_buffer_memory(&self, buffer: vk::Buffer, strategy: &AllocationStrategy) -> Result<Allocation, AllocationError> {
-        let location = match strategy {
-            AllocationStrategy::AutoGpuOnly => MemoryLocation::GpuOnly,
-            AllocationStrategy::AutoGpuCpu => MemoryLocation::CpuToGpu,
-        };
+    impl Allocator {
+        pub fn new(device: DeviceContext) -> Result<Self, AllocationError> {
+            let allocator = gpu_allocator::vulkan::Allocator::new(&AllocatorCreateDesc{
+                instance: device.get_instance().vk().clone(),
+                device: device.vk().clone(),
+                physical_device: device.get_physical_device().clone(),
+                debug_settings: Default::default(),
+                buffer_device_address: false
+            })?;
+
+            Ok(Self {
+                device,
+                allocator: ManuallyDrop::new(Mutex::new(allocator)),
+            })
+        }
+
+        pub fn allocate_buffer_memory(&self, buffer: vk::Buffer, strategy: &AllocationStrategy) -> Result<Allocation, AllocationError> {
+            let location = match strategy {
+                AllocationStrategy::AutoGpuOnly => MemoryLocation::GpuOnly,
+                AllocationStrategy::AutoGpuCpu => MemoryLocation::CpuToGpu,
+            };
 
-        let requirements = unsafe {
-            self.device.vk().get_buffer_memory_requirements(buffer)
-        };
+            let requirements = unsafe {
+                self.device.vk().get_buffer_memory_requirements(buffer)
+            };
 
-        let alloc_desc = AllocationCreateDesc{
-            name: "",
-            requirements,
-            location,
-            linear: true
-        };
+            let alloc_desc = AllocationCreateDesc{
+                name: "",
+                requirements,
+                location,
+                linear: true
+            };
 
-        let alloc = self.allocator.lock().unwrap().allocate(&alloc_desc)?;
+            let alloc = self.allocator.lock().unwrap().allocate(&alloc_desc)?;
 
-        Ok(Allocation::new(alloc))
-    }
+            Ok(Allocation::new(alloc))
+        }
+
+        pub fn allocate_image_memory(&self, image: vk::Image, strategy: &AllocationStrategy) -> Result<Allocation, AllocationError> {
+            let location = match strategy {
+                AllocationStrategy::AutoGpuOnly => MemoryLocation::GpuOnly,
+                AllocationStrategy::AutoGpuCpu => MemoryLocation::CpuToGpu,
+            };
+
+            let requirements = unsafe {
+                self.device.vk().get_image_memory_requirements(image)
+            };
+
+            let alloc_desc = AllocationCreateDesc{
+                name: "",
+                requirements,
+                location,
+                // If image is accessed by the cpu it has to be linear
+                linear: location == MemoryLocation::CpuToGpu,
+            };
+
+            let alloc = self.allocator.lock().unwrap().allocate(&alloc_desc)?;
 
-    pub fn allocate_image_memory(&self, image: vk::Image, strategy: &AllocationStrategy) -> Result<Allocation, AllocationError> {
-        let location = match strategy {
-            AllocationStrategy::AutoGpuOnly => MemoryLocation::GpuOnly,
-            AllocationStrategy::AutoGpuCpu => MemoryLocation::CpuToGpu,
-        };
+            Ok(Allocation::new(alloc))
+        }
+
+        /// Allocates a block of memory satisfying the requirements. Used for suballocation where the
+        /// memory is not bound to a single object.
+        pub fn allocate_memory(&self, requirements: vk::MemoryRequirements, strategy: &AllocationStrategy) -> Result<Allocation, AllocationError> {
+            let location = match strategy {
+                AllocationStrategy::AutoGpuOnly => MemoryLocation::GpuOnly,
+                AllocationStrategy::AutoGpuCpu => MemoryLocation::CpuToGpu,
+            };
 
-        let requirements = unsafe {
-            self.device.vk().get_image_memory_requirements(image)
-        };
+            let alloc_desc = AllocationCreateDesc{
+                name: "",
+                requirements,
+                location,
+                linear: true
+            };
 
-        let alloc_desc = AllocationCreateDesc{
-            name: "",
-            requirements,
-            location,
-            // If image is accessed by the cpu it has to be linear
-            linear: location == MemoryLocation::CpuToGpu,
-        };
+            let alloc = self.allocator.lock().unwrap().allocate(&alloc_desc)?;
 
-        let alloc = self.allocator.lock().unwrap().allocate(&alloc_desc)?;
+            Ok(Allocation::new(alloc))
+        }
 
-        Ok(Allocation::new(alloc))
+        pub fn free(&self, allocation: Allocation) {
+            self.allocator.lock().unwrap().free(allocation.alloc).unwrap()
+        }
     }
 
-    pub fn free(&self, allocation: Allocation) {
-        self.allocator.lock().unwrap().free(allocation.alloc).unwrap()
+    impl Drop for Allocator {
+        fn drop(&mut self) {
+            unsafe { ManuallyDrop::drop(&mut self.allocator) };
+        }
     }
-}
 
-impl Drop for Allocator {
-    fn drop(&mut self) {
-        unsafe { ManuallyDrop::drop(&mut self.allocator) };
+    pub struct Allocation {
+        alloc: gpu_allocator::vulkan::Allocation,
     }
-}
 
-pub struct Allocation {
-    alloc: gpu_allocator::vulkan::Allocation,
-}
+    impl Allocation {
+        fn new(alloc: gpu_allocator::vulkan::Allocation) -> Self {
+            Self {
+                alloc,
+            }
+        }
 
-impl Allocation {
-    fn new(alloc: gpu_allocator::vulkan::Allocation) -> Self {
-        Self {
-            alloc,
+        pub fn memory(&self) -> vk::DeviceMemory {
+            unsafe { self.alloc.memory() }
         }
-    }
 
-    pub fn memory(&self) -> vk::DeviceMemory {
-        unsafe { self.alloc.memory() }
-    }
+        pub fn offset(&self) -> vk::DeviceSize {
+            self.alloc.offset()
+        }
 
-    pub fn offset(&self) -> vk::DeviceSize {
-        self.alloc.offset()
+        /// Returns a pointer to the start of the allocation if it is host visible.
+        pub fn mapped_ptr(&self) -> Option<std::ptr::NonNull<std::ffi::c_void>> {
+            self.alloc.mapped_ptr()
+        }
     }
-}
\ No newline at end of file
+}
@@ -21,26 +21,43 @@
 
 pub(super) mod synchronization_group;
 pub(super) mod object_set;
+pub(super) mod suballocator;
 
 mod allocator;
 
-use std::sync::Arc;
+pub use allocator::AllocationError;
+pub use allocator::AllocationStrategy;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use ash::vk;
 
 use synchronization_group::*;
 use object_set::*;
-use crate::objects::buffer::{BufferCreateDesc, BufferViewCreateDesc};
+use suballocator::*;
+use crate::objects::buffer::{self, BufferCreateDesc, BufferViewCreateDesc, BufferViewError};
 use crate::objects::id;
 use crate::objects::image::{ImageCreateDesc, ImageViewCreateDesc};
 use crate::objects::manager::allocator::*;
 use crate::util::slice_splitter::Splitter;
+use crate::NamedUUID;
 
+/// An error returned when creating the objects of a object set.
 #[derive(Debug)]
-enum ObjectCreateError {
+pub enum ObjectCreateError {
     Vulkan(vk::Result),
     Allocation(AllocationError),
+    /// A view references a object that does not exist
     InvalidReference,
+    /// A buffer view could not be created for the referenced buffer
+    BufferView(BufferViewError),
+    /// The image format does not support the features required for the requested tiling and usage
+    UnsupportedFormatFeatures {
+        format: vk::Format,
+        tiling: vk::ImageTiling,
+        missing: vk::FormatFeatureFlags,
+    },
 }
 
 impl<'s> From<ash::vk::Result> for ObjectCreateError {
@@ -49,12 +66,21 @@ impl<'s> From<ash::vk::Result> for ObjectCreateError {
     }
 }
 
+impl From<BufferViewError> for ObjectCreateError {
+    fn from(err: BufferViewError) -> Self {
+        ObjectCreateError::BufferView(err)
+    }
+}
+
 impl<'s> From<AllocationError> for ObjectCreateError {
     fn from(err: AllocationError) -> Self {
         ObjectCreateError::Allocation(err)
     }
 }
 
+/// The objects and allocations created for a object set
+type CreatedObjects = (Box<[ObjectData]>, Box<[Allocation]>);
+
 struct BufferCreateMetadata<'a> {
     handle: vk::Buffer,
     allocation: Option<Allocation>,
@@ -121,16 +147,19 @@ impl<'a> ObjectCreateMetadata<'a> {
 struct ObjectManagerImpl {
     device: crate::rosella::DeviceContext,
     allocator: Allocator,
+    /// The synchronization group tracking the state of each image
+    image_owners: Mutex<HashMap<id::ImageId, crate::util::id::GlobalId>>,
 }
 
 impl ObjectManagerImpl {
-    fn new(device: crate::rosella::DeviceContext) -> Self {
-        let allocator = Allocator::new(device.clone());
+    fn new(device: crate::rosella::DeviceContext) -> Result<Self, AllocationError> {
+        let allocator = Allocator::new(device.clone())?;
 
-        Self{
+        Ok(Self{
             device,
             allocator,
-        }
+            image_owners: Mutex::new(HashMap::new()),
+        })
     }
 
     /// Creates a timeline semaphore for use in a synchronization group
@@ -187,7 +216,7 @@ impl ObjectManagerImpl {
     fn create_buffer(&self, meta: &mut BufferCreateMetadata) -> Result<(), ObjectCreateError> {
         if meta.handle == vk::Buffer::null() {
             let create_info = vk::BufferCreateInfo::builder()
-                .size(meta.desc.description.size)
+                .size(meta.desc.description.spec.size)
                 .usage(meta.desc.description.usage_flags)
                 .sharing_mode(vk::SharingMode::EXCLUSIVE);
 
@@ -215,7 +244,15 @@ impl ObjectManagerImpl {
                 None => {
                     let index = meta.desc.buffer_id.get_index() as usize;
                     match split.get(index).ok_or(ObjectCreateError::InvalidReference)? {
-                        ObjectCreateMetadata::Buffer(BufferCreateMetadata{ handle, .. }) => *handle,
+                        ObjectCreateMetadata::Buffer(BufferCreateMetadata{ handle, desc, .. }) => {
+                            // The usage of buffers from other sets is not known so only internal buffers are validated
+                            let format = meta.desc.description.format.get_format();
+                            let properties = unsafe {
+                                self.device.get_instance().vk().get_physical_device_format_properties(*self.device.get_physical_device(), format)
+                            };
+                            buffer::validate_texel_buffer_format(format, properties.buffer_features, desc.description.usage_flags)?;
+                            *handle
+                        }
                         _ => return Err(ObjectCreateError::InvalidReference)
                     }
                 }
@@ -236,6 +273,11 @@ impl ObjectManagerImpl {
 
     fn create_image(&self, meta: &mut ImageCreateMetadata) -> Result<(), ObjectCreateError> {
         if meta.handle == vk::Image::null() {
+            let description = &meta.desc.description;
+            if description.tiling == vk::ImageTiling::LINEAR {
+                self.validate_linear_tiling(description)?;
+            }
+
             let create_info = vk::ImageCreateInfo::builder()
                 .image_type(meta.desc.description.spec.size.get_vulkan_type())
                 .format(meta.desc.description.spec.format.get_format())
@@ -243,9 +285,10 @@ impl ObjectManagerImpl {
                 .mip_levels(meta.desc.description.spec.size.get_mip_levels())
                 .array_layers(meta.desc.description.spec.size.get_array_layers())
                 .samples(meta.desc.description.spec.sample_count)
-                .tiling(vk::ImageTiling::OPTIMAL) // TODO we need some way to turn this linear
+                .tiling(description.tiling)
                 .usage(meta.desc.description.usage_flags)
-                .sharing_mode(vk::SharingMode::EXCLUSIVE);
+                .sharing_mode(description.sharing.get_sharing_mode())
+                .queue_family_indices(description.sharing.get_queue_family_indices());
 
             meta.handle = unsafe {
                 self.device.vk().create_image(&create_info.build(), None)
@@ -262,6 +305,21 @@ impl ObjectManagerImpl {
         Ok(())
     }
 
+    /// Verifies that the image format supports the usage of the description with linear tiling.
+    fn validate_linear_tiling(&self, description: &ImageCreateDesc) -> Result<(), ObjectCreateError> {
+        let format = description.spec.format.get_format();
+        let properties = unsafe {
+            self.device.get_instance().vk().get_physical_device_format_properties(*self.device.get_physical_device(), format)
+        };
+
+        let required = description.required_format_features();
+        let missing = required & !properties.linear_tiling_features;
+        if properties.linear_tiling_features.is_empty() || !missing.is_empty() {
+            return Err(ObjectCreateError::UnsupportedFormatFeatures { format, tiling: vk::ImageTiling::LINEAR, missing });
+        }
+        Ok(())
+    }
+
     fn create_image_view(&self, meta: &mut ImageViewCreateMetadata, split: Splitter<ObjectCreateMetadata>) -> Result<(), ObjectCreateError> {
         if meta.handle == vk::ImageView::null() {
             let image = match meta.desc.owning_set.as_ref() {
@@ -369,13 +427,13 @@ impl ObjectManagerImpl {
     }
 
     /// Creates objects for a object request description list
-    fn create_objects(&self, objects: &[ObjectRequestDescription]) -> (Box<[ObjectData]>, Box<[Allocation]>) {
+    fn create_objects(&self, objects: &[ObjectRequestDescription]) -> Result<CreatedObjects, ObjectCreateError> {
         let mut objects = self.generate_objects_metadata(objects);
         self.create_objects_for_metadata(objects.as_mut_slice()).map_err(|err| {
             self.destroy_temporary_objects(objects.as_mut_slice()); err
-        }).unwrap();
+        })?;
 
-        self.flatten_object_metadata(objects)
+        Ok(self.flatten_object_metadata(objects))
     }
 
     /// Destroys objects previously created using [`ObjectManagerImpl::create_objects`]
@@ -416,13 +474,24 @@ pub struct ObjectManager(Arc<ObjectManagerImpl>);
 
 impl ObjectManager {
     /// Creates a new ObjectManager
-    pub fn new(device: crate::rosella::DeviceContext) -> Self {
-        Self(Arc::new(ObjectManagerImpl::new(device)))
+    ///
+    /// Fails if the memory allocator could not be initialized.
+    pub fn new(device: crate::rosella::DeviceContext) -> Result<Self, AllocationError> {
+        Ok(Self(Arc::new(ObjectManagerImpl::new(device)?)))
     }
 
     /// Creates a new synchronization group managed by this object manager
     pub fn create_synchronization_group(&self) -> SynchronizationGroup {
-        SynchronizationGroup::new(self.clone(), self.0.create_timeline_semaphore(0u64))
+        SynchronizationGroup::new(self.clone(), self.0.create_timeline_semaphore(0u64), None)
+    }
+
+    /// Creates a new synchronization group with a name used in errors and as the debug name of
+    /// its semaphore.
+    pub fn create_named_synchronization_group(&self, name: NamedUUID) -> SynchronizationGroup {
+        let semaphore = self.0.create_timeline_semaphore(0u64);
+        self.0.device.set_debug_name(semaphore, name.get_name());
+
+        SynchronizationGroup::new(self.clone(), semaphore, Some(name))
     }
 
     /// Creates a new object set builder
@@ -439,12 +508,18 @@ impl ObjectManager {
         ObjectSetBuilder::new_no_group(self.clone())
     }
 
+    /// Creates a suballocator owning a single memory block of `size` bytes. All buffers created by
+    /// the suballocator use the provided usage flags.
+    pub fn create_suballocator(&self, size: u64, usage_flags: vk::BufferUsageFlags, location: AllocationStrategy, strategy: SubAllocationStrategy) -> Result<SubAllocator, SubAllocatorError> {
+        SubAllocator::new(self.clone(), size, usage_flags, location, strategy)
+    }
+
     // Internal function that destroys a semaphore created for a synchronization group
     fn destroy_semaphore(&self, semaphore: vk::Semaphore) {
         self.0.destroy_semaphore(semaphore)
     }
 
-    fn create_objects(&self, objects: &[ObjectRequestDescription]) -> (Box<[ObjectData]>, Box<[Allocation]>) {
+    fn create_objects(&self, objects: &[ObjectRequestDescription]) -> Result<CreatedObjects, ObjectCreateError> {
         self.0.create_objects(objects)
     }
 
@@ -466,16 +541,17 @@ mod tests {
     use crate::objects::buffer::{BufferCreateDesc, BufferViewCreateDesc};
     use crate::objects::image::ImageCreateDesc;
     use super::*;
+    use ash::vk::Handle;
 
     fn create() -> ObjectManager {
         let (_, device) = crate::test::make_headless_instance_device();
-        ObjectManager::new(device)
+        ObjectManager::new(device).unwrap()
     }
 
     #[test]
     fn create_destroy() {
         let (_, device) = crate::test::make_headless_instance_device();
-        let manager = ObjectManager::new(device);
+        let manager = ObjectManager::new(device).unwrap();
         drop(manager);
     }
 
@@ -502,7 +578,7 @@ mod tests {
         let desc = BufferCreateDesc::new_simple(1024, vk::BufferUsageFlags::TRANSFER_SRC | vk::BufferUsageFlags::TRANSFER_DST);
         let id = builder.add_default_gpu_only_buffer(desc);
 
-        let set = builder.build();
+        let set = builder.build().unwrap();
 
         assert_eq!(set.get_synchronization_group(), Some(&group));
 
@@ -521,7 +597,7 @@ mod tests {
             vk::ImageUsageFlags::TRANSFER_SRC | vk::ImageUsageFlags::TRANSFER_DST);
         let id = builder.add_default_gpu_only_image(desc);
 
-        let set = builder.build();
+        let set = builder.build().unwrap();
 
         assert_eq!(set.get_synchronization_group(), Some(&group));
 
@@ -543,7 +619,7 @@ mod tests {
         let view_desc = BufferViewCreateDesc::new_simple(BufferRange { offset: 256, length: 256 }, &crate::objects::Format::R16_UNORM);
         let view_id = builder.add_internal_buffer_view(view_desc, buffer_id);
 
-        let set = builder.build();
+        let set = builder.build().unwrap();
 
         assert!(set.get_buffer_handle(buffer_id).is_some());
         assert!(set.get_buffer_view_handle(view_id).is_some());
@@ -552,14 +628,33 @@ mod tests {
         let view_desc = BufferViewCreateDesc::new_simple(BufferRange { offset: 256, length: 256 }, &crate::objects::Format::R16_UNORM);
         let view2_id = builder.add_external_buffer_view(view_desc, set.clone(), buffer_id);
 
-        let set2 = builder.build();
+        let set2 = builder.build().unwrap();
 
         assert!(set2.get_buffer_view_handle(view2_id).is_some());
 
+        let objects: Vec<_> = set.iter().collect();
+        assert_eq!(set.len(), 2);
+        assert_eq!(objects[0].get_id().downcast::<{ id::ObjectType::BUFFER }>(), Some(buffer_id));
+        assert_eq!(objects[1].get_type(), id::ObjectType::BUFFER_VIEW);
+        assert_eq!(objects[1].get_raw_handle(), set.get_buffer_view_handle(view_id).unwrap().as_raw());
+
         // Test that original set does not get destroyed early
         drop(set);
         drop(set2);
     }
+
+    #[test]
+    fn create_object_set_invalid_buffer_view() {
+        let manager = create();
+
+        let mut builder = manager.create_no_group_object_set();
+        let buffer_desc = BufferCreateDesc::new_simple(1024, vk::BufferUsageFlags::TRANSFER_SRC);
+        let buffer_id = builder.add_default_gpu_only_buffer(buffer_desc);
+        let view_desc = BufferViewCreateDesc::new_simple(BufferRange { offset: 0, length: 256 }, &crate::objects::Format::R16_UNORM);
+        builder.add_internal_buffer_view(view_desc, buffer_id);
+
+        assert!(matches!(builder.build(), Err(ObjectCreateError::BufferView(BufferViewError::NotATexelBuffer))));
+    }
 }
 
 struct BufferRequestDescription {
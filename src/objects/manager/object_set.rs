@@ -11,7 +11,7 @@ use crate::util::id::GlobalId;
 use ash::vk;
 use ash::vk::Handle;
 use crate::objects::manager::allocator::{Allocation, AllocationStrategy};
-use crate::objects::manager::ObjectRequestDescription;
+use crate::objects::manager::{ObjectCreateError, ObjectRequestDescription};
 
 pub(super) enum ObjectData {
     Buffer{
@@ -41,6 +41,37 @@ impl ObjectData {
             ObjectData::ImageView { handle, .. } => handle.as_raw(),
         }
     }
+
+    fn get_id(&self, set_id: GlobalId, index: u64) -> id::GenericId {
+        match self {
+            ObjectData::Buffer { .. } => id::BufferId::new(set_id, index).as_generic(),
+            ObjectData::BufferView { .. } => id::BufferViewId::new(set_id, index).as_generic(),
+            ObjectData::Image { .. } => id::ImageId::new(set_id, index).as_generic(),
+            ObjectData::ImageView { .. } => id::ImageViewId::new(set_id, index).as_generic(),
+        }
+    }
+}
+
+/// A reference to an object inside of an object set.
+#[derive(Copy, Clone)]
+pub struct ObjectRef {
+    id: id::GenericId,
+    handle: u64,
+}
+
+impl ObjectRef {
+    pub fn get_id(&self) -> id::GenericId {
+        self.id
+    }
+
+    /// Returns the object type as defined in [`id::ObjectType`]
+    pub fn get_type(&self) -> u8 {
+        self.id.get_type()
+    }
+
+    pub fn get_raw_handle(&self) -> u64 {
+        self.handle
+    }
 }
 
 pub(super) struct ObjectSetData {
@@ -57,6 +88,8 @@ pub struct ObjectSetBuilder {
     manager: ObjectManager,
     set_id: GlobalId,
     requests: Vec<ObjectRequestDescription>,
+    /// Image views already requested in this builder, used to reuse identical views
+    image_views: Vec<(id::ImageId, ImageViewCreateDesc, id::ImageViewId)>,
     requires_group: bool,
 }
 
@@ -68,6 +101,7 @@ impl ObjectSetBuilder {
             manager,
             set_id: GlobalId::new(),
             requests: Vec::new(),
+            image_views: Vec::new(),
             requires_group: false,
         }
     }
@@ -78,6 +112,7 @@ impl ObjectSetBuilder {
             manager,
             set_id: GlobalId::new(),
             requests: Vec::new(),
+            image_views: Vec::new(),
             requires_group: false,
         }
     }
@@ -177,7 +212,16 @@ impl ObjectSetBuilder {
         id::ImageId::new(self.set_id, index as u64)
     }
 
+    /// Returns a view previously requested in this builder with the same image and description
+    fn find_image_view(&self, image: id::ImageId, desc: &ImageViewCreateDesc) -> Option<id::ImageViewId> {
+        self.image_views.iter()
+            .find(|(view_image, view_desc, _)| *view_image == image && view_desc == desc)
+            .map(|(_, _, view)| *view)
+    }
+
     /// Adds a image view for a image created as part of this object set
+    ///
+    /// If an identical view of the same image has already been added the existing id is returned.
     pub fn add_internal_image_view(&mut self, desc: ImageViewCreateDesc, image: id::ImageId) -> id::ImageViewId {
         if self.synchronization_group.is_none() {
             panic!("Attempted to add image view to object set without synchronization group");
@@ -187,14 +231,21 @@ impl ObjectSetBuilder {
         if image.get_global_id() != self.set_id {
             panic!("Image global id does not match set id")
         }
+        if let Some(view) = self.find_image_view(image, &desc) {
+            return view;
+        }
         let index = self.requests.len();
 
         self.requests.push(ObjectRequestDescription::make_image_view(desc, None, image));
 
-        id::ImageViewId::new(self.set_id, index as u64)
+        let view = id::ImageViewId::new(self.set_id, index as u64);
+        self.image_views.push((image, desc, view));
+        view
     }
 
     /// Adds a image view for a image owned by a different object set
+    ///
+    /// If an identical view of the same image has already been added the existing id is returned.
     pub fn add_external_image_view(&mut self, desc: ImageViewCreateDesc, set: ObjectSet, image: id::ImageId) -> id::ImageViewId {
         if self.synchronization_group.is_none() {
             panic!("Attempted to add image view to object set without synchronization group");
@@ -209,19 +260,25 @@ impl ObjectSetBuilder {
             panic!("Image does not match internal synchronization group")
         }
 
+        if let Some(view) = self.find_image_view(image, &desc) {
+            return view;
+        }
         let index = self.requests.len();
 
         self.requests.push(ObjectRequestDescription::make_image_view(desc, Some(set), image));
 
-        id::ImageViewId::new(self.set_id, index as u64)
+        let view = id::ImageViewId::new(self.set_id, index as u64);
+        self.image_views.push((image, desc, view));
+        view
     }
 
-    /// Creates the objects and returns the resulting object set
-    pub fn build(self) -> ObjectSet {
+    /// Creates the objects and returns the resulting object set. If any object fails to be created
+    /// all previously created objects are destroyed and the error is returned.
+    pub fn build(self) -> Result<ObjectSet, ObjectCreateError> {
         let group = if self.requires_group { self.synchronization_group } else { None };
 
-        let (objects, allocation) = self.manager.create_objects(self.requests.as_slice());
-        ObjectSet::new(self.set_id, group, self.manager, objects, allocation)
+        let (objects, allocation) = self.manager.create_objects(self.requests.as_slice())?;
+        Ok(ObjectSet::new(self.set_id, group, self.manager, objects, allocation))
     }
 }
 
@@ -402,6 +459,26 @@ impl ObjectSet {
     pub fn get_image_view_handle(&self, id: id::ImageViewId) -> Option<vk::ImageView> {
         self.0.get_image_view_handle(id)
     }
+
+    /// Returns an iterator over all objects in this object set in creation order.
+    pub fn iter(&self) -> impl Iterator<Item = ObjectRef> + '_ {
+        let set_id = self.0.set_id;
+        self.0.data.objects.iter().enumerate().map(move |(index, object)| {
+            ObjectRef {
+                id: object.get_id(set_id, index as u64),
+                handle: object.get_raw_handle(),
+            }
+        })
+    }
+
+    /// Returns the number of objects in this object set.
+    pub fn len(&self) -> usize {
+        self.0.data.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.data.objects.is_empty()
+    }
 }
 
 impl Clone for ObjectSet {
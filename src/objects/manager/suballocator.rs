@@ -0,0 +1,326 @@
+use std::collections::HashSet;
+
+use ash::vk;
+
+use crate::objects::BufferRange;
+use crate::objects::manager::ObjectManager;
+use crate::objects::manager::allocator::{Allocation, AllocationError, AllocationStrategy};
+
+#[derive(Debug)]
+pub enum SubAllocatorError {
+    Allocation(AllocationError),
+    Vulkan(vk::Result),
+    /// The block does not have enough free space left for the requested buffer
+    OutOfSpace,
+    /// The buffer cannot be bound to the memory type of the block
+    IncompatibleMemoryType,
+    /// A block or buffer of size 0 was requested
+    ZeroSize,
+    /// The freed buffer was not allocated by this suballocator
+    ForeignAllocation,
+}
+
+impl From<AllocationError> for SubAllocatorError {
+    fn from(err: AllocationError) -> Self {
+        SubAllocatorError::Allocation(err)
+    }
+}
+
+impl From<vk::Result> for SubAllocatorError {
+    fn from(err: vk::Result) -> Self {
+        SubAllocatorError::Vulkan(err)
+    }
+}
+
+/// How free space inside a block is managed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SubAllocationStrategy {
+    /// Buffers are placed linearly. Space is only reclaimed once all buffers have been freed.
+    Bump,
+    /// Freed space is reused by later allocations.
+    FreeList,
+}
+
+/// Tracks which ranges of a block are in use.
+struct RangeAllocator {
+    size: u64,
+    strategy: SubAllocationStrategy,
+    /// The first unused byte for the bump strategy
+    head: u64,
+    live: usize,
+    /// Free ranges sorted by their offset for the free list strategy
+    free: Vec<BufferRange>,
+}
+
+impl RangeAllocator {
+    fn new(size: u64, strategy: SubAllocationStrategy) -> Self {
+        Self {
+            size,
+            strategy,
+            head: 0,
+            live: 0,
+            free: vec![BufferRange::new(0, size)],
+        }
+    }
+
+    /// Returns the offset of a new range of `size` bytes aligned to `alignment`.
+    fn allocate(&mut self, size: u64, alignment: u64) -> Option<u64> {
+        let offset = match self.strategy {
+            SubAllocationStrategy::Bump => {
+                let offset = align_up(self.head, alignment);
+                if offset.checked_add(size)? > self.size {
+                    return None;
+                }
+                self.head = offset + size;
+                offset
+            }
+            SubAllocationStrategy::FreeList => {
+                let (index, offset) = self.free.iter().enumerate().find_map(|(index, range)| {
+                    let offset = align_up(range.offset, alignment);
+                    if offset.checked_add(size)? <= range.end() {
+                        Some((index, offset))
+                    } else {
+                        None
+                    }
+                })?;
+
+                let range = self.free.remove(index);
+                if offset + size < range.end() {
+                    self.free.insert(index, BufferRange::new(offset + size, range.end() - offset - size));
+                }
+                if offset > range.offset {
+                    self.free.insert(index, BufferRange::new(range.offset, offset - range.offset));
+                }
+                offset
+            }
+        };
+
+        self.live += 1;
+        Some(offset)
+    }
+
+    /// Returns a range previously returned by [`RangeAllocator::allocate`].
+    fn free(&mut self, range: BufferRange) {
+        self.live -= 1;
+
+        match self.strategy {
+            SubAllocationStrategy::Bump => {
+                if self.live == 0 {
+                    self.head = 0;
+                }
+            }
+            SubAllocationStrategy::FreeList => {
+                let index = self.free.partition_point(|free| free.offset < range.offset);
+                self.free.insert(index, range);
+
+                // Merge with the following and then the preceding range
+                if index + 1 < self.free.len() && self.free[index].end() == self.free[index + 1].offset {
+                    let next = self.free.remove(index + 1);
+                    self.free[index].length += next.length;
+                }
+                if index > 0 && self.free[index - 1].end() == self.free[index].offset {
+                    let current = self.free.remove(index);
+                    self.free[index - 1].length += current.length;
+                }
+            }
+        }
+    }
+}
+
+fn align_up(value: u64, alignment: u64) -> u64 {
+    let alignment = alignment.max(1);
+    value.div_ceil(alignment) * alignment
+}
+
+/// A buffer bound to a range of the memory block of a [`SubAllocator`].
+#[derive(Copy, Clone, Debug)]
+pub struct SubAllocation {
+    buffer: vk::Buffer,
+    range: BufferRange,
+}
+
+impl SubAllocation {
+    pub fn get_buffer(&self) -> vk::Buffer {
+        self.buffer
+    }
+
+    /// Returns the offset of the buffer inside the memory block.
+    pub fn get_offset(&self) -> u64 {
+        self.range.offset
+    }
+
+    pub fn get_size(&self) -> u64 {
+        self.range.length
+    }
+}
+
+/// Owns a single memory allocation and binds many small buffers into it.
+///
+/// All buffers are created with the same usage flags. Buffers that have not been freed are
+/// destroyed when the suballocator is dropped.
+pub struct SubAllocator {
+    manager: ObjectManager,
+    usage_flags: vk::BufferUsageFlags,
+    memory_type_bits: u32,
+    allocation: Option<Allocation>,
+    ranges: RangeAllocator,
+    buffers: HashSet<vk::Buffer>,
+}
+
+impl SubAllocator {
+    pub(super) fn new(manager: ObjectManager, size: u64, usage_flags: vk::BufferUsageFlags, location: AllocationStrategy, strategy: SubAllocationStrategy) -> Result<Self, SubAllocatorError> {
+        if size == 0 {
+            return Err(SubAllocatorError::ZeroSize);
+        }
+
+        let device = manager.0.device.clone();
+
+        // Query the memory types usable by buffers with these usage flags
+        let probe = create_buffer(&device, size, usage_flags)?;
+        let mut requirements = unsafe { device.vk().get_buffer_memory_requirements(probe) };
+        unsafe { device.vk().destroy_buffer(probe, None) };
+        requirements.size = size;
+
+        let allocation = manager.0.allocator.allocate_memory(requirements, &location)?;
+
+        Ok(Self {
+            manager,
+            usage_flags,
+            memory_type_bits: requirements.memory_type_bits,
+            allocation: Some(allocation),
+            ranges: RangeAllocator::new(size, strategy),
+            buffers: HashSet::new(),
+        })
+    }
+
+    /// Creates a buffer of `size` bytes bound inside the block. The offset is aligned to the
+    /// buffer memory requirements and additionally to `alignment`. The size must not be 0.
+    pub fn allocate(&mut self, size: u64, alignment: u64) -> Result<SubAllocation, SubAllocatorError> {
+        if size == 0 {
+            return Err(SubAllocatorError::ZeroSize);
+        }
+
+        let buffer = create_buffer(&self.manager.0.device, size, self.usage_flags)?;
+
+        match self.bind(buffer, size, alignment) {
+            Ok(range) => {
+                self.buffers.insert(buffer);
+                Ok(SubAllocation { buffer, range })
+            }
+            Err(err) => {
+                unsafe { self.manager.0.device.vk().destroy_buffer(buffer, None) };
+                Err(err)
+            }
+        }
+    }
+
+    /// Destroys the buffer and returns its range to the block. Returns an error if the buffer was
+    /// not allocated by this suballocator.
+    pub fn free(&mut self, allocation: SubAllocation) -> Result<(), SubAllocatorError> {
+        if !self.buffers.remove(&allocation.buffer) {
+            return Err(SubAllocatorError::ForeignAllocation);
+        }
+
+        unsafe { self.manager.0.device.vk().destroy_buffer(allocation.buffer, None) };
+        self.ranges.free(allocation.range);
+        Ok(())
+    }
+
+    /// Returns a host pointer to the start of the buffer if the block is host visible.
+    pub fn mapped_ptr(&self, allocation: &SubAllocation) -> Option<std::ptr::NonNull<u8>> {
+        let ptr = self.allocation.as_ref()?.mapped_ptr()?;
+        std::ptr::NonNull::new(unsafe { (ptr.as_ptr() as *mut u8).add(allocation.range.offset as usize) })
+    }
+
+    fn bind(&mut self, buffer: vk::Buffer, size: u64, alignment: u64) -> Result<BufferRange, SubAllocatorError> {
+        let device = &self.manager.0.device;
+        let requirements = unsafe { device.vk().get_buffer_memory_requirements(buffer) };
+        if requirements.memory_type_bits & self.memory_type_bits == 0 {
+            return Err(SubAllocatorError::IncompatibleMemoryType);
+        }
+
+        let size = size.max(requirements.size);
+        let offset = self.ranges.allocate(size, requirements.alignment.max(alignment))
+            .ok_or(SubAllocatorError::OutOfSpace)?;
+        let range = BufferRange::new(offset, size);
+
+        let allocation = self.allocation.as_ref().unwrap();
+        if let Err(err) = unsafe { device.vk().bind_buffer_memory(buffer, allocation.memory(), allocation.offset() + offset) } {
+            self.ranges.free(range);
+            return Err(err.into());
+        }
+
+        Ok(range)
+    }
+}
+
+impl Drop for SubAllocator {
+    fn drop(&mut self) {
+        for buffer in self.buffers.drain() {
+            unsafe { self.manager.0.device.vk().destroy_buffer(buffer, None) };
+        }
+        if let Some(allocation) = self.allocation.take() {
+            self.manager.0.allocator.free(allocation);
+        }
+    }
+}
+
+fn create_buffer(device: &crate::rosella::DeviceContext, size: u64, usage_flags: vk::BufferUsageFlags) -> ash::prelude::VkResult<vk::Buffer> {
+    let create_info = vk::BufferCreateInfo::builder()
+        .size(size)
+        .usage(usage_flags)
+        .sharing_mode(vk::SharingMode::EXCLUSIVE);
+
+    unsafe { device.vk().create_buffer(&create_info, None) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alignment() {
+        assert_eq!(align_up(0, 64), 0);
+        assert_eq!(align_up(1, 64), 64);
+        assert_eq!(align_up(64, 64), 64);
+        assert_eq!(align_up(65, 64), 128);
+        assert_eq!(align_up(7, 0), 7);
+    }
+
+    #[test]
+    fn bump_allocation() {
+        let mut ranges = RangeAllocator::new(256, SubAllocationStrategy::Bump);
+
+        assert_eq!(ranges.allocate(10, 1), Some(0));
+        assert_eq!(ranges.allocate(10, 64), Some(64));
+        assert_eq!(ranges.allocate(200, 1), None);
+
+        ranges.free(BufferRange::new(0, 10));
+        assert_eq!(ranges.allocate(10, 1), Some(74));
+
+        ranges.free(BufferRange::new(64, 10));
+        ranges.free(BufferRange::new(74, 10));
+        assert_eq!(ranges.allocate(200, 1), Some(0));
+    }
+
+    #[test]
+    fn free_list_allocation() {
+        let mut ranges = RangeAllocator::new(256, SubAllocationStrategy::FreeList);
+
+        assert_eq!(ranges.allocate(64, 1), Some(0));
+        assert_eq!(ranges.allocate(64, 1), Some(64));
+        assert_eq!(ranges.allocate(64, 1), Some(128));
+        assert_eq!(ranges.allocate(128, 1), None);
+
+        // Freed space is reused
+        ranges.free(BufferRange::new(0, 64));
+        assert_eq!(ranges.allocate(32, 16), Some(0));
+        assert_eq!(ranges.allocate(16, 32), Some(32));
+
+        // Adjacent ranges are merged
+        ranges.free(BufferRange::new(0, 32));
+        ranges.free(BufferRange::new(32, 16));
+        ranges.free(BufferRange::new(64, 64));
+        assert_eq!(ranges.allocate(128, 1), Some(0));
+    }
+}
@@ -1,17 +1,80 @@
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 use std::hash::{Hash, Hasher};
-use std::sync::{Arc, LockResult, Mutex, MutexGuard};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 
+use crate::objects::barrier::ImageBarrierBuilder;
+use crate::objects::id::ImageId;
+use crate::objects::image::ImageSubresourceRange;
 use crate::util::id::GlobalId;
+use crate::NamedUUID;
 use super::ObjectManager;
 
 use ash::vk;
 
+/// An error that may occur when enqueuing accesses to synchronization groups.
+#[derive(Debug)]
+pub enum SynchronizationError {
+    /// The mutex protecting a group was poisoned by a thread that panicked while holding it.
+    /// Contains the id and name of the group.
+    GroupPoisoned(GlobalId, Option<NamedUUID>),
+    /// The image state is tracked by a different group. The image must be handed off using
+    /// [`SynchronizationGroup::hand_off_image`] first.
+    ImageOwnedByOtherGroup {
+        image: ImageId,
+        owner: GlobalId,
+    },
+    /// The mutex protecting the image owners of the object manager was poisoned by a thread that
+    /// panicked while holding it.
+    ImageOwnersPoisoned,
+}
+
+impl std::fmt::Display for SynchronizationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SynchronizationError::GroupPoisoned(id, Some(name)) => write!(f, "Synchronization group {:?} ({:#016X}) has been poisoned", name, id.get_raw()),
+            SynchronizationError::GroupPoisoned(id, None) => write!(f, "Synchronization group {:#016X} has been poisoned", id.get_raw()),
+            SynchronizationError::ImageOwnedByOtherGroup { image, owner } => write!(f, "Image {:?} is tracked by synchronization group {:#016X}", image, owner.get_raw()),
+            SynchronizationError::ImageOwnersPoisoned => write!(f, "The image owners of the object manager have been poisoned"),
+        }
+    }
+}
+
+impl std::error::Error for SynchronizationError {
+}
+
+/// The layout and last access of an image at the end of the last enqueued access using it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ImageState {
+    pub layout: vk::ImageLayout,
+    pub stage_mask: vk::PipelineStageFlags2KHR,
+    pub access_mask: vk::AccessFlags2KHR,
+}
+
+impl ImageState {
+    /// The state of an image that has not been used yet. Its contents are undefined.
+    pub const UNDEFINED: ImageState = ImageState::new(vk::ImageLayout::UNDEFINED, vk::PipelineStageFlags2KHR::empty(), vk::AccessFlags2KHR::empty());
+
+    pub const fn new(layout: vk::ImageLayout, stage_mask: vk::PipelineStageFlags2KHR, access_mask: vk::AccessFlags2KHR) -> Self {
+        Self { layout, stage_mask, access_mask }
+    }
+
+    /// Returns a barrier transitioning the image from this state to `next`. The barrier must be
+    /// recorded with [`crate::objects::barrier::pipeline_barrier2`].
+    pub fn barrier_to(&self, image: vk::Image, subresource_range: ImageSubresourceRange, next: &ImageState) -> ImageBarrierBuilder {
+        ImageBarrierBuilder::new(image, subresource_range)
+            .layouts(self.layout, next.layout)
+            .src(self.stage_mask, self.access_mask)
+            .dst(next.stage_mask, next.access_mask)
+    }
+}
+
 // Internal struct containing the semaphore payload and metadata
 struct SyncData {
     semaphore: vk::Semaphore,
     last_access: u64,
+    image_states: HashMap<ImageId, ImageState>,
 }
 
 impl SyncData {
@@ -31,27 +94,59 @@ impl SyncData {
 // Internal implementation of the synchronization group
 struct SynchronizationGroupImpl {
     group_id: GlobalId,
+    name: Option<NamedUUID>,
     sync_data: Mutex<SyncData>,
     manager: ObjectManager,
 }
 
 impl SynchronizationGroupImpl {
-    fn new(manager: ObjectManager, semaphore: vk::Semaphore) -> Self {
-        Self{ group_id: GlobalId::new(), sync_data: Mutex::new(SyncData{ semaphore, last_access: 0u64 }), manager }
+    fn new(manager: ObjectManager, semaphore: vk::Semaphore, name: Option<NamedUUID>) -> Self {
+        Self{ group_id: GlobalId::new(), name, sync_data: Mutex::new(SyncData{ semaphore, last_access: 0u64, image_states: HashMap::new() }), manager }
     }
 
     fn get_group_id(&self) -> GlobalId {
         self.group_id
     }
 
-    fn lock(&self) -> LockResult<MutexGuard<SyncData>> {
-        self.sync_data.lock()
+    fn lock(&self) -> Result<MutexGuard<'_, SyncData>, SynchronizationError> {
+        self.sync_data.lock().map_err(|_| SynchronizationError::GroupPoisoned(self.group_id, self.name.clone()))
+    }
+
+    fn lock_image_owners(&self) -> Result<MutexGuard<'_, HashMap<ImageId, GlobalId>>, SynchronizationError> {
+        self.manager.0.image_owners.lock().map_err(|_| SynchronizationError::ImageOwnersPoisoned)
+    }
+
+    /// Claims the images for this group and records their new states. Returns the previous state of
+    /// each image. No state is modified if any image is owned by another group.
+    fn transition_images(&self, data: &mut SyncData, images: &[(ImageId, ImageState)]) -> Result<Box<[ImageState]>, SynchronizationError> {
+        let mut owners = self.lock_image_owners()?;
+        for (image, _) in images {
+            match owners.get(image) {
+                Some(owner) if *owner != self.group_id => {
+                    return Err(SynchronizationError::ImageOwnedByOtherGroup { image: *image, owner: *owner });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(images.iter().map(|(image, next)| {
+            owners.insert(*image, self.group_id);
+            data.image_states.insert(*image, *next).unwrap_or(ImageState::UNDEFINED)
+        }).collect())
     }
 }
 
 impl Drop for SynchronizationGroupImpl {
     fn drop(&mut self) {
-        self.manager.destroy_semaphore(self.sync_data.get_mut().unwrap().semaphore)
+        let data = self.sync_data.get_mut().unwrap_or_else(|err| err.into_inner());
+        if !data.image_states.is_empty() {
+            let mut owners = self.manager.0.image_owners.lock().unwrap_or_else(PoisonError::into_inner);
+            for image in data.image_states.keys() {
+                owners.remove(image);
+            }
+        }
+
+        self.manager.destroy_semaphore(data.semaphore)
     }
 }
 
@@ -78,7 +173,10 @@ impl Ord for SynchronizationGroupImpl {
 
 impl Debug for SynchronizationGroup {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&*format!("SynchronizationGroup({:#16X})", self.0.group_id.get_raw()))
+        match &self.0.name {
+            Some(name) => f.write_str(&format!("SynchronizationGroup({:#16X}, {:?})", self.0.group_id.get_raw(), name)),
+            None => f.write_str(&format!("SynchronizationGroup({:#16X})", self.0.group_id.get_raw())),
+        }
     }
 }
 
@@ -88,14 +186,20 @@ impl Debug for SynchronizationGroup {
 pub struct SynchronizationGroup(Arc<SynchronizationGroupImpl>);
 
 impl SynchronizationGroup {
-    pub(super) fn new(manager: ObjectManager, semaphore: vk::Semaphore) -> Self {
-        Self(Arc::new(SynchronizationGroupImpl::new(manager, semaphore)))
+    pub(super) fn new(manager: ObjectManager, semaphore: vk::Semaphore, name: Option<NamedUUID>) -> Self {
+        Self(Arc::new(SynchronizationGroupImpl::new(manager, semaphore, name)))
     }
 
     pub fn get_group_id(&self) -> GlobalId {
         self.0.get_group_id()
     }
 
+    /// Returns the name of the group if it was created using
+    /// [`ObjectManager::create_named_synchronization_group`]
+    pub fn get_name(&self) -> Option<&NamedUUID> {
+        self.0.name.as_ref()
+    }
+
     /// Returns the object manager managing this synchronization group
     pub fn get_manager(&self) -> &ObjectManager {
         &self.0.manager
@@ -108,8 +212,59 @@ impl SynchronizationGroup {
     /// If access to multiple groups is needed simultaneously; accesses **must not** be queued
     /// individually but by using a synchronization group set. Not doing so may result in a
     /// deadlock when waiting for the semaphores.
-    pub fn enqueue_access(&self, step_count: u64) -> AccessInfo {
-        self.0.lock().unwrap().enqueue_access(step_count)
+    ///
+    /// Returns an error if the group has been poisoned.
+    pub fn enqueue_access(&self, step_count: u64) -> Result<AccessInfo, SynchronizationError> {
+        Ok(self.0.lock()?.enqueue_access(step_count))
+    }
+
+    /// Enqueues an access like [`SynchronizationGroup::enqueue_access`] which additionally uses
+    /// images protected by this group.
+    ///
+    /// For every image the state it will be in at the end of the access must be provided. The state
+    /// of each image before the access is returned in the same order and can be used to record the
+    /// initial barriers using [`ImageState::barrier_to`]. Images that have not been used before are
+    /// in the [`ImageState::UNDEFINED`] state.
+    ///
+    /// Returns an error if an image is tracked by another group.
+    pub fn enqueue_image_access(&self, step_count: u64, images: &[(ImageId, ImageState)]) -> Result<(AccessInfo, Box<[ImageState]>), SynchronizationError> {
+        let mut guard = self.0.lock()?;
+        let previous = self.0.transition_images(&mut guard, images)?;
+
+        Ok((guard.enqueue_access(step_count), previous))
+    }
+
+    /// Returns the state of the image at the end of the last enqueued access or [`None`] if the
+    /// image is not tracked by this group.
+    pub fn get_image_state(&self, image: ImageId) -> Result<Option<ImageState>, SynchronizationError> {
+        Ok(self.0.lock()?.image_states.get(&image).copied())
+    }
+
+    /// Moves the tracked state of an image to another group. Afterwards the image can only be used
+    /// by accesses enqueued in `target`. Does nothing if the image is not tracked by this group.
+    ///
+    /// The caller must ensure accesses in `target` wait for the last access to the image in this
+    /// group.
+    pub fn hand_off_image(&self, image: ImageId, target: &SynchronizationGroup) -> Result<(), SynchronizationError> {
+        if self == target {
+            return Ok(());
+        }
+
+        // Lock in ascending id order like synchronization group sets
+        let (mut source, mut target_data) = if self < target {
+            let source = self.0.lock()?;
+            (source, target.0.lock()?)
+        } else {
+            let target_data = target.0.lock()?;
+            (self.0.lock()?, target_data)
+        };
+
+        let mut owners = self.0.lock_image_owners()?;
+        if let Some(state) = source.image_states.remove(&image) {
+            owners.insert(image, target.get_group_id());
+            target_data.image_states.insert(image, state);
+        }
+        Ok(())
     }
 }
 
@@ -167,10 +322,36 @@ impl SynchronizationGroupSet {
         // BTreeSet is required to guarantee the groups are sorted
 
         let collected : Vec<_> = groups.into_iter().map(|group| group.clone()).collect();
-        Self{ groups: collected.into_boxed_slice() }
+        Self::from_sorted(collected)
+    }
+
+    /// Creates a set from groups in any order. Duplicate groups are removed.
+    pub fn from_groups(groups: &[SynchronizationGroup]) -> Self {
+        let mut collected = groups.to_vec();
+        collected.sort();
+        collected.dedup();
+
+        Self::from_sorted(collected)
+    }
+
+    fn from_sorted(groups: Vec<SynchronizationGroup>) -> Self {
+        // Groups are always locked in ascending id order. Since every set follows the same order two
+        // sets sharing groups cannot deadlock each other.
+        debug_assert!(groups.windows(2).all(|pair| pair[0] < pair[1]), "Synchronization groups must be strictly ordered");
+
+        Self{ groups: groups.into_boxed_slice() }
     }
 
-    pub fn enqueue_access(&self, step_counts: &[u64]) -> Box<[AccessInfo]> {
+    /// Returns the groups of this set in locking order
+    pub fn get_groups(&self) -> &[SynchronizationGroup] {
+        &self.groups
+    }
+
+    /// Enqueues an access to all groups in this set.
+    ///
+    /// If any group has been poisoned no access is enqueued and an error identifying the group is
+    /// returned.
+    pub fn enqueue_access(&self, step_counts: &[u64]) -> Result<Box<[AccessInfo]>, SynchronizationError> {
         if self.groups.len() != step_counts.len() {
             panic!("Step counts length mismatch")
         }
@@ -178,7 +359,7 @@ impl SynchronizationGroupSet {
         let mut guards = Vec::with_capacity(self.groups.len());
 
         for group in self.groups.iter() {
-            guards.push(group.0.lock().unwrap())
+            guards.push(group.0.lock()?)
         }
 
         let mut accesses = Vec::with_capacity(self.groups.len());
@@ -187,6 +368,148 @@ impl SynchronizationGroupSet {
             accesses.push(guard.enqueue_access(*step_counts.get(i).unwrap()));
         }
 
-        accesses.into_boxed_slice()
+        Ok(accesses.into_boxed_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::AssertUnwindSafe;
+
+    use super::*;
+
+    fn create() -> ObjectManager {
+        ObjectManager::new(crate::rosella::DeviceContext::new_mock()).unwrap()
+    }
+
+    fn poison(group: &SynchronizationGroup) {
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = group.0.sync_data.lock().unwrap();
+            panic!("Poisoning synchronization group");
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn enqueue_access() {
+        let manager = create();
+        let group = manager.create_synchronization_group();
+
+        let access = group.enqueue_access(2).unwrap();
+        assert_eq!(access.begin_access, 0);
+        assert_eq!(access.end_access, 2);
+
+        let access = group.enqueue_access(1).unwrap();
+        assert_eq!(access.begin_access, 2);
+        assert_eq!(access.end_access, 3);
+    }
+
+    #[test]
+    fn enqueue_access_poisoned() {
+        let manager = create();
+        let group = manager.create_synchronization_group();
+
+        poison(&group);
+
+        match group.enqueue_access(1) {
+            Err(SynchronizationError::GroupPoisoned(id, _)) => assert_eq!(id, group.get_group_id()),
+            _ => panic!("Enqueued access on poisoned group"),
+        }
+    }
+
+    #[test]
+    fn enqueue_access_set_poisoned() {
+        let manager = create();
+        let group = manager.create_synchronization_group();
+        let poisoned = manager.create_synchronization_group();
+
+        poison(&poisoned);
+
+        let set = SynchronizationGroupSet::from_groups(&[group.clone(), poisoned.clone()]);
+
+        match set.enqueue_access(&[1, 1]) {
+            Err(SynchronizationError::GroupPoisoned(id, _)) => assert_eq!(id, poisoned.get_group_id()),
+            _ => panic!("Enqueued access on poisoned group set"),
+        }
+    }
+
+    #[test]
+    fn named_group_poisoned() {
+        let manager = create();
+        let group = manager.create_named_synchronization_group(NamedUUID::new("test_group".to_string()));
+        assert_eq!(group.get_name().map(NamedUUID::get_name), Some("test_group"));
+
+        poison(&group);
+
+        match group.enqueue_access(1) {
+            Err(err @ SynchronizationError::GroupPoisoned(_, Some(_))) => assert!(err.to_string().contains("test_group")),
+            _ => panic!("Expected named poisoned error"),
+        }
+    }
+
+    #[test]
+    fn image_state_tracking() {
+        let manager = create();
+        let group = manager.create_synchronization_group();
+        let other = manager.create_synchronization_group();
+        let image = ImageId::new(GlobalId::new(), 0);
+
+        let attachment = ImageState::new(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL, vk::PipelineStageFlags2KHR::COLOR_ATTACHMENT_OUTPUT, vk::AccessFlags2KHR::COLOR_ATTACHMENT_WRITE);
+        let sampled = ImageState::new(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, vk::PipelineStageFlags2KHR::FRAGMENT_SHADER, vk::AccessFlags2KHR::SHADER_SAMPLED_READ);
+
+        let (_, previous) = group.enqueue_image_access(1, &[(image, attachment)]).unwrap();
+        assert_eq!(&*previous, &[ImageState::UNDEFINED]);
+
+        let (access, previous) = group.enqueue_image_access(1, &[(image, sampled)]).unwrap();
+        assert_eq!(access.begin_access, 1);
+        assert_eq!(&*previous, &[attachment]);
+        assert_eq!(group.get_image_state(image).unwrap(), Some(sampled));
+
+        // Using the image in another group requires a hand off
+        match other.enqueue_image_access(1, &[(image, attachment)]) {
+            Err(SynchronizationError::ImageOwnedByOtherGroup { owner, .. }) => assert_eq!(owner, group.get_group_id()),
+            _ => panic!("Expected image to be owned by other group"),
+        }
+        assert_eq!(other.enqueue_access(1).unwrap().begin_access, 0);
+
+        group.hand_off_image(image, &other).unwrap();
+        assert_eq!(group.get_image_state(image).unwrap(), None);
+
+        let (_, previous) = other.enqueue_image_access(1, &[(image, attachment)]).unwrap();
+        assert_eq!(&*previous, &[sampled]);
+    }
+
+    #[test]
+    fn image_owners_poisoned() {
+        let manager = create();
+        let group = manager.create_synchronization_group();
+        let other = manager.create_synchronization_group();
+        let image = ImageId::new(GlobalId::new(), 0);
+        let sampled = ImageState::new(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, vk::PipelineStageFlags2KHR::FRAGMENT_SHADER, vk::AccessFlags2KHR::SHADER_SAMPLED_READ);
+
+        group.enqueue_image_access(1, &[(image, sampled)]).unwrap();
+
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = manager.0.image_owners.lock().unwrap();
+            panic!("Poisoning image owners");
+        }));
+        assert!(result.is_err());
+
+        assert!(matches!(other.enqueue_image_access(1, &[(image, sampled)]), Err(SynchronizationError::ImageOwnersPoisoned)));
+        assert!(matches!(group.hand_off_image(image, &other), Err(SynchronizationError::ImageOwnersPoisoned)));
+        assert_eq!(group.get_image_state(image).unwrap(), Some(sampled));
+
+        // Dropping a group tracking images must not panic
+        drop(group);
+    }
+
+    #[test]
+    fn set_from_unordered_groups() {
+        let manager = create();
+        let first = manager.create_synchronization_group();
+        let second = manager.create_synchronization_group();
+
+        let set = SynchronizationGroupSet::from_groups(&[second.clone(), first.clone(), second.clone()]);
+        assert_eq!(set.get_groups(), &[first, second]);
     }
-}
\ No newline at end of file
+}
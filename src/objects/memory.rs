@@ -0,0 +1,80 @@
+use ash::vk;
+
+/// A memory type selected by [`select_memory_type`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MemoryTypeSelection {
+    pub index: u32,
+    pub property_flags: vk::MemoryPropertyFlags,
+    /// True if the memory type has all preferred properties. False if the selection fell back to
+    /// a type with only the required properties.
+    pub preferred_satisfied: bool,
+}
+
+/// Selects a memory type allowed by `type_bits` which has all `required` properties.
+///
+/// A type which additionally has all `preferred` properties is searched first. Only if no such
+/// type exists a type with just the required properties is selected. Returns [`None`] if no
+/// allowed type has the required properties.
+pub fn select_memory_type(properties: &vk::PhysicalDeviceMemoryProperties, type_bits: u32, required: vk::MemoryPropertyFlags, preferred: vk::MemoryPropertyFlags) -> Option<MemoryTypeSelection> {
+    find_memory_type(properties, type_bits, required | preferred)
+        .map(|(index, property_flags)| MemoryTypeSelection { index, property_flags, preferred_satisfied: true })
+        .or_else(|| {
+            find_memory_type(properties, type_bits, required)
+                .map(|(index, property_flags)| MemoryTypeSelection { index, property_flags, preferred_satisfied: preferred.is_empty() })
+        })
+}
+
+fn find_memory_type(properties: &vk::PhysicalDeviceMemoryProperties, type_bits: u32, flags: vk::MemoryPropertyFlags) -> Option<(u32, vk::MemoryPropertyFlags)> {
+    properties.memory_types[..properties.memory_type_count as usize].iter().enumerate()
+        .find(|(index, memory_type)| {
+            (type_bits & (1 << *index as u32)) != 0 && memory_type.property_flags.contains(flags)
+        })
+        .map(|(index, memory_type)| (index as u32, memory_type.property_flags))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_properties(types: &[vk::MemoryPropertyFlags]) -> vk::PhysicalDeviceMemoryProperties {
+        let mut properties = vk::PhysicalDeviceMemoryProperties::default();
+        for (index, flags) in types.iter().enumerate() {
+            properties.memory_types[index].property_flags = *flags;
+        }
+        properties.memory_type_count = types.len() as u32;
+        properties
+    }
+
+    #[test]
+    fn preferred_satisfied() {
+        let properties = make_properties(&[
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL | vk::MemoryPropertyFlags::HOST_VISIBLE,
+        ]);
+
+        let selection = select_memory_type(&properties, !0, vk::MemoryPropertyFlags::HOST_VISIBLE, vk::MemoryPropertyFlags::DEVICE_LOCAL).unwrap();
+        assert_eq!(selection.index, 2);
+        assert!(selection.preferred_satisfied);
+
+        // The matching type is not allowed by the type bits
+        let selection = select_memory_type(&properties, 0b011, vk::MemoryPropertyFlags::HOST_VISIBLE, vk::MemoryPropertyFlags::DEVICE_LOCAL).unwrap();
+        assert_eq!(selection.index, 0);
+        assert!(!selection.preferred_satisfied);
+    }
+
+    #[test]
+    fn fallback_to_required() {
+        let properties = make_properties(&[
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        ]);
+
+        let selection = select_memory_type(&properties, !0, vk::MemoryPropertyFlags::HOST_VISIBLE, vk::MemoryPropertyFlags::HOST_CACHED).unwrap();
+        assert_eq!(selection.index, 1);
+        assert_eq!(selection.property_flags, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
+        assert!(!selection.preferred_satisfied);
+
+        assert_eq!(select_memory_type(&properties, 0b01, vk::MemoryPropertyFlags::HOST_VISIBLE, vk::MemoryPropertyFlags::empty()), None);
+    }
+}
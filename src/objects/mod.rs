@@ -1,21 +1,58 @@
+pub mod barrier;
 pub mod format;
 pub mod image;
 pub mod buffer;
+pub mod fence;
 pub mod id;
 pub mod manager;
+pub mod memory;
+pub mod query;
+pub mod readback;
+pub mod sampler;
+pub mod semaphore;
 pub mod swapchain;
 
 pub use format::Format;
 
 pub use image::ImageSize;
 pub use image::ImageSpec;
+pub use image::ImageCreateDesc;
+pub use image::ImageSharing;
 pub use image::ImageSubresourceRange;
 
 pub use buffer::BufferSpec;
+pub use buffer::BufferCreateDesc;
 pub use buffer::BufferRange;
+pub use buffer::BufferViewError;
+
+pub use swapchain::SurfaceFormatPolicy;
+pub use swapchain::SwapchainSharing;
+pub use swapchain::SwapchainImageState;
+
+pub use barrier::ImageBarrierBuilder;
+pub use barrier::BufferBarrierBuilder;
+
+pub use fence::FenceHandle;
+
+pub use query::OcclusionQueryPool;
+pub use query::PipelineStatisticsQueryPool;
+pub use query::QueryError;
+
+pub use sampler::SamplerCreateDesc;
 
 pub use manager::ObjectManager;
+pub use manager::AllocationError;
+pub use manager::AllocationStrategy;
+pub use manager::ObjectCreateError;
+pub use manager::suballocator::SubAllocator;
+pub use manager::suballocator::SubAllocation;
+pub use manager::suballocator::SubAllocationStrategy;
+pub use manager::suballocator::SubAllocatorError;
 pub use manager::synchronization_group::SynchronizationGroup;
 pub use manager::synchronization_group::SynchronizationGroupSet;
+pub use manager::synchronization_group::SynchronizationError;
+pub use manager::synchronization_group::AccessInfo;
+pub use manager::synchronization_group::ImageState;
 pub use manager::object_set::ObjectSet;
-pub use manager::object_set::ObjectSetBuilder;
\ No newline at end of file
+pub use manager::object_set::ObjectSetBuilder;
+pub use manager::object_set::ObjectRef;
\ No newline at end of file
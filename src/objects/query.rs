@@ -0,0 +1,224 @@
+use ash::prelude::VkResult;
+use ash::vk;
+
+use crate::init::rosella_features::PipelineStatisticsQueryDevice;
+use crate::rosella::DeviceContext;
+
+#[derive(Debug)]
+pub enum QueryError {
+    /// The pipelineStatisticsQuery feature is not enabled on the device.
+    PipelineStatisticsNotEnabled,
+    /// The queries `first..first + count` are not all inside the pool.
+    RangeOutOfBounds {
+        first: u32,
+        count: u32,
+        pool_size: u32,
+    },
+    VulkanError(vk::Result),
+}
+
+impl From<vk::Result> for QueryError {
+    fn from(err: vk::Result) -> Self {
+        QueryError::VulkanError(err)
+    }
+}
+
+/// A query pool for occlusion queries. The pool is destroyed when dropped.
+pub struct OcclusionQueryPool {
+    device: DeviceContext,
+    pool: vk::QueryPool,
+    count: u32,
+}
+
+impl OcclusionQueryPool {
+    pub fn new(device: DeviceContext, count: u32) -> VkResult<Self> {
+        let pool = create_query_pool(&device, vk::QueryType::OCCLUSION, count, vk::QueryPipelineStatisticFlags::empty())?;
+
+        Ok(Self {
+            device,
+            pool,
+            count,
+        })
+    }
+
+    pub fn get_handle(&self) -> vk::QueryPool {
+        self.pool
+    }
+
+    pub fn get_count(&self) -> u32 {
+        self.count
+    }
+
+    /// Records a reset of all queries in the pool. Must be recorded before the queries are used.
+    pub fn reset(&self, command_buffer: vk::CommandBuffer) {
+        unsafe { self.device.vk().cmd_reset_query_pool(command_buffer, self.pool, 0, self.count) };
+    }
+
+    /// Begins a occlusion query. If `precise` is true the occlusionQueryPrecise feature must be
+    /// enabled.
+    pub fn begin_query(&self, command_buffer: vk::CommandBuffer, index: u32, precise: bool) {
+        let flags = if precise { vk::QueryControlFlags::PRECISE } else { vk::QueryControlFlags::empty() };
+        unsafe { self.device.vk().cmd_begin_query(command_buffer, self.pool, index, flags) };
+    }
+
+    pub fn end_query(&self, command_buffer: vk::CommandBuffer, index: u32) {
+        unsafe { self.device.vk().cmd_end_query(command_buffer, self.pool, index) };
+    }
+
+    /// Waits for the queries `first..first + count` to complete and returns the number of samples
+    /// that passed for each query. All queries in the range must have been ended, otherwise this
+    /// function never returns. Returns an error if the range is not inside the pool.
+    pub fn read_results(&self, first: u32, count: u32) -> Result<Vec<u64>, QueryError> {
+        validate_range(first, count, self.count)?;
+        Ok(read_query_results(&self.device, self.pool, first, count, 1)?)
+    }
+}
+
+impl Drop for OcclusionQueryPool {
+    fn drop(&mut self) {
+        unsafe { self.device.vk().destroy_query_pool(self.pool, None) };
+    }
+}
+
+/// A query pool for pipeline statistics queries. Requires the [`PipelineStatisticsQueryDevice`]
+/// feature. The pool is destroyed when dropped.
+pub struct PipelineStatisticsQueryPool {
+    device: DeviceContext,
+    pool: vk::QueryPool,
+    count: u32,
+    statistics: vk::QueryPipelineStatisticFlags,
+}
+
+impl PipelineStatisticsQueryPool {
+    pub fn new(device: DeviceContext, count: u32, statistics: vk::QueryPipelineStatisticFlags) -> Result<Self, QueryError> {
+        if !PipelineStatisticsQueryDevice::is_enabled(&device) {
+            return Err(QueryError::PipelineStatisticsNotEnabled);
+        }
+
+        let pool = create_query_pool(&device, vk::QueryType::PIPELINE_STATISTICS, count, statistics)?;
+
+        Ok(Self {
+            device,
+            pool,
+            count,
+            statistics,
+        })
+    }
+
+    pub fn get_handle(&self) -> vk::QueryPool {
+        self.pool
+    }
+
+    pub fn get_count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn get_statistics(&self) -> vk::QueryPipelineStatisticFlags {
+        self.statistics
+    }
+
+    /// Records a reset of all queries in the pool. Must be recorded before the queries are used.
+    pub fn reset(&self, command_buffer: vk::CommandBuffer) {
+        unsafe { self.device.vk().cmd_reset_query_pool(command_buffer, self.pool, 0, self.count) };
+    }
+
+    pub fn begin_query(&self, command_buffer: vk::CommandBuffer, index: u32) {
+        unsafe { self.device.vk().cmd_begin_query(command_buffer, self.pool, index, vk::QueryControlFlags::empty()) };
+    }
+
+    pub fn end_query(&self, command_buffer: vk::CommandBuffer, index: u32) {
+        unsafe { self.device.vk().cmd_end_query(command_buffer, self.pool, index) };
+    }
+
+    /// Waits for the queries `first..first + count` to complete and returns the statistics of each
+    /// query. All queries in the range must have been ended, otherwise this function never returns.
+    /// Returns an error if the range is not inside the pool.
+    ///
+    /// The values of a query are ordered by the bit order of the statistic flags.
+    pub fn read_results(&self, first: u32, count: u32) -> Result<Vec<Box<[u64]>>, QueryError> {
+        validate_range(first, count, self.count)?;
+
+        let values = Self::values_per_query(self.statistics);
+        if values == 0 {
+            return Ok((0..count).map(|_| Box::from([])).collect());
+        }
+        let results = read_query_results(&self.device, self.pool, first, count, values)?;
+
+        Ok(results.chunks(values as usize).map(Box::from).collect())
+    }
+
+    /// Returns the number of values written for each query with the given statistics flags
+    fn values_per_query(statistics: vk::QueryPipelineStatisticFlags) -> u32 {
+        statistics.as_raw().count_ones()
+    }
+}
+
+impl Drop for PipelineStatisticsQueryPool {
+    fn drop(&mut self) {
+        unsafe { self.device.vk().destroy_query_pool(self.pool, None) };
+    }
+}
+
+fn create_query_pool(device: &DeviceContext, query_type: vk::QueryType, count: u32, statistics: vk::QueryPipelineStatisticFlags) -> VkResult<vk::QueryPool> {
+    let create_info = vk::QueryPoolCreateInfo::builder()
+        .query_type(query_type)
+        .query_count(count)
+        .pipeline_statistics(statistics);
+
+    unsafe { device.vk().create_query_pool(&create_info, None) }
+}
+
+/// Returns an error if the queries `first..first + count` are not all inside a pool of `pool_size`
+/// queries.
+fn validate_range(first: u32, count: u32, pool_size: u32) -> Result<(), QueryError> {
+    if first.checked_add(count).is_some_and(|end| end <= pool_size) {
+        Ok(())
+    } else {
+        Err(QueryError::RangeOutOfBounds { first, count, pool_size })
+    }
+}
+
+/// Reads `values` 64bit values for each query in `first..first + count` waiting for the queries to
+/// complete.
+fn read_query_results(device: &DeviceContext, pool: vk::QueryPool, first: u32, count: u32, values: u32) -> VkResult<Vec<u64>> {
+    let mut results = vec![0u64; (count * values) as usize];
+    let stride = std::mem::size_of::<u64>() * values as usize;
+
+    unsafe {
+        device.vk().fp_v1_0().get_query_pool_results(
+            device.vk().handle(),
+            pool,
+            first,
+            count,
+            results.len() * std::mem::size_of::<u64>(),
+            results.as_mut_ptr() as *mut std::ffi::c_void,
+            stride as vk::DeviceSize,
+            vk::QueryResultFlags::TYPE_64 | vk::QueryResultFlags::WAIT
+        )
+    }.result()?;
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statistics_value_count() {
+        let statistics = vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_VERTICES
+            | vk::QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES
+            | vk::QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS;
+
+        assert_eq!(PipelineStatisticsQueryPool::values_per_query(statistics), 3);
+        assert_eq!(PipelineStatisticsQueryPool::values_per_query(vk::QueryPipelineStatisticFlags::empty()), 0);
+    }
+
+    #[test]
+    fn query_range_validation() {
+        assert!(validate_range(0, 8, 8).is_ok());
+        assert!(validate_range(6, 2, 8).is_ok());
+        assert!(matches!(validate_range(6, 3, 8), Err(QueryError::RangeOutOfBounds { first: 6, count: 3, pool_size: 8 })));
+        assert!(matches!(validate_range(u32::MAX, 2, 8), Err(QueryError::RangeOutOfBounds { .. })));
+    }
+}
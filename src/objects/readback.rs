@@ -0,0 +1,132 @@
+use ash::prelude::VkResult;
+use ash::vk;
+
+use crate::init::device::VulkanQueue;
+use crate::objects::AccessInfo;
+use crate::objects::memory::select_memory_type;
+use crate::rosella::DeviceContext;
+
+/// Copies `len` bytes starting at `offset` from `buffer` into a host visible staging buffer and
+/// returns its contents.
+///
+/// The buffer must have been created with `TRANSFER_SRC` usage. If `access` is provided the copy
+/// waits for the beginning of the access and signals its end, otherwise the caller must ensure the
+/// buffer is not being written to. This function blocks until the copy has completed.
+pub fn download_from_buffer(device: &DeviceContext, queue: &VulkanQueue, buffer: vk::Buffer, offset: vk::DeviceSize, len: vk::DeviceSize, access: Option<&AccessInfo>) -> VkResult<Vec<u8>> {
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+
+    let staging = StagingBuffer::new(device, len)?;
+
+    let region = vk::BufferCopy {
+        src_offset: offset,
+        dst_offset: 0,
+        size: len,
+    };
+    // Waiting on the fence alone does not make the transfer writes visible to the host
+    let barrier = vk::BufferMemoryBarrier::builder()
+        .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+        .dst_access_mask(vk::AccessFlags::HOST_READ)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .buffer(staging.buffer)
+        .offset(0)
+        .size(len);
+    let record = |command_buffer| unsafe {
+        device.vk().cmd_copy_buffer(command_buffer, buffer, staging.buffer, std::slice::from_ref(&region));
+        device.vk().cmd_pipeline_barrier(command_buffer, vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::HOST, vk::DependencyFlags::empty(), &[], std::slice::from_ref(&barrier), &[]);
+    };
+    match access {
+        Some(access) => device.immediate_submit_synchronized(queue, access, record)?,
+        None => device.immediate_submit(queue, record)?,
+    }
+
+    staging.read(len)
+}
+
+/// Downloads `count` elements of `T` from `buffer`. See [`download_from_buffer`].
+pub fn download_from_buffer_typed<T: bytemuck::Pod>(device: &DeviceContext, queue: &VulkanQueue, buffer: vk::Buffer, offset: vk::DeviceSize, count: usize, access: Option<&AccessInfo>) -> VkResult<Vec<T>> {
+    let len = (count * std::mem::size_of::<T>()) as vk::DeviceSize;
+    let bytes = download_from_buffer(device, queue, buffer, offset, len, access)?;
+
+    let mut result = vec![T::zeroed(); count];
+    bytemuck::cast_slice_mut::<T, u8>(&mut result).copy_from_slice(&bytes);
+
+    Ok(result)
+}
+
+/// A temporary host visible buffer with dedicated memory.
+struct StagingBuffer<'a> {
+    device: &'a DeviceContext,
+    buffer: vk::Buffer,
+    memory: vk::DeviceMemory,
+    coherent: bool,
+}
+
+impl<'a> StagingBuffer<'a> {
+    fn new(device: &'a DeviceContext, size: vk::DeviceSize) -> VkResult<Self> {
+        let create_info = vk::BufferCreateInfo::builder()
+            .size(size)
+            .usage(vk::BufferUsageFlags::TRANSFER_DST)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+        let buffer = unsafe { device.vk().create_buffer(&create_info, None) }?;
+
+        let mut staging = Self {
+            device,
+            buffer,
+            memory: vk::DeviceMemory::null(),
+            coherent: false,
+        };
+
+        let requirements = unsafe { device.vk().get_buffer_memory_requirements(buffer) };
+        let properties = unsafe { device.get_instance().vk().get_physical_device_memory_properties(*device.get_physical_device()) };
+
+        // Cached memory is much faster to read from on the cpu
+        let memory_type = select_memory_type(&properties, requirements.memory_type_bits, vk::MemoryPropertyFlags::HOST_VISIBLE, vk::MemoryPropertyFlags::HOST_CACHED)
+            .ok_or(vk::Result::ERROR_OUT_OF_HOST_MEMORY)?;
+
+        let allocate_info = vk::MemoryAllocateInfo::builder()
+            .allocation_size(requirements.size)
+            .memory_type_index(memory_type.index);
+        staging.memory = unsafe { device.vk().allocate_memory(&allocate_info, None) }?;
+        staging.coherent = memory_type.property_flags.contains(vk::MemoryPropertyFlags::HOST_COHERENT);
+
+        unsafe { device.vk().bind_buffer_memory(buffer, staging.memory, 0) }?;
+
+        Ok(staging)
+    }
+
+    fn read(&self, len: vk::DeviceSize) -> VkResult<Vec<u8>> {
+        unsafe {
+            let ptr = self.device.vk().map_memory(self.memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty())?;
+
+            if !self.coherent {
+                let range = vk::MappedMemoryRange::builder()
+                    .memory(self.memory)
+                    .offset(0)
+                    .size(vk::WHOLE_SIZE);
+                if let Err(err) = self.device.vk().invalidate_mapped_memory_ranges(std::slice::from_ref(&range)) {
+                    self.device.vk().unmap_memory(self.memory);
+                    return Err(err);
+                }
+            }
+
+            let data = std::slice::from_raw_parts(ptr as *const u8, len as usize).to_vec();
+            self.device.vk().unmap_memory(self.memory);
+
+            Ok(data)
+        }
+    }
+}
+
+impl<'a> Drop for StagingBuffer<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.vk().destroy_buffer(self.buffer, None);
+            if self.memory != vk::DeviceMemory::null() {
+                self.device.vk().free_memory(self.memory, None);
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use ash::prelude::VkResult;
+use ash::vk;
+
+use crate::init::rosella_features::SamplerAnisotropyDevice;
+use crate::rosella::DeviceContext;
+
+/// Describes a sampler. Use [`create_sampler`] to create a sampler respecting the features and
+/// limits of the device.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SamplerCreateDesc {
+    pub mag_filter: vk::Filter,
+    pub min_filter: vk::Filter,
+    pub mipmap_mode: vk::SamplerMipmapMode,
+    pub address_mode: vk::SamplerAddressMode,
+    pub mip_lod_bias: f32,
+    /// The maximum anisotropy or [`None`] to disable anisotropic filtering
+    pub max_anisotropy: Option<f32>,
+    pub min_lod: f32,
+    pub max_lod: f32,
+    pub border_color: vk::BorderColor,
+}
+
+impl SamplerCreateDesc {
+    /// Creates a description using the filter for minification, magnification and mipmapping
+    /// which samples all mip levels.
+    pub fn new(filter: vk::Filter, address_mode: vk::SamplerAddressMode) -> Self {
+        let mipmap_mode = match filter {
+            vk::Filter::NEAREST => vk::SamplerMipmapMode::NEAREST,
+            _ => vk::SamplerMipmapMode::LINEAR,
+        };
+
+        Self {
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_mode,
+            address_mode,
+            mip_lod_bias: 0.0,
+            max_anisotropy: None,
+            min_lod: 0.0,
+            max_lod: vk::LOD_CLAMP_NONE,
+            border_color: vk::BorderColor::FLOAT_TRANSPARENT_BLACK,
+        }
+    }
+
+    pub fn with_anisotropy(mut self, max_anisotropy: f32) -> Self {
+        self.max_anisotropy = Some(max_anisotropy);
+        self
+    }
+
+    pub fn with_lod_bias(mut self, mip_lod_bias: f32) -> Self {
+        self.mip_lod_bias = mip_lod_bias;
+        self
+    }
+
+    /// Builds the create info for a device with the given limits.
+    ///
+    /// Anisotropy is disabled if `anisotropy_enabled` is false and otherwise clamped to
+    /// `maxSamplerAnisotropy`. The lod bias is clamped to `maxSamplerLodBias`.
+    fn make_create_info(&self, anisotropy_enabled: bool, limits: &vk::PhysicalDeviceLimits) -> vk::SamplerCreateInfo {
+        let max_anisotropy = match self.max_anisotropy {
+            Some(_) if !anisotropy_enabled => {
+                log::warn!("Sampler requested anisotropic filtering but the samplerAnisotropy feature is not enabled. Anisotropy will be disabled");
+                None
+            }
+            Some(max_anisotropy) => Some(max_anisotropy.clamp(1.0, limits.max_sampler_anisotropy)),
+            None => None,
+        };
+        let max_lod_bias = limits.max_sampler_lod_bias;
+
+        vk::SamplerCreateInfo::builder()
+            .mag_filter(self.mag_filter)
+            .min_filter(self.min_filter)
+            .mipmap_mode(self.mipmap_mode)
+            .address_mode_u(self.address_mode)
+            .address_mode_v(self.address_mode)
+            .address_mode_w(self.address_mode)
+            .mip_lod_bias(self.mip_lod_bias.clamp(-max_lod_bias, max_lod_bias))
+            .anisotropy_enable(max_anisotropy.is_some())
+            .max_anisotropy(max_anisotropy.unwrap_or(1.0))
+            .min_lod(self.min_lod)
+            .max_lod(self.max_lod)
+            .border_color(self.border_color)
+            .build()
+    }
+}
+
+/// Creates a sampler which must be destroyed by the caller.
+///
+/// If anisotropic filtering is requested but the [`SamplerAnisotropyDevice`] feature is not enabled
+/// it is disabled with a warning instead of failing.
+pub fn create_sampler(device: &DeviceContext, desc: &SamplerCreateDesc) -> VkResult<vk::Sampler> {
+    let properties = unsafe { device.get_instance().vk().get_physical_device_properties(*device.get_physical_device()) };
+    let create_info = desc.make_create_info(SamplerAnisotropyDevice::is_enabled(device), &properties.limits);
+
+    unsafe { device.vk().create_sampler(&create_info, None) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anisotropy_and_lod_bias_clamping() {
+        let limits = vk::PhysicalDeviceLimits {
+            max_sampler_anisotropy: 16.0,
+            max_sampler_lod_bias: 4.0,
+            ..Default::default()
+        };
+        let desc = SamplerCreateDesc::new(vk::Filter::LINEAR, vk::SamplerAddressMode::REPEAT)
+            .with_anisotropy(32.0)
+            .with_lod_bias(-8.0);
+
+        let info = desc.make_create_info(true, &limits);
+        assert_eq!(info.anisotropy_enable, vk::TRUE);
+        assert_eq!(info.max_anisotropy, 16.0);
+        assert_eq!(info.mip_lod_bias, -4.0);
+
+        let info = desc.make_create_info(false, &limits);
+        assert_eq!(info.anisotropy_enable, vk::FALSE);
+        assert_eq!(info.max_anisotropy, 1.0);
+
+        let info = SamplerCreateDesc::new(vk::Filter::NEAREST, vk::SamplerAddressMode::REPEAT).make_create_info(true, &limits);
+        assert_eq!(info.anisotropy_enable, vk::FALSE);
+        assert_eq!(info.mipmap_mode, vk::SamplerMipmapMode::NEAREST);
+    }
+}
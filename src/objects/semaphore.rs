@@ -0,0 +1,63 @@
+use ash::prelude::VkResult;
+use ash::vk;
+
+use crate::instance::VulkanVersion;
+use crate::rosella::DeviceContext;
+
+/// Returns true if timeline semaphores are provided by vulkan 1.2 core. Otherwise the functions of
+/// the VK_KHR_timeline_semaphore extension are used.
+pub fn is_timeline_semaphore_core(device: &DeviceContext) -> bool {
+    device.get_effective_version().is_supported(VulkanVersion::VK_1_2)
+}
+
+/// Returns the current counter value of a timeline semaphore.
+///
+/// Returns `ERROR_EXTENSION_NOT_PRESENT` if timeline semaphores are neither core nor loaded as an
+/// extension on the device.
+pub fn get_semaphore_counter_value(device: &DeviceContext, semaphore: vk::Semaphore) -> VkResult<u64> {
+    if is_timeline_semaphore_core(device) {
+        unsafe { device.vk().get_semaphore_counter_value(semaphore) }
+    } else {
+        let timeline_semaphore = device.get_extension::<ash::extensions::khr::TimelineSemaphore>()
+            .ok_or(vk::Result::ERROR_EXTENSION_NOT_PRESENT)?;
+        unsafe { timeline_semaphore.get_semaphore_counter_value(semaphore) }
+    }
+}
+
+/// Waits for all timeline semaphores to reach their respective value.
+///
+/// Returns false if the timeout (in nanoseconds) expired before the wait condition was met.
+pub fn wait_semaphores(device: &DeviceContext, semaphores: &[vk::Semaphore], values: &[u64], timeout: u64) -> VkResult<bool> {
+    let wait_info = vk::SemaphoreWaitInfo::builder()
+        .semaphores(semaphores)
+        .values(values);
+
+    let result = if is_timeline_semaphore_core(device) {
+        unsafe { device.vk().wait_semaphores(&wait_info, timeout) }
+    } else {
+        let timeline_semaphore = device.get_extension::<ash::extensions::khr::TimelineSemaphore>()
+            .ok_or(vk::Result::ERROR_EXTENSION_NOT_PRESENT)?;
+        unsafe { timeline_semaphore.wait_semaphores(&wait_info, timeout) }
+    };
+
+    match result {
+        Ok(_) => Ok(true),
+        Err(vk::Result::TIMEOUT) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Sets the counter value of a timeline semaphore from the host.
+pub fn signal_semaphore(device: &DeviceContext, semaphore: vk::Semaphore, value: u64) -> VkResult<()> {
+    let signal_info = vk::SemaphoreSignalInfo::builder()
+        .semaphore(semaphore)
+        .value(value);
+
+    if is_timeline_semaphore_core(device) {
+        unsafe { device.vk().signal_semaphore(&signal_info) }
+    } else {
+        let timeline_semaphore = device.get_extension::<ash::extensions::khr::TimelineSemaphore>()
+            .ok_or(vk::Result::ERROR_EXTENSION_NOT_PRESENT)?;
+        unsafe { timeline_semaphore.signal_semaphore(&signal_info) }
+    }
+}
@@ -3,6 +3,8 @@ use super::image::*;
 
 use ash::vk;
 
+use crate::rosella::DeviceContext;
+
 #[derive(Copy, Clone)]
 pub struct SwapchainImageSpec {
     pub format: &'static Format,
@@ -72,4 +74,356 @@ pub struct SwapchainCreateDesc {
     pub composite_alpha: vk::CompositeAlphaFlagsKHR,
     pub present_mode: vk::PresentModeKHR,
     pub clipped: bool,
-}
\ No newline at end of file
+}
+
+impl SwapchainCreateDesc {
+    /// Creates a description for color attachment images using the identity transform, opaque
+    /// composition and clipping.
+    pub const fn new(image_spec: SwapchainImageSpec, present_mode: vk::PresentModeKHR) -> Self {
+        Self {
+            image_spec,
+            usage: vk::ImageUsageFlags::COLOR_ATTACHMENT,
+            pre_transform: vk::SurfaceTransformFlagsKHR::IDENTITY,
+            composite_alpha: vk::CompositeAlphaFlagsKHR::OPAQUE,
+            present_mode,
+            clipped: true,
+        }
+    }
+
+    /// Sets the usage flags of the swapchain images. For example compute based renderers may
+    /// request `STORAGE` usage to write to the images directly.
+    pub const fn with_usage(mut self, usage: vk::ImageUsageFlags) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    pub const fn with_composite_alpha(mut self, composite_alpha: vk::CompositeAlphaFlagsKHR) -> Self {
+        self.composite_alpha = composite_alpha;
+        self
+    }
+
+    /// Returns the first mode supported by the surface out of opaque, inherit, pre multiplied and
+    /// post multiplied composition. Surfaces are required to support at least one of them.
+    pub fn select_composite_alpha(supported: vk::CompositeAlphaFlagsKHR) -> vk::CompositeAlphaFlagsKHR {
+        [
+            vk::CompositeAlphaFlagsKHR::OPAQUE,
+            vk::CompositeAlphaFlagsKHR::INHERIT,
+            vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED,
+            vk::CompositeAlphaFlagsKHR::POST_MULTIPLIED,
+        ].iter().copied().find(|mode| supported.contains(*mode)).unwrap_or(vk::CompositeAlphaFlagsKHR::OPAQUE)
+    }
+
+    /// Verifies that the requested usage flags are supported by the surface. Returns the
+    /// unsupported flags on failure.
+    ///
+    /// Support for `STORAGE` usage additionally depends on the format features of the image format.
+    pub fn validate_usage(&self, capabilities: &vk::SurfaceCapabilitiesKHR) -> Result<(), vk::ImageUsageFlags> {
+        let unsupported = self.usage & !capabilities.supported_usage_flags;
+        if unsupported.is_empty() {
+            Ok(())
+        } else {
+            Err(unsupported)
+        }
+    }
+
+    /// Returns the create info for a swapchain matching this description.
+    pub fn make_create_info<'a>(&self, surface: vk::SurfaceKHR, min_image_count: u32, sharing: &'a SwapchainSharing, old_swapchain: vk::SwapchainKHR) -> vk::SwapchainCreateInfoKHRBuilder<'a> {
+        vk::SwapchainCreateInfoKHR::builder()
+            .surface(surface)
+            .min_image_count(min_image_count)
+            .image_format(self.image_spec.format.get_format())
+            .image_color_space(self.image_spec.color_space)
+            .image_extent(self.image_spec.extent)
+            .image_array_layers(self.image_spec.array_layers)
+            .image_usage(self.usage)
+            .image_sharing_mode(sharing.get_sharing_mode())
+            .queue_family_indices(sharing.get_queue_family_indices())
+            .pre_transform(self.pre_transform)
+            .composite_alpha(self.composite_alpha)
+            .present_mode(self.present_mode)
+            .clipped(self.clipped)
+            .old_swapchain(old_swapchain)
+    }
+}
+
+/// Describes how swapchain images are shared between the graphics and present queue families.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SwapchainSharing {
+    /// Both queues belong to the same family.
+    Exclusive,
+    /// The queues belong to different families. Contains the graphics and present family.
+    Concurrent([u32; 2]),
+}
+
+impl SwapchainSharing {
+    pub fn new(graphics_family: u32, present_family: u32) -> Self {
+        if graphics_family == present_family {
+            SwapchainSharing::Exclusive
+        } else {
+            SwapchainSharing::Concurrent([graphics_family, present_family])
+        }
+    }
+
+    /// Determines the sharing of swapchain images for a surface based on the queues acquired
+    /// during device creation.
+    pub fn for_surface(device: &DeviceContext, surface: vk::SurfaceKHR) -> Option<Self> {
+        let graphics = device.get_graphics_queue()?.get_family();
+        let present = device.get_present_queue(surface)?.get_family();
+
+        let sharing = Self::new(graphics, present);
+        if let SwapchainSharing::Concurrent(_) = sharing {
+            log::info!("Graphics family {} and present family {} differ. Swapchain images will be shared concurrently", graphics, present);
+        }
+        Some(sharing)
+    }
+
+    pub fn get_sharing_mode(&self) -> vk::SharingMode {
+        match self {
+            SwapchainSharing::Exclusive => vk::SharingMode::EXCLUSIVE,
+            SwapchainSharing::Concurrent(_) => vk::SharingMode::CONCURRENT,
+        }
+    }
+
+    /// Returns the queue family indices to be used in `VkSwapchainCreateInfoKHR`
+    pub fn get_queue_family_indices(&self) -> &[u32] {
+        match self {
+            SwapchainSharing::Exclusive => &[],
+            SwapchainSharing::Concurrent(families) => families,
+        }
+    }
+}
+
+/// Policy used to pick a surface format from the list reported by the surface.
+///
+/// HDR color spaces require the `VK_EXT_swapchain_colorspace` instance extension to be reported by the surface.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SurfaceFormatPolicy {
+    /// Use the first reported format.
+    First,
+    /// Prefer an 8 bit sRGB format with the nonlinear sRGB color space.
+    #[default]
+    PreferSrgb,
+    /// Prefer a 10 bit format with the HDR10 (ST2084) color space. Falls back to [`SurfaceFormatPolicy::PreferSrgb`].
+    PreferHdr10,
+}
+
+impl SurfaceFormatPolicy {
+    const SRGB_FORMATS: &'static [vk::SurfaceFormatKHR] = &[
+        vk::SurfaceFormatKHR { format: vk::Format::B8G8R8A8_SRGB, color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR },
+        vk::SurfaceFormatKHR { format: vk::Format::R8G8B8A8_SRGB, color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR },
+    ];
+
+    const HDR10_FORMATS: &'static [vk::SurfaceFormatKHR] = &[
+        vk::SurfaceFormatKHR { format: vk::Format::A2B10G10R10_UNORM_PACK32, color_space: vk::ColorSpaceKHR::HDR10_ST2084_EXT },
+        vk::SurfaceFormatKHR { format: vk::Format::A2R10G10B10_UNORM_PACK32, color_space: vk::ColorSpaceKHR::HDR10_ST2084_EXT },
+    ];
+
+    /// Selects a format from the formats reported by the surface. Returns [`None`] only if `available` is empty.
+    pub fn select(&self, available: &[vk::SurfaceFormatKHR]) -> Option<vk::SurfaceFormatKHR> {
+        // A single undefined entry means the surface has no preference
+        if available.len() == 1 && available[0].format == vk::Format::UNDEFINED {
+            return Some(match self {
+                SurfaceFormatPolicy::First => available[0],
+                _ => Self::SRGB_FORMATS[0],
+            });
+        }
+
+        let find = |candidates: &[vk::SurfaceFormatKHR]| {
+            candidates.iter().find_map(|candidate| {
+                available.iter().find(|format| format.format == candidate.format && format.color_space == candidate.color_space).copied()
+            })
+        };
+
+        let preferred = match self {
+            SurfaceFormatPolicy::First => None,
+            SurfaceFormatPolicy::PreferSrgb => find(Self::SRGB_FORMATS),
+            SurfaceFormatPolicy::PreferHdr10 => find(Self::HDR10_FORMATS).or_else(|| find(Self::SRGB_FORMATS)),
+        };
+
+        let selected = preferred.or_else(|| available.first().copied());
+        log::debug!("Selected surface format {:?} using policy {:?}", selected, self);
+        selected
+    }
+}
+
+const SWAPCHAIN_IMAGE_RANGE: vk::ImageSubresourceRange = vk::ImageSubresourceRange {
+    aspect_mask: vk::ImageAspectFlags::COLOR,
+    base_mip_level: 0,
+    level_count: 1,
+    base_array_layer: 0,
+    layer_count: 1,
+};
+
+/// The layout a swapchain image is used in between acquiring and presenting it, together with the
+/// stages and accesses using it in that layout.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SwapchainImageState {
+    pub layout: vk::ImageLayout,
+    pub stage_mask: vk::PipelineStageFlags,
+    pub access_mask: vk::AccessFlags,
+}
+
+impl SwapchainImageState {
+    /// The image is rendered to as a color attachment.
+    pub const COLOR_ATTACHMENT: SwapchainImageState = SwapchainImageState::new(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL, vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, vk::AccessFlags::COLOR_ATTACHMENT_WRITE);
+
+    /// The image is written to as a storage image by a compute shader.
+    pub const STORAGE: SwapchainImageState = SwapchainImageState::new(vk::ImageLayout::GENERAL, vk::PipelineStageFlags::COMPUTE_SHADER, vk::AccessFlags::SHADER_WRITE);
+
+    /// The image is written to by transfer commands.
+    pub const TRANSFER_DST: SwapchainImageState = SwapchainImageState::new(vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::PipelineStageFlags::TRANSFER, vk::AccessFlags::TRANSFER_WRITE);
+
+    pub const fn new(layout: vk::ImageLayout, stage_mask: vk::PipelineStageFlags, access_mask: vk::AccessFlags) -> Self {
+        Self { layout, stage_mask, access_mask }
+    }
+
+    /// Returns the state swapchain images created with `usage` are written in. `COLOR_ATTACHMENT`
+    /// is preferred over `STORAGE` which is preferred over `TRANSFER_DST`. Returns [`None`] if the
+    /// usage contains none of them.
+    pub fn for_usage(usage: vk::ImageUsageFlags) -> Option<Self> {
+        if usage.contains(vk::ImageUsageFlags::COLOR_ATTACHMENT) {
+            Some(Self::COLOR_ATTACHMENT)
+        } else if usage.contains(vk::ImageUsageFlags::STORAGE) {
+            Some(Self::STORAGE)
+        } else if usage.contains(vk::ImageUsageFlags::TRANSFER_DST) {
+            Some(Self::TRANSFER_DST)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the barrier transitioning a newly acquired swapchain image into the layout of `state`.
+///
+/// The previous contents of the image are discarded so this is valid independent of the layout
+/// the image was last presented in.
+pub fn make_acquire_barrier(image: vk::Image, state: &SwapchainImageState) -> vk::ImageMemoryBarrier {
+    vk::ImageMemoryBarrier::builder()
+        .old_layout(vk::ImageLayout::UNDEFINED)
+        .new_layout(state.layout)
+        .src_access_mask(vk::AccessFlags::empty())
+        .dst_access_mask(state.access_mask)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(SWAPCHAIN_IMAGE_RANGE)
+        .build()
+}
+
+/// Returns the barrier transitioning a swapchain image used as described by `state` into the
+/// `PRESENT_SRC_KHR` layout.
+pub fn make_present_barrier(image: vk::Image, state: &SwapchainImageState) -> vk::ImageMemoryBarrier {
+    vk::ImageMemoryBarrier::builder()
+        .old_layout(state.layout)
+        .new_layout(vk::ImageLayout::PRESENT_SRC_KHR)
+        .src_access_mask(state.access_mask)
+        .dst_access_mask(vk::AccessFlags::empty())
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(SWAPCHAIN_IMAGE_RANGE)
+        .build()
+}
+
+/// Records the barrier transitioning a newly acquired swapchain image into the layout of `state`.
+/// See [`make_acquire_barrier`].
+///
+/// The acquire semaphore must be waited on in the stages of `state`.
+pub fn record_acquire_barrier(device: &DeviceContext, command_buffer: vk::CommandBuffer, image: vk::Image, state: &SwapchainImageState) {
+    let barrier = make_acquire_barrier(image, state);
+    unsafe {
+        device.vk().cmd_pipeline_barrier(command_buffer, state.stage_mask, state.stage_mask, vk::DependencyFlags::empty(), &[], &[], std::slice::from_ref(&barrier));
+    }
+}
+
+/// Records the barrier transitioning a swapchain image used as described by `state` into the
+/// `PRESENT_SRC_KHR` layout. This must be the last command using the image before it is presented.
+pub fn record_present_barrier(device: &DeviceContext, command_buffer: vk::CommandBuffer, image: vk::Image, state: &SwapchainImageState) {
+    let barrier = make_present_barrier(image, state);
+    unsafe {
+        device.vk().cmd_pipeline_barrier(command_buffer, state.stage_mask, vk::PipelineStageFlags::BOTTOM_OF_PIPE, vk::DependencyFlags::empty(), &[], &[], std::slice::from_ref(&barrier));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn surface_format(format: vk::Format, color_space: vk::ColorSpaceKHR) -> vk::SurfaceFormatKHR {
+        vk::SurfaceFormatKHR { format, color_space }
+    }
+
+    #[test]
+    fn surface_format_policy() {
+        let unorm = surface_format(vk::Format::B8G8R8A8_UNORM, vk::ColorSpaceKHR::SRGB_NONLINEAR);
+        let srgb = surface_format(vk::Format::B8G8R8A8_SRGB, vk::ColorSpaceKHR::SRGB_NONLINEAR);
+        let hdr = surface_format(vk::Format::A2B10G10R10_UNORM_PACK32, vk::ColorSpaceKHR::HDR10_ST2084_EXT);
+        let available = [unorm, srgb, hdr];
+
+        assert_eq!(SurfaceFormatPolicy::First.select(&available), Some(unorm));
+        assert_eq!(SurfaceFormatPolicy::PreferSrgb.select(&available), Some(srgb));
+        assert_eq!(SurfaceFormatPolicy::PreferHdr10.select(&available), Some(hdr));
+        assert_eq!(SurfaceFormatPolicy::PreferHdr10.select(&available[0..2]), Some(srgb));
+        assert_eq!(SurfaceFormatPolicy::PreferSrgb.select(&[unorm]), Some(unorm));
+        assert_eq!(SurfaceFormatPolicy::PreferSrgb.select(&[]), None);
+
+        let undefined = surface_format(vk::Format::UNDEFINED, vk::ColorSpaceKHR::SRGB_NONLINEAR);
+        assert_eq!(SurfaceFormatPolicy::PreferSrgb.select(&[undefined]), Some(srgb));
+    }
+
+    #[test]
+    fn composite_alpha_selection() {
+        let all = vk::CompositeAlphaFlagsKHR::OPAQUE | vk::CompositeAlphaFlagsKHR::INHERIT;
+        assert_eq!(SwapchainCreateDesc::select_composite_alpha(all), vk::CompositeAlphaFlagsKHR::OPAQUE);
+        assert_eq!(SwapchainCreateDesc::select_composite_alpha(vk::CompositeAlphaFlagsKHR::INHERIT), vk::CompositeAlphaFlagsKHR::INHERIT);
+
+        let multiplied = vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED | vk::CompositeAlphaFlagsKHR::POST_MULTIPLIED;
+        assert_eq!(SwapchainCreateDesc::select_composite_alpha(multiplied), vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED);
+    }
+
+    #[test]
+    fn swapchain_sharing() {
+        let exclusive = SwapchainSharing::new(0, 0);
+        assert_eq!(exclusive.get_sharing_mode(), vk::SharingMode::EXCLUSIVE);
+        assert!(exclusive.get_queue_family_indices().is_empty());
+
+        let concurrent = SwapchainSharing::new(0, 2);
+        assert_eq!(concurrent.get_sharing_mode(), vk::SharingMode::CONCURRENT);
+        assert_eq!(concurrent.get_queue_family_indices(), &[0, 2]);
+    }
+
+    #[test]
+    fn swapchain_usage_validation() {
+        let spec = SwapchainImageSpec::make(&Format::B8G8R8A8_SRGB, vk::ColorSpaceKHR::SRGB_NONLINEAR, 800, 600);
+        let capabilities = vk::SurfaceCapabilitiesKHR {
+            supported_usage_flags: vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_DST,
+            ..Default::default()
+        };
+
+        let desc = SwapchainCreateDesc::new(spec, vk::PresentModeKHR::FIFO);
+        assert_eq!(desc.validate_usage(&capabilities), Ok(()));
+
+        let desc = desc.with_usage(vk::ImageUsageFlags::STORAGE | vk::ImageUsageFlags::TRANSFER_DST);
+        assert_eq!(desc.validate_usage(&capabilities), Err(vk::ImageUsageFlags::STORAGE));
+    }
+
+    #[test]
+    fn general_layout_barriers() {
+        let image = <vk::Image as vk::Handle>::from_raw(1);
+        let state = SwapchainImageState::STORAGE;
+
+        let acquire = make_acquire_barrier(image, &state);
+        assert_eq!(acquire.old_layout, vk::ImageLayout::UNDEFINED);
+        assert_eq!(acquire.new_layout, vk::ImageLayout::GENERAL);
+        assert_eq!(acquire.src_access_mask, vk::AccessFlags::empty());
+        assert_eq!(acquire.dst_access_mask, vk::AccessFlags::SHADER_WRITE);
+        assert_eq!(acquire.image, image);
+
+        let present = make_present_barrier(image, &state);
+        assert_eq!(present.old_layout, vk::ImageLayout::GENERAL);
+        assert_eq!(present.new_layout, vk::ImageLayout::PRESENT_SRC_KHR);
+        assert_eq!(present.src_access_mask, vk::AccessFlags::SHADER_WRITE);
+        assert_eq!(present.dst_access_mask, vk::AccessFlags::empty());
+        assert_eq!(present.subresource_range.aspect_mask, vk::ImageAspectFlags::COLOR);
+    }
+}
@@ -1,70 +1,198 @@
-use crate::init::device::{create_device, DeviceCreateError};
+use ash::vk;
+
+use crate::frame::{FrameContext, FrameError, FrameRenderer};
+use crate::init::device::{create_device_with_index, enumerate_devices, DeviceCreateError, PhysicalDeviceSummary};
 use crate::init::initialization_registry::InitializationRegistry;
 use crate::init::instance::{create_instance, InstanceCreateError};
 use crate::window::{RosellaSurface, RosellaWindow};
 
-use crate::init::rosella_features::WindowSurface;
-use crate::objects::ObjectManager;
+use crate::init::rosella_features::{SurfacePresentQueues, WindowSurface};
+use crate::objects::{AllocationError, ObjectManager};
 
 pub use crate::instance::VulkanVersion;
 pub use crate::instance::InstanceContext;
 pub use crate::device::DeviceContext;
+pub use crate::device::DriverInfo;
 
 pub struct Rosella {
     pub instance: InstanceContext,
     pub surface: RosellaSurface,
     pub device: DeviceContext,
     pub object_manager: ObjectManager,
+    window_extent: vk::Extent2D,
+    frame_renderer: Option<FrameRenderer>,
 }
 
 #[derive(Debug)]
 pub enum RosellaCreateError {
-    InstanceCreateError(InstanceCreateError),
-    DeviceCreateError(DeviceCreateError),
+    Instance(InstanceCreateError),
+    /// The window surface could not be created.
+    Surface(vk::Result),
+    Device(DeviceCreateError),
+    /// None of the physical devices support all required features. Contains the names of all
+    /// physical devices that were considered.
+    NoSuitableDevice {
+        candidates: Vec<String>,
+    },
+    Allocation(AllocationError),
+}
+
+impl std::fmt::Display for RosellaCreateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RosellaCreateError::Instance(err) => write!(f, "Failed to create vulkan instance: {:?}", err),
+            RosellaCreateError::Surface(err) => write!(f, "Failed to create window surface: {}", err),
+            RosellaCreateError::Device(err) => write!(f, "Failed to create vulkan device: {:?}", err),
+            RosellaCreateError::NoSuitableDevice { candidates } => {
+                if candidates.is_empty() {
+                    write!(f, "No vulkan capable device found")
+                } else {
+                    write!(f, "No device supports all required features. Available devices: {}", candidates.join(", "))
+                }
+            }
+            RosellaCreateError::Allocation(err) => write!(f, "Failed to create allocator: {:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for RosellaCreateError {
 }
 
 impl From<InstanceCreateError> for RosellaCreateError {
     fn from(err: InstanceCreateError) -> Self {
-        RosellaCreateError::InstanceCreateError(err)
+        RosellaCreateError::Instance(err)
     }
 }
 
 impl From<DeviceCreateError> for RosellaCreateError {
     fn from(err: DeviceCreateError) -> Self {
-        RosellaCreateError::DeviceCreateError(err)
+        RosellaCreateError::Device(err)
+    }
+}
+
+impl From<AllocationError> for RosellaCreateError {
+    fn from(err: AllocationError) -> Self {
+        RosellaCreateError::Allocation(err)
     }
 }
 
 impl Rosella {
-    pub fn new(mut registry: InitializationRegistry, window: &RosellaWindow, application_name: &str) -> Result<Rosella, RosellaCreateError> {
-        log::info!("Starting Rosella");
+    pub fn new(registry: InitializationRegistry, window: &RosellaWindow, application_name: &str) -> Result<Rosella, RosellaCreateError> {
+        Self::new_impl(registry, window, application_name, None)
+    }
 
-        WindowSurface::register_into(&mut registry, &window.handle, true);
+    /// Creates a new rosella instance using the physical device at `device_index` in the list
+    /// returned by [`Rosella::enumerate_devices`].
+    pub fn new_with_device(registry: InitializationRegistry, window: &RosellaWindow, application_name: &str, device_index: usize) -> Result<Rosella, RosellaCreateError> {
+        Self::new_impl(registry, window, application_name, Some(device_index))
+    }
 
-        let now = std::time::Instant::now();
+    /// Lists all physical devices and whether they support the features required by the registry
+    /// without creating a device.
+    ///
+    /// A temporary instance and surface are created for the query.
+    pub fn enumerate_devices(registry: &InitializationRegistry, window: &RosellaWindow, application_name: &str) -> Result<Vec<PhysicalDeviceSummary>, RosellaCreateError> {
+        let mut registry = registry.clone();
+        let (instance, surface) = Self::create_instance_and_surface(&mut registry, window, application_name)?;
+
+        let result = enumerate_devices(&registry, instance);
+        unsafe { surface.ash_surface.destroy_surface(surface.khr_surface, None) };
+
+        Ok(result?)
+    }
 
-        let instance = create_instance(&mut registry, application_name, 0)?;
+    fn new_impl(mut registry: InitializationRegistry, window: &RosellaWindow, application_name: &str, device_index: Option<usize>) -> Result<Rosella, RosellaCreateError> {
+        log::info!("Starting Rosella");
 
-        let surface = RosellaSurface::new(instance.vk(), &instance.get_entry(), window);
+        let now = std::time::Instant::now();
 
-        let device = create_device(&mut registry, instance.clone())?;
+        let (instance, surface) = Self::create_instance_and_surface(&mut registry, window, application_name)?;
+
+        let device_index = match device_index {
+            Some(index) => index,
+            None => Self::select_device(&registry, &instance)?,
+        };
+        let device = create_device_with_index(&registry, instance.clone(), device_index)?;
 
         let elapsed = now.elapsed();
-        println!("Instance & Device Initialization took: {:.2?}", elapsed);
+        log::info!("Instance & Device Initialization took: {:.2?}", elapsed);
+
+        let object_manager = ObjectManager::new(device.clone())?;
 
-        let object_manager = ObjectManager::new(device.clone());
+        let window_size = window.handle.inner_size();
+        let window_extent = vk::Extent2D { width: window_size.width, height: window_size.height };
 
         Ok(Rosella {
             instance,
             surface,
             device,
             object_manager,
+            window_extent,
+            frame_renderer: None,
         })
     }
 
+    /// Returns the index of the first physical device supporting all required features.
+    fn select_device(registry: &InitializationRegistry, instance: &InstanceContext) -> Result<usize, RosellaCreateError> {
+        let devices = enumerate_devices(registry, instance.clone())?;
+
+        devices.iter().find(|device| device.is_suitable())
+            .map(PhysicalDeviceSummary::get_index)
+            .ok_or_else(|| RosellaCreateError::NoSuitableDevice {
+                candidates: devices.iter().map(|device| device.get_name().to_string()).collect(),
+            })
+    }
+
+    /// Creates the instance and window surface and registers the surface dependent features.
+    fn create_instance_and_surface(registry: &mut InitializationRegistry, window: &RosellaWindow, application_name: &str) -> Result<(InstanceContext, RosellaSurface), RosellaCreateError> {
+        WindowSurface::register_into(registry, &window.handle, true);
+
+        let instance = create_instance(registry, application_name, 0)?;
+
+        let surface = RosellaSurface::new(instance.vk(), &instance.get_entry(), window)
+            .map_err(RosellaCreateError::Surface)?;
+        SurfacePresentQueues::register_into(registry, &[surface.khr_surface], true);
+
+        Ok((instance, surface))
+    }
+
     pub fn window_update(&self) {}
 
-    pub fn recreate_swapchain(&self, width: u32, height: u32) {
-        println!("resize to {}x{}", width, height);
+    /// Returns the name of the physical device used for rendering.
+    pub fn device_name(&self) -> &str {
+        self.device.get_device_name()
     }
-}
\ No newline at end of file
+
+    /// Renders a single frame to the window. See [`FrameRenderer::render_frame`].
+    ///
+    /// The swapchain is created on the first call. Returns false if the frame was skipped.
+    pub fn render_frame<F: FnOnce(&FrameContext)>(&mut self, record: F) -> Result<bool, FrameError> {
+        if self.frame_renderer.is_none() {
+            self.frame_renderer = Some(FrameRenderer::new(self.device.clone(), self.surface.ash_surface.clone(), self.surface.khr_surface, self.window_extent)?);
+        }
+
+        self.frame_renderer.as_mut().unwrap().render_frame(record)
+    }
+
+    /// Recreates the swapchain with the new window size before the next frame is rendered.
+    pub fn recreate_swapchain(&mut self, width: u32, height: u32) {
+        self.window_extent = vk::Extent2D { width, height };
+        if let Some(renderer) = &mut self.frame_renderer {
+            renderer.resize(width, height);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_suitable_device_message() {
+        let err = RosellaCreateError::NoSuitableDevice { candidates: Vec::new() };
+        assert_eq!(err.to_string(), "No vulkan capable device found");
+
+        let err = RosellaCreateError::NoSuitableDevice { candidates: vec!["GPU A".to_string(), "GPU B".to_string()] };
+        assert_eq!(err.to_string(), "No device supports all required features. Available devices: GPU A, GPU B");
+    }
+}
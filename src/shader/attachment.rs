@@ -0,0 +1,216 @@
+use ash::vk;
+
+#[derive(Debug)]
+pub enum AttachmentError {
+    /// The number of color attachments does not match the number of attachments used for rendering.
+    CountMismatch {
+        count: usize,
+        expected: usize,
+    },
+    /// More color attachments are used than supported by the device.
+    LimitExceeded {
+        count: usize,
+        limit: u32,
+    },
+    /// Attachments use different blend states but the independentBlend feature is not supported.
+    IndependentBlendUnsupported,
+}
+
+/// The blend equation of a single color attachment.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BlendState {
+    pub src_color_factor: vk::BlendFactor,
+    pub dst_color_factor: vk::BlendFactor,
+    pub color_op: vk::BlendOp,
+    pub src_alpha_factor: vk::BlendFactor,
+    pub dst_alpha_factor: vk::BlendFactor,
+    pub alpha_op: vk::BlendOp,
+}
+
+impl BlendState {
+    /// Standard alpha blending with non premultiplied colors.
+    pub const ALPHA: BlendState = BlendState {
+        src_color_factor: vk::BlendFactor::SRC_ALPHA,
+        dst_color_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+        color_op: vk::BlendOp::ADD,
+        src_alpha_factor: vk::BlendFactor::ONE,
+        dst_alpha_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+        alpha_op: vk::BlendOp::ADD,
+    };
+
+    /// Adds the source to the destination.
+    pub const ADDITIVE: BlendState = BlendState {
+        src_color_factor: vk::BlendFactor::ONE,
+        dst_color_factor: vk::BlendFactor::ONE,
+        color_op: vk::BlendOp::ADD,
+        src_alpha_factor: vk::BlendFactor::ONE,
+        dst_alpha_factor: vk::BlendFactor::ONE,
+        alpha_op: vk::BlendOp::ADD,
+    };
+}
+
+/// The format and blend configuration of a color attachment written by a graphics pipeline.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ColorAttachmentState {
+    pub format: vk::Format,
+    /// The blend equation or [`None`] if blending is disabled
+    pub blend: Option<BlendState>,
+    pub write_mask: vk::ColorComponentFlags,
+}
+
+impl ColorAttachmentState {
+    /// Creates a state writing all components without blending.
+    pub fn new(format: vk::Format) -> Self {
+        Self {
+            format,
+            blend: None,
+            write_mask: vk::ColorComponentFlags::R | vk::ColorComponentFlags::G | vk::ColorComponentFlags::B | vk::ColorComponentFlags::A,
+        }
+    }
+
+    pub fn with_blend(mut self, blend: BlendState) -> Self {
+        self.blend = Some(blend);
+        self
+    }
+
+    pub fn with_write_mask(mut self, write_mask: vk::ColorComponentFlags) -> Self {
+        self.write_mask = write_mask;
+        self
+    }
+
+    pub fn make_blend_attachment_state(&self) -> vk::PipelineColorBlendAttachmentState {
+        let mut state = vk::PipelineColorBlendAttachmentState {
+            color_write_mask: self.write_mask,
+            ..Default::default()
+        };
+
+        if let Some(blend) = &self.blend {
+            state.blend_enable = vk::TRUE;
+            state.src_color_blend_factor = blend.src_color_factor;
+            state.dst_color_blend_factor = blend.dst_color_factor;
+            state.color_blend_op = blend.color_op;
+            state.src_alpha_blend_factor = blend.src_alpha_factor;
+            state.dst_alpha_blend_factor = blend.dst_alpha_factor;
+            state.alpha_blend_op = blend.alpha_op;
+        }
+        state
+    }
+}
+
+/// The color attachments written by a graphics pipeline in attachment index order. Used to build
+/// the color blend state and the attachment formats for dynamic rendering.
+#[derive(Clone, Debug)]
+pub struct ColorAttachments {
+    attachments: Vec<ColorAttachmentState>,
+    blend_states: Vec<vk::PipelineColorBlendAttachmentState>,
+    formats: Vec<vk::Format>,
+}
+
+impl ColorAttachments {
+    pub fn new(attachments: Vec<ColorAttachmentState>) -> Self {
+        let blend_states = attachments.iter().map(ColorAttachmentState::make_blend_attachment_state).collect();
+        let formats = attachments.iter().map(|attachment| attachment.format).collect();
+
+        Self {
+            attachments,
+            blend_states,
+            formats,
+        }
+    }
+
+    pub fn get_attachments(&self) -> &[ColorAttachmentState] {
+        &self.attachments
+    }
+
+    pub fn len(&self) -> usize {
+        self.attachments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.attachments.is_empty()
+    }
+
+    /// Validates that the pipeline writes exactly the color attachments used for rendering.
+    pub fn validate_attachment_count(&self, expected: usize) -> Result<(), AttachmentError> {
+        if self.attachments.len() != expected {
+            return Err(AttachmentError::CountMismatch { count: self.attachments.len(), expected });
+        }
+        Ok(())
+    }
+
+    /// Validates the attachments against the device limits. Using different blend states requires
+    /// the independentBlend feature.
+    pub fn validate_limits(&self, limits: &vk::PhysicalDeviceLimits, features: &vk::PhysicalDeviceFeatures) -> Result<(), AttachmentError> {
+        if self.attachments.len() > limits.max_color_attachments as usize {
+            return Err(AttachmentError::LimitExceeded { count: self.attachments.len(), limit: limits.max_color_attachments });
+        }
+
+        let independent = self.attachments.windows(2)
+            .any(|pair| pair[0].blend != pair[1].blend || pair[0].write_mask != pair[1].write_mask);
+        if independent && features.independent_blend != vk::TRUE {
+            return Err(AttachmentError::IndependentBlendUnsupported);
+        }
+        Ok(())
+    }
+
+    /// Returns the color blend state with one entry per attachment.
+    pub fn make_blend_state(&self) -> vk::PipelineColorBlendStateCreateInfoBuilder<'_> {
+        vk::PipelineColorBlendStateCreateInfo::builder()
+            .attachments(&self.blend_states)
+    }
+
+    /// Returns the attachment formats used to create a pipeline for dynamic rendering.
+    pub fn make_rendering_info(&self) -> vk::PipelineRenderingCreateInfoKHRBuilder<'_> {
+        vk::PipelineRenderingCreateInfoKHR::builder()
+            .color_attachment_formats(&self.formats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_gbuffer() -> ColorAttachments {
+        ColorAttachments::new(vec![
+            ColorAttachmentState::new(vk::Format::R8G8B8A8_SRGB),
+            ColorAttachmentState::new(vk::Format::R16G16B16A16_SFLOAT).with_blend(BlendState::ADDITIVE),
+            ColorAttachmentState::new(vk::Format::R32_SFLOAT).with_write_mask(vk::ColorComponentFlags::R),
+        ])
+    }
+
+    #[test]
+    fn multiple_attachments() {
+        let attachments = make_gbuffer();
+
+        let blend_state = attachments.make_blend_state();
+        assert_eq!(blend_state.attachment_count, 3);
+        let blend_states = unsafe { std::slice::from_raw_parts(blend_state.p_attachments, 3) };
+        assert_eq!(blend_states[0].blend_enable, vk::FALSE);
+        assert_eq!(blend_states[1].blend_enable, vk::TRUE);
+        assert_eq!(blend_states[1].dst_color_blend_factor, vk::BlendFactor::ONE);
+        assert_eq!(blend_states[2].color_write_mask, vk::ColorComponentFlags::R);
+
+        let rendering_info = attachments.make_rendering_info();
+        assert_eq!(rendering_info.color_attachment_count, 3);
+        let formats = unsafe { std::slice::from_raw_parts(rendering_info.p_color_attachment_formats, 3) };
+        assert_eq!(formats, &[vk::Format::R8G8B8A8_SRGB, vk::Format::R16G16B16A16_SFLOAT, vk::Format::R32_SFLOAT]);
+
+        assert!(attachments.validate_attachment_count(3).is_ok());
+        assert!(matches!(attachments.validate_attachment_count(2), Err(AttachmentError::CountMismatch { count: 3, expected: 2 })));
+    }
+
+    #[test]
+    fn attachment_limits() {
+        let attachments = make_gbuffer();
+        let mut limits = vk::PhysicalDeviceLimits { max_color_attachments: 4, ..Default::default() };
+        let mut features = vk::PhysicalDeviceFeatures { independent_blend: vk::TRUE, ..Default::default() };
+
+        assert!(attachments.validate_limits(&limits, &features).is_ok());
+
+        features.independent_blend = vk::FALSE;
+        assert!(matches!(attachments.validate_limits(&limits, &features), Err(AttachmentError::IndependentBlendUnsupported)));
+
+        limits.max_color_attachments = 2;
+        assert!(matches!(attachments.validate_limits(&limits, &features), Err(AttachmentError::LimitExceeded { count: 3, limit: 2 })));
+    }
+}
@@ -0,0 +1,220 @@
+use std::path::{Path, PathBuf};
+
+use ash::vk;
+use shaderc::{CompileOptions, Compiler, IncludeType, OptimizationLevel, ResolvedInclude, ShaderKind, TargetEnv};
+
+use crate::rosella::DeviceContext;
+use crate::shader::{ComputeContext, ComputeShader, GraphicsContext, GraphicsShader};
+
+#[derive(Debug)]
+pub enum ShaderCompileError {
+    /// The shaderc compiler or compile options could not be created.
+    CompilerUnavailable,
+    CompilationError(shaderc::Error),
+    VulkanError(vk::Result),
+}
+
+impl From<shaderc::Error> for ShaderCompileError {
+    fn from(err: shaderc::Error) -> Self {
+        ShaderCompileError::CompilationError(err)
+    }
+}
+
+impl From<vk::Result> for ShaderCompileError {
+    fn from(err: vk::Result) -> Self {
+        ShaderCompileError::VulkanError(err)
+    }
+}
+
+/// Options applied to every shader stage compiled by a [`ShaderCompiler`].
+#[derive(Clone, Default)]
+pub struct ShaderCompileConfig {
+    /// Macro definitions as name and optional value
+    pub macros: Vec<(String, Option<String>)>,
+    /// The optimization level or [`None`] to use the shaderc default
+    pub optimization_level: Option<OptimizationLevel>,
+    /// Directories searched for `#include` directives. Relative includes are first resolved
+    /// against the directory of the including file.
+    pub include_paths: Vec<PathBuf>,
+    pub generate_debug_info: bool,
+}
+
+impl ShaderCompileConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a macro definition
+    pub fn define(mut self, name: &str, value: Option<&str>) -> Self {
+        self.macros.push((name.to_string(), value.map(str::to_string)));
+        self
+    }
+
+    pub fn optimization_level(mut self, level: OptimizationLevel) -> Self {
+        self.optimization_level = Some(level);
+        self
+    }
+
+    pub fn include_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.include_paths.push(path.into());
+        self
+    }
+
+    pub fn generate_debug_info(mut self) -> Self {
+        self.generate_debug_info = true;
+        self
+    }
+
+    /// Creates compile options for this config targeting the specified vulkan version.
+    fn make_options(&self, vulkan_version: u32) -> Result<CompileOptions<'static>, ShaderCompileError> {
+        let mut options = CompileOptions::new().ok_or(ShaderCompileError::CompilerUnavailable)?;
+        options.set_target_env(TargetEnv::Vulkan, vulkan_version);
+
+        for (name, value) in &self.macros {
+            options.add_macro_definition(name, value.as_deref());
+        }
+        if let Some(level) = self.optimization_level {
+            options.set_optimization_level(level);
+        }
+        if self.generate_debug_info {
+            options.set_generate_debug_info();
+        }
+        if !self.include_paths.is_empty() {
+            let include_paths = self.include_paths.clone();
+            options.set_include_callback(move |name, include_type, requesting, _| {
+                resolve_include(&include_paths, name, include_type, requesting)
+            });
+        }
+
+        Ok(options)
+    }
+}
+
+fn resolve_include(include_paths: &[PathBuf], name: &str, include_type: IncludeType, requesting: &str) -> Result<ResolvedInclude, String> {
+    let relative = match include_type {
+        IncludeType::Relative => Path::new(requesting).parent().map(|dir| dir.join(name)),
+        IncludeType::Standard => None,
+    };
+
+    for path in relative.into_iter().chain(include_paths.iter().map(|dir| dir.join(name))) {
+        if let Ok(content) = std::fs::read_to_string(&path) {
+            return Ok(ResolvedInclude {
+                resolved_name: path.to_string_lossy().into_owned(),
+                content,
+            });
+        }
+    }
+
+    Err(format!("Failed to resolve include \"{}\"", name))
+}
+
+/// Compiles glsl shaders into shader modules.
+///
+/// Creating a shaderc compiler is expensive so a single instance should be reused to compile
+/// multiple shaders. The compiler is neither `Send` nor `Sync`, every thread compiling shaders needs
+/// its own instance.
+pub struct ShaderCompiler {
+    device: DeviceContext,
+    compiler: Compiler,
+    options: CompileOptions<'static>,
+}
+
+impl ShaderCompiler {
+    /// Creates a new compiler targeting the vulkan version supported by the loader.
+    pub fn new(device: DeviceContext) -> Result<Self, ShaderCompileError> {
+        Self::new_with_config(device, &ShaderCompileConfig::default())
+    }
+
+    /// Creates a new compiler applying the config to every compilation.
+    pub fn new_with_config(device: DeviceContext, config: &ShaderCompileConfig) -> Result<Self, ShaderCompileError> {
+        let compiler = Compiler::new().ok_or(ShaderCompileError::CompilerUnavailable)?;
+        let options = config.make_options(
+            device.get_entry().try_enumerate_instance_version().ok().flatten().unwrap_or(vk::API_VERSION_1_0)
+        )?;
+
+        Ok(Self {
+            device,
+            compiler,
+            options,
+        })
+    }
+
+    /// Returns the options used for all compilations. Can be used to configure optimization or
+    /// include resolution.
+    pub fn options_mut(&mut self) -> &mut CompileOptions<'static> {
+        &mut self.options
+    }
+
+    /// Compiles the source into spirv.
+    pub fn compile(&mut self, source: &str, kind: ShaderKind, file_name: &str) -> Result<Vec<u32>, ShaderCompileError> {
+        let artifact = self.compiler.compile_into_spirv(source, kind, file_name, "main", Some(&self.options))?;
+        Ok(artifact.as_binary().to_vec())
+    }
+
+    /// Compiles the source and creates a shader module from it.
+    pub fn create_module(&mut self, source: &str, kind: ShaderKind, file_name: &str) -> Result<vk::ShaderModule, ShaderCompileError> {
+        let artifact = self.compiler.compile_into_spirv(source, kind, file_name, "main", Some(&self.options))?;
+        let create_info = vk::ShaderModuleCreateInfo::builder().code(artifact.as_binary());
+
+        Ok(unsafe { self.device.vk().create_shader_module(&create_info, None) }?)
+    }
+
+    pub fn compile_graphics(&mut self, vertex_shader: &str, fragment_shader: &str, graphics_context: GraphicsContext) -> Result<GraphicsShader, ShaderCompileError> {
+        let vertex_shader = self.create_module(vertex_shader, ShaderKind::Vertex, "vertex.glsl")?;
+        let fragment_shader = match self.create_module(fragment_shader, ShaderKind::Fragment, "fragment.glsl") {
+            Ok(module) => module,
+            Err(err) => {
+                unsafe { self.device.vk().destroy_shader_module(vertex_shader, None) };
+                return Err(err);
+            }
+        };
+
+        Ok(GraphicsShader {
+            device: self.device.clone(),
+            graphics_context,
+            vertex_shader,
+            fragment_shader,
+        })
+    }
+
+    pub fn compile_compute(&mut self, compute_shader: &str, compute_context: ComputeContext) -> Result<ComputeShader, ShaderCompileError> {
+        let compute_shader = self.create_module(compute_shader, ShaderKind::Compute, "compute.glsl")?;
+
+        Ok(ComputeShader {
+            device: self.device.clone(),
+            compute_context,
+            compute_shader,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "#version 450
+layout (location = 0) out vec4 outColor;
+void main() {
+#ifdef RED
+    outColor = vec4(1.0, 0.0, 0.0, 1.0);
+#else
+    outColor = vec4(0.0, 0.0, 1.0, 1.0);
+#endif
+}
+";
+
+    fn compile(config: &ShaderCompileConfig) -> Vec<u32> {
+        let mut compiler = Compiler::new().unwrap();
+        let options = config.make_options(vk::API_VERSION_1_0).unwrap();
+        compiler.compile_into_spirv(SOURCE, ShaderKind::Fragment, "test.frag", "main", Some(&options)).unwrap().as_binary().to_vec()
+    }
+
+    #[test]
+    fn macro_definition_changes_spirv() {
+        let default = compile(&ShaderCompileConfig::new());
+        let red = compile(&ShaderCompileConfig::new().define("RED", None));
+
+        assert_ne!(default, red);
+        assert_eq!(red, compile(&ShaderCompileConfig::new().define("RED", Some("1"))));
+    }
+}
@@ -0,0 +1,167 @@
+use ash::prelude::VkResult;
+use ash::vk;
+
+use crate::rosella::DeviceContext;
+
+enum WriteInfo {
+    Buffer(usize),
+    Image(usize),
+}
+
+struct PendingWrite {
+    set: vk::DescriptorSet,
+    binding: u32,
+    array_element: u32,
+    descriptor_type: vk::DescriptorType,
+    info: WriteInfo,
+}
+
+/// Accumulates descriptor writes and submits them in a single vkUpdateDescriptorSets call.
+///
+/// The buffer and image infos are stored inside the writer so they stay alive until
+/// [`DescriptorWriter::update`] is called.
+///
+/// The writer can also be used to push descriptors with [`DescriptorWriter::push`]. In that case
+/// the destination set of the queued writes is ignored and may be null.
+#[derive(Default)]
+pub struct DescriptorWriter {
+    buffer_infos: Vec<vk::DescriptorBufferInfo>,
+    image_infos: Vec<vk::DescriptorImageInfo>,
+    writes: Vec<PendingWrite>,
+}
+
+impl DescriptorWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a write of a buffer descriptor to element 0 of the binding.
+    pub fn write_buffer(&mut self, set: vk::DescriptorSet, binding: u32, descriptor_type: vk::DescriptorType, buffer_info: vk::DescriptorBufferInfo) -> &mut Self {
+        self.write_buffer_element(set, binding, 0, descriptor_type, buffer_info)
+    }
+
+    /// Queues a write of a buffer descriptor to some element of an array binding.
+    pub fn write_buffer_element(&mut self, set: vk::DescriptorSet, binding: u32, array_element: u32, descriptor_type: vk::DescriptorType, buffer_info: vk::DescriptorBufferInfo) -> &mut Self {
+        self.buffer_infos.push(buffer_info);
+        self.writes.push(PendingWrite {
+            set,
+            binding,
+            array_element,
+            descriptor_type,
+            info: WriteInfo::Buffer(self.buffer_infos.len() - 1),
+        });
+        self
+    }
+
+    /// Queues a write of an image descriptor to element 0 of the binding.
+    pub fn write_image(&mut self, set: vk::DescriptorSet, binding: u32, descriptor_type: vk::DescriptorType, image_info: vk::DescriptorImageInfo) -> &mut Self {
+        self.write_image_element(set, binding, 0, descriptor_type, image_info)
+    }
+
+    /// Queues a write of an image descriptor to some element of an array binding.
+    pub fn write_image_element(&mut self, set: vk::DescriptorSet, binding: u32, array_element: u32, descriptor_type: vk::DescriptorType, image_info: vk::DescriptorImageInfo) -> &mut Self {
+        self.image_infos.push(image_info);
+        self.writes.push(PendingWrite {
+            set,
+            binding,
+            array_element,
+            descriptor_type,
+            info: WriteInfo::Image(self.image_infos.len() - 1),
+        });
+        self
+    }
+
+    /// Queues a write of a sampler descriptor to element 0 of the binding.
+    pub fn write_sampler(&mut self, set: vk::DescriptorSet, binding: u32, sampler: vk::Sampler) -> &mut Self {
+        let image_info = vk::DescriptorImageInfo::builder()
+            .sampler(sampler)
+            .build();
+
+        self.write_image(set, binding, vk::DescriptorType::SAMPLER, image_info)
+    }
+
+    /// Returns the number of queued writes.
+    pub fn len(&self) -> usize {
+        self.writes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /// Builds the write structs. The returned structs point into the info vecs of this writer.
+    fn build_writes(&self) -> Vec<vk::WriteDescriptorSet> {
+        self.writes.iter().map(|write| {
+            let builder = vk::WriteDescriptorSet::builder()
+                .dst_set(write.set)
+                .dst_binding(write.binding)
+                .dst_array_element(write.array_element)
+                .descriptor_type(write.descriptor_type);
+
+            match write.info {
+                WriteInfo::Buffer(index) => builder.buffer_info(std::slice::from_ref(&self.buffer_infos[index])).build(),
+                WriteInfo::Image(index) => builder.image_info(std::slice::from_ref(&self.image_infos[index])).build(),
+            }
+        }).collect()
+    }
+
+    /// Issues all queued writes in a single vkUpdateDescriptorSets call.
+    pub fn update(self, device: &DeviceContext) {
+        if self.is_empty() {
+            return;
+        }
+
+        let writes = self.build_writes();
+        unsafe { device.vk().update_descriptor_sets(writes.as_slice(), &[]) };
+    }
+
+    /// Records all queued writes into the command buffer using vkCmdPushDescriptorSetKHR. Requires
+    /// the [`crate::init::rosella_features::PushDescriptorDevice`] feature.
+    pub fn push(&self, device: &DeviceContext, command_buffer: vk::CommandBuffer, bind_point: vk::PipelineBindPoint, layout: vk::PipelineLayout, set: u32) -> VkResult<()> {
+        let push_descriptor = device.get_extension::<ash::extensions::khr::PushDescriptor>()
+            .ok_or(vk::Result::ERROR_EXTENSION_NOT_PRESENT)?;
+
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let writes = self.build_writes();
+        unsafe { push_descriptor.cmd_push_descriptor_set(command_buffer, bind_point, layout, set, writes.as_slice()) };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ash::vk::Handle;
+
+    #[test]
+    fn build_writes() {
+        let set = vk::DescriptorSet::from_raw(1);
+        let buffer = vk::Buffer::from_raw(2);
+        let sampler = vk::Sampler::from_raw(3);
+
+        let mut writer = DescriptorWriter::new();
+        writer.write_sampler(set, 1, sampler)
+            .write_buffer_element(set, 0, 2, vk::DescriptorType::STORAGE_BUFFER, vk::DescriptorBufferInfo {
+                buffer,
+                offset: 16,
+                range: 64,
+            });
+
+        let writes = writer.build_writes();
+        assert_eq!(writes.len(), 2);
+
+        assert_eq!(writes[0].dst_binding, 1);
+        assert_eq!(writes[0].descriptor_type, vk::DescriptorType::SAMPLER);
+        assert_eq!(writes[0].descriptor_count, 1);
+        assert_eq!(unsafe { (*writes[0].p_image_info).sampler }, sampler);
+
+        assert_eq!(writes[1].dst_binding, 0);
+        assert_eq!(writes[1].dst_array_element, 2);
+        assert_eq!(writes[1].descriptor_count, 1);
+        assert_eq!(unsafe { (*writes[1].p_buffer_info).buffer }, buffer);
+        assert_eq!(unsafe { (*writes[1].p_buffer_info).offset }, 16);
+    }
+}
@@ -0,0 +1,53 @@
+use ash::prelude::VkResult;
+use ash::vk;
+
+use crate::rosella::DeviceContext;
+
+macro_rules! define_handle {
+    ($(#[$doc:meta])* $name:ident, $vk_type:ty, $create_info:ty, $create_fn:ident, $destroy_fn:ident) => {
+        $(#[$doc])*
+        pub struct $name {
+            device: DeviceContext,
+            handle: $vk_type,
+        }
+
+        impl $name {
+            pub fn new(device: DeviceContext, create_info: &$create_info) -> VkResult<Self> {
+                let handle = unsafe { device.vk().$create_fn(create_info, None) }?;
+                Ok(Self { device, handle })
+            }
+
+            /// Takes ownership of a handle created by the caller. The handle will be destroyed
+            /// when the returned object is dropped.
+            pub fn from_raw(device: DeviceContext, handle: $vk_type) -> Self {
+                Self { device, handle }
+            }
+
+            pub fn get_handle(&self) -> $vk_type {
+                self.handle
+            }
+        }
+
+        impl Drop for $name {
+            fn drop(&mut self) {
+                unsafe { self.device.vk().$destroy_fn(self.handle, None) };
+            }
+        }
+    }
+}
+
+define_handle!(
+    /// A descriptor set layout which is destroyed when dropped.
+    DescriptorSetLayoutHandle, vk::DescriptorSetLayout, vk::DescriptorSetLayoutCreateInfo, create_descriptor_set_layout, destroy_descriptor_set_layout
+);
+
+define_handle!(
+    /// A descriptor pool which is destroyed when dropped. All sets allocated from the pool are
+    /// freed with it.
+    DescriptorPoolHandle, vk::DescriptorPool, vk::DescriptorPoolCreateInfo, create_descriptor_pool, destroy_descriptor_pool
+);
+
+define_handle!(
+    /// A pipeline layout which is destroyed when dropped.
+    PipelineLayoutHandle, vk::PipelineLayout, vk::PipelineLayoutCreateInfo, create_pipeline_layout, destroy_pipeline_layout
+);
@@ -1,4 +1,12 @@
+pub mod attachment;
+pub mod compiler;
+pub mod descriptor;
+pub mod handle;
 pub mod shader;
 pub mod vertex;
 
-pub use shader::{ComputeContext, ComputeShader, GraphicsContext, GraphicsShader};
+pub use attachment::{AttachmentError, BlendState, ColorAttachmentState, ColorAttachments};
+pub use compiler::{ShaderCompileConfig, ShaderCompileError, ShaderCompiler};
+pub use descriptor::DescriptorWriter;
+pub use handle::{DescriptorPoolHandle, DescriptorSetLayoutHandle, PipelineLayoutHandle};
+pub use shader::{ComputeContext, ComputeShader, GraphicsContext, GraphicsShader, LayoutError, PipelineError, Uniform, UniformType};
@@ -1,16 +1,174 @@
 use crate::shader::vertex::VertexFormat;
-use ash::vk::{ShaderModule, ShaderModuleCreateInfo};
+use ash::vk;
+use ash::vk::ShaderModule;
 use ash::{Device, Entry};
-use shaderc::{CompileOptions, Compiler, ShaderKind, TargetEnv};
 use std::collections::HashSet;
+use std::ffi::CStr;
 use std::rc::Rc;
 use std::sync::Arc;
 use crate::rosella::DeviceContext;
+use crate::init::rosella_features::DescriptorIndexingDevice;
+use crate::shader::compiler::{ShaderCompileConfig, ShaderCompiler};
+
+/// The kind of resource bound to a uniform.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum UniformType {
+    UniformBuffer,
+    DynamicUniformBuffer,
+    StorageBuffer,
+    DynamicStorageBuffer,
+    ImageSampler,
+    SampledImage,
+    Sampler,
+    StorageImage,
+    InputAttachment,
+}
+
+impl UniformType {
+    /// Returns the types whose per stage limits descriptors of this type count towards. Combined
+    /// image samplers count towards both the sampler and sampled image limits.
+    fn limit_types(&self) -> &'static [UniformType] {
+        match self {
+            UniformType::UniformBuffer | UniformType::DynamicUniformBuffer => &[UniformType::UniformBuffer],
+            UniformType::StorageBuffer | UniformType::DynamicStorageBuffer => &[UniformType::StorageBuffer],
+            UniformType::ImageSampler => &[UniformType::Sampler, UniformType::SampledImage],
+            UniformType::SampledImage => &[UniformType::SampledImage],
+            UniformType::Sampler => &[UniformType::Sampler],
+            UniformType::StorageImage => &[UniformType::StorageImage],
+            UniformType::InputAttachment => &[UniformType::InputAttachment],
+        }
+    }
+
+    /// Returns the device limit on the number of descriptors of this type accessible to a single shader stage.
+    pub fn max_per_stage(&self, limits: &vk::PhysicalDeviceLimits) -> u32 {
+        match self {
+            UniformType::UniformBuffer | UniformType::DynamicUniformBuffer => limits.max_per_stage_descriptor_uniform_buffers,
+            UniformType::StorageBuffer | UniformType::DynamicStorageBuffer => limits.max_per_stage_descriptor_storage_buffers,
+            UniformType::ImageSampler => limits.max_per_stage_descriptor_samplers.min(limits.max_per_stage_descriptor_sampled_images),
+            UniformType::SampledImage => limits.max_per_stage_descriptor_sampled_images,
+            UniformType::Sampler => limits.max_per_stage_descriptor_samplers,
+            UniformType::StorageImage => limits.max_per_stage_descriptor_storage_images,
+            UniformType::InputAttachment => limits.max_per_stage_descriptor_input_attachments,
+        }
+    }
+
+    /// Returns the vulkan descriptor type used for this uniform type.
+    pub fn as_descriptor_type(&self) -> vk::DescriptorType {
+        match self {
+            UniformType::UniformBuffer => vk::DescriptorType::UNIFORM_BUFFER,
+            UniformType::DynamicUniformBuffer => vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC,
+            UniformType::StorageBuffer => vk::DescriptorType::STORAGE_BUFFER,
+            UniformType::DynamicStorageBuffer => vk::DescriptorType::STORAGE_BUFFER_DYNAMIC,
+            UniformType::ImageSampler => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            UniformType::SampledImage => vk::DescriptorType::SAMPLED_IMAGE,
+            UniformType::Sampler => vk::DescriptorType::SAMPLER,
+            UniformType::StorageImage => vk::DescriptorType::STORAGE_IMAGE,
+            UniformType::InputAttachment => vk::DescriptorType::INPUT_ATTACHMENT,
+        }
+    }
+
+    /// Returns the graphics shader stages which can access a uniform of this type. Input attachments
+    /// can only be read from fragment shaders.
+    pub fn stage_flags(&self) -> vk::ShaderStageFlags {
+        match self {
+            UniformType::InputAttachment => vk::ShaderStageFlags::FRAGMENT,
+            _ => vk::ShaderStageFlags::ALL_GRAPHICS,
+        }
+    }
+}
 
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub struct Uniform {
     pub name: String,
-    //TODO: the rest of this
+    pub set: u32,
+    pub binding: u32,
+    pub uniform_type: UniformType,
+    /// The number of descriptors in the binding. If `variable_count` is set this is the upper bound.
+    pub count: u32,
+    /// If true the binding is a partially bound array with a variable descriptor count. This must be
+    /// the last binding in its set and requires the descriptor indexing device feature.
+    pub variable_count: bool,
+}
+
+impl Uniform {
+    pub fn new(name: &str, set: u32, binding: u32, uniform_type: UniformType) -> Self {
+        Self {
+            name: name.to_string(),
+            set,
+            binding,
+            uniform_type,
+            count: 1,
+            variable_count: false,
+        }
+    }
+
+    pub fn with_count(mut self, count: u32) -> Self {
+        self.count = count;
+        self
+    }
+
+    pub fn with_variable_count(mut self, max_count: u32) -> Self {
+        self.count = max_count;
+        self.variable_count = true;
+        self
+    }
+}
+
+/// An error that may occur when creating the descriptor layout of a shader.
+#[derive(Debug)]
+pub enum LayoutError {
+    /// Two uniforms use the same binding in the same set.
+    DuplicateBinding {
+        set: u32,
+        binding: u32,
+        first: String,
+        second: String,
+    },
+    /// A uniform has a count of 0.
+    EmptyBinding(String),
+    /// A variable count uniform is not the last binding in its set.
+    VariableCountNotLast(String),
+    /// A variable count uniform is used but the descriptor indexing feature is not enabled.
+    VariableCountUnsupported(String),
+    /// The total number of descriptors of a type exceeds the per stage device limit.
+    LimitExceeded {
+        uniform_type: UniformType,
+        count: u32,
+        limit: u32,
+    },
+    /// The push constant block is larger than the device limit.
+    PushConstantSize {
+        size: u32,
+        limit: u32,
+    },
+    /// The local size is 0 in a dimension or exceeds the compute work group limits of the device.
+    InvalidLocalSize([u32; 3]),
+    /// The provided push constants do not match the size declared in the context.
+    PushConstantSizeMismatch {
+        size: u32,
+        expected: u32,
+    },
+    VulkanError(vk::Result),
+}
+
+impl From<vk::Result> for LayoutError {
+    fn from(err: vk::Result) -> Self {
+        LayoutError::VulkanError(err)
+    }
+}
+
+/// An error that may occur when creating a pipeline.
+#[derive(Debug)]
+pub enum PipelineError {
+    /// No shaders were provided to create pipelines from.
+    NoShaders,
+    VulkanError(vk::Result),
+}
+
+impl From<vk::Result> for PipelineError {
+    fn from(err: vk::Result) -> Self {
+        PipelineError::VulkanError(err)
+    }
 }
 
 pub struct GraphicsContext {
@@ -22,6 +180,158 @@ pub struct GraphicsContext {
     pub vertex_format: VertexFormat,
 }
 
+impl GraphicsContext {
+    /// Returns all uniforms of the context sorted by set and binding.
+    fn sorted_uniforms(&self) -> Vec<&Uniform> {
+        let mut uniforms: Vec<_> = self.mutable_uniforms.iter().chain(self.push_uniforms.iter()).collect();
+        uniforms.sort_by(|a, b| {
+            a.set.cmp(&b.set)
+                .then_with(|| a.binding.cmp(&b.binding))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        uniforms
+    }
+
+    /// Validates that no two uniforms share a binding in the same set, that no uniform is empty and
+    /// that variable count uniforms are the last binding of their set.
+    pub fn validate(&self) -> Result<(), LayoutError> {
+        let uniforms = self.sorted_uniforms();
+        for pair in uniforms.windows(2) {
+            if pair[0].set == pair[1].set && pair[0].binding == pair[1].binding {
+                return Err(LayoutError::DuplicateBinding {
+                    set: pair[0].set,
+                    binding: pair[0].binding,
+                    first: pair[0].name.clone(),
+                    second: pair[1].name.clone(),
+                });
+            }
+            if pair[0].variable_count && pair[0].set == pair[1].set {
+                return Err(LayoutError::VariableCountNotLast(pair[0].name.clone()));
+            }
+        }
+        for uniform in &uniforms {
+            if uniform.count == 0 {
+                return Err(LayoutError::EmptyBinding(uniform.name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates that the descriptor counts of all uniforms fit into the per stage limits of a device.
+    /// Since all uniforms are visible to all graphics stages the counts are summed across all sets.
+    pub fn validate_limits(&self, limits: &vk::PhysicalDeviceLimits) -> Result<(), LayoutError> {
+        let mut totals: Vec<(UniformType, u32)> = Vec::new();
+        for uniform in self.mutable_uniforms.iter().chain(self.push_uniforms.iter()) {
+            for uniform_type in uniform.uniform_type.limit_types() {
+                match totals.iter_mut().find(|(t, _)| t == uniform_type) {
+                    Some((_, total)) => *total += uniform.count,
+                    None => totals.push((*uniform_type, uniform.count)),
+                }
+            }
+        }
+
+        for (uniform_type, count) in totals {
+            let limit = uniform_type.max_per_stage(limits);
+            if count > limit {
+                return Err(LayoutError::LimitExceeded { uniform_type, count, limit });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of descriptor sets used by this context.
+    pub fn set_count(&self) -> u32 {
+        self.mutable_uniforms.iter().chain(self.push_uniforms.iter()).map(|uniform| uniform.set + 1).max().unwrap_or(0)
+    }
+
+    /// Returns the pool sizes needed to allocate `copies` instances of every descriptor set of this
+    /// context. Variable count uniforms are counted with their upper bound.
+    pub fn descriptor_pool_sizes(&self, copies: u32) -> Vec<vk::DescriptorPoolSize> {
+        Self::combined_descriptor_pool_sizes(&[self], copies)
+    }
+
+    /// Returns the pool sizes needed to allocate `copies` instances of every descriptor set of all
+    /// provided contexts. The sizes are sorted by descriptor type.
+    pub fn combined_descriptor_pool_sizes(contexts: &[&GraphicsContext], copies: u32) -> Vec<vk::DescriptorPoolSize> {
+        let mut sizes: Vec<vk::DescriptorPoolSize> = Vec::new();
+        let uniforms = contexts.iter().flat_map(|context| context.mutable_uniforms.iter().chain(context.push_uniforms.iter()));
+        for uniform in uniforms {
+            let ty = uniform.uniform_type.as_descriptor_type();
+            let count = uniform.count * copies;
+            match sizes.iter_mut().find(|size| size.ty == ty) {
+                Some(size) => size.descriptor_count += count,
+                None => sizes.push(vk::DescriptorPoolSize { ty, descriptor_count: count }),
+            }
+        }
+        sizes.sort_by_key(|size| size.ty.as_raw());
+        sizes
+    }
+
+    /// Creates one descriptor set layout for each set used by this context. The returned vec is
+    /// indexed by set. Sets without any uniforms receive an empty layout.
+    pub fn create_layout(&self, device: &DeviceContext) -> Result<Vec<vk::DescriptorSetLayout>, LayoutError> {
+        self.validate()?;
+
+        let properties = unsafe { device.get_instance().vk().get_physical_device_properties(*device.get_physical_device()) };
+        self.validate_limits(&properties.limits)?;
+
+        let descriptor_indexing = DescriptorIndexingDevice::is_enabled(device);
+
+        let mut set_bindings: Vec<Vec<vk::DescriptorSetLayoutBinding>> = vec![Vec::new(); self.set_count() as usize];
+        let mut set_flags: Vec<Vec<vk::DescriptorBindingFlags>> = vec![Vec::new(); self.set_count() as usize];
+        for uniform in self.sorted_uniforms() {
+            let flags = if uniform.variable_count {
+                if !descriptor_indexing {
+                    return Err(LayoutError::VariableCountUnsupported(uniform.name.clone()));
+                }
+                vk::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT | vk::DescriptorBindingFlags::PARTIALLY_BOUND
+            } else {
+                vk::DescriptorBindingFlags::empty()
+            };
+
+            set_bindings[uniform.set as usize].push(vk::DescriptorSetLayoutBinding::builder()
+                .binding(uniform.binding)
+                .descriptor_type(uniform.uniform_type.as_descriptor_type())
+                .descriptor_count(uniform.count)
+                .stage_flags(uniform.uniform_type.stage_flags())
+                .build()
+            );
+            set_flags[uniform.set as usize].push(flags);
+        }
+
+        let mut layouts = Vec::with_capacity(set_bindings.len());
+        for (bindings, flags) in set_bindings.iter().zip(set_flags.iter()) {
+            let mut flags_info = vk::DescriptorSetLayoutBindingFlagsCreateInfo::builder()
+                .binding_flags(flags.as_slice());
+
+            let mut create_info = vk::DescriptorSetLayoutCreateInfo::builder()
+                .bindings(bindings.as_slice());
+            if descriptor_indexing {
+                create_info = create_info.push_next(&mut flags_info);
+            }
+
+            match unsafe { device.vk().create_descriptor_set_layout(&create_info, None) } {
+                Ok(layout) => layouts.push(layout),
+                Err(err) => {
+                    for layout in layouts {
+                        unsafe { device.vk().destroy_descriptor_set_layout(layout, None) };
+                    }
+                    return Err(err.into());
+                }
+            }
+        }
+
+        Ok(layouts)
+    }
+
+    /// Creates a pipeline layout using the provided set layouts in order.
+    pub fn create_pipeline_layout(&self, device: &DeviceContext, set_layouts: &[vk::DescriptorSetLayout]) -> Result<vk::PipelineLayout, LayoutError> {
+        let create_info = vk::PipelineLayoutCreateInfo::builder()
+            .set_layouts(set_layouts);
+
+        Ok(unsafe { device.vk().create_pipeline_layout(&create_info, None) }?)
+    }
+}
 
 pub struct ShaderStage {}
 
@@ -29,6 +339,53 @@ pub struct ShaderStage {}
 pub struct ComputeContext {
     /// The stage at when the compute shader will be run.
     pub state: ShaderStage,
+    /// The size in bytes of the push constant block of the shader. 0 if no push constants are used.
+    pub push_constant_size: u32,
+    /// The local work group size declared by the shader. Must not be 0 in any dimension.
+    pub local_size: [u32; 3],
+}
+
+impl ComputeContext {
+    /// Returns the push constant range used by the compute stage.
+    pub fn push_constant_range(&self) -> Option<vk::PushConstantRange> {
+        if self.push_constant_size == 0 {
+            return None;
+        }
+
+        Some(vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::COMPUTE,
+            offset: 0,
+            size: self.push_constant_size,
+        })
+    }
+
+    /// Validates that the push constant block and the local size fit into the device limits.
+    pub fn validate_limits(&self, limits: &vk::PhysicalDeviceLimits) -> Result<(), LayoutError> {
+        if self.push_constant_size > limits.max_push_constants_size {
+            return Err(LayoutError::PushConstantSize { size: self.push_constant_size, limit: limits.max_push_constants_size });
+        }
+
+        let invocations = self.local_size.iter().try_fold(1u32, |total, size| total.checked_mul(*size));
+        let exceeds_size = self.local_size.iter().zip(limits.max_compute_work_group_size.iter()).any(|(size, limit)| size > limit);
+        if self.local_size.contains(&0) || exceeds_size || invocations.is_none_or(|invocations| invocations > limits.max_compute_work_group_invocations) {
+            return Err(LayoutError::InvalidLocalSize(self.local_size));
+        }
+        Ok(())
+    }
+
+    /// Creates a pipeline layout using the provided set layouts in order and the push constant range
+    /// of this context.
+    pub fn create_pipeline_layout(&self, device: &DeviceContext, set_layouts: &[vk::DescriptorSetLayout]) -> Result<vk::PipelineLayout, LayoutError> {
+        let properties = unsafe { device.get_instance().vk().get_physical_device_properties(*device.get_physical_device()) };
+        self.validate_limits(&properties.limits)?;
+
+        let push_constant_ranges: Vec<_> = self.push_constant_range().into_iter().collect();
+        let create_info = vk::PipelineLayoutCreateInfo::builder()
+            .set_layouts(set_layouts)
+            .push_constant_ranges(push_constant_ranges.as_slice());
+
+        Ok(unsafe { device.vk().create_pipeline_layout(&create_info, None) }?)
+    }
 }
 
 /// Shaders & context needed to render a object.
@@ -41,56 +398,26 @@ pub struct GraphicsShader {
 
 /// Shaders & context needed to run compute operations through shaders.
 pub struct ComputeShader {
+    pub device: DeviceContext,
     pub compute_context: ComputeContext,
     pub compute_shader: ShaderModule,
 }
 
 impl GraphicsShader {
     /// Creates a new GraphicsShader based on glsl shaders.
+    ///
+    /// This creates a temporary [`ShaderCompiler`]. Use a shared compiler when compiling multiple
+    /// shaders.
     pub fn new(
         device: DeviceContext,
         vertex_shader: String,
         fragment_shader: String,
         graphics_context: GraphicsContext,
+        config: &ShaderCompileConfig,
     ) -> GraphicsShader {
-        let mut compiler = Compiler::new().unwrap();
-        let mut options = CompileOptions::new().unwrap();
-
-        options.set_target_env(
-            TargetEnv::Vulkan,
-            device.get_entry().try_enumerate_instance_version().ok().flatten().unwrap(),
-        );
-
-        let vertex_shader = unsafe {
-            device.vk().create_shader_module(
-                &ShaderModuleCreateInfo::builder().code(
-                    compiler
-                        .compile_into_spirv(&vertex_shader, ShaderKind::Vertex, "vertex.glsl", "main", Some(&options))
-                        .expect("Failed to compile the VertexShader.")
-                        .as_binary(),
-                ),
-                None,
-            )
-        }.unwrap();
-
-        let fragment_shader = unsafe {
-            device.vk().create_shader_module(
-                &ShaderModuleCreateInfo::builder().code(
-                    compiler
-                        .compile_into_spirv(&fragment_shader, ShaderKind::Fragment, "fragment.glsl", "main", Some(&options))
-                        .expect("Failed to compile the FragmentShader.")
-                        .as_binary(),
-                ),
-                None,
-            )
-        }.unwrap();
-
-        GraphicsShader {
-            device,
-            graphics_context,
-            vertex_shader,
-            fragment_shader,
-        }
+        ShaderCompiler::new_with_config(device, config)
+            .and_then(|mut compiler| compiler.compile_graphics(&vertex_shader, &fragment_shader, graphics_context))
+            .expect("Failed to compile the GraphicsShader.")
     }
 
     /// Sends a command to run the compute shader.
@@ -106,6 +433,299 @@ impl Drop for GraphicsShader {
     }
 }
 
+impl ComputeShader {
+    /// Creates a compute pipeline using the `main` entry point of the shader.
+    ///
+    /// If a base pipeline is provided the new pipeline is created as a derivative of it which may
+    /// speed up creation of similar pipelines. The base pipeline must have been created allowing
+    /// derivatives. If `allow_derivatives` is true the new pipeline may itself be used as a base
+    /// pipeline.
+    pub fn create_pipeline(&self, device: &DeviceContext, layout: vk::PipelineLayout, base_pipeline: Option<vk::Pipeline>, allow_derivatives: bool) -> Result<vk::Pipeline, PipelineError> {
+        let mut flags = vk::PipelineCreateFlags::empty();
+        if allow_derivatives {
+            flags |= vk::PipelineCreateFlags::ALLOW_DERIVATIVES;
+        }
+
+        let mut create_info = self.make_create_info(layout, flags);
+        if let Some(base_pipeline) = base_pipeline {
+            create_info.flags |= vk::PipelineCreateFlags::DERIVATIVE;
+            create_info.base_pipeline_handle = base_pipeline;
+        }
+
+        let pipelines = unsafe { device.vk().create_compute_pipelines(vk::PipelineCache::null(), std::slice::from_ref(&create_info), None) }
+            .map_err(|(_, err)| err)?;
+        Ok(pipelines[0])
+    }
+
+    /// Creates the pipelines for multiple shaders in a single call. This is considerably faster than
+    /// creating each pipeline individually.
+    ///
+    /// The first pipeline is used as the base pipeline for all others. Returns the pipelines in the
+    /// same order as the shaders. If creation fails no pipeline is returned.
+    pub fn create_pipelines(device: &DeviceContext, shaders: &[(&ComputeShader, vk::PipelineLayout)]) -> Result<Vec<vk::Pipeline>, PipelineError> {
+        if shaders.is_empty() {
+            return Err(PipelineError::NoShaders);
+        }
+
+        let create_infos: Vec<_> = shaders.iter().enumerate().map(|(index, (shader, layout))| {
+            let mut create_info = shader.make_create_info(*layout, Self::batch_flags(index, shaders.len()));
+            if index != 0 {
+                create_info.base_pipeline_index = 0;
+            }
+            create_info
+        }).collect();
+
+        match unsafe { device.vk().create_compute_pipelines(vk::PipelineCache::null(), create_infos.as_slice(), None) } {
+            Ok(pipelines) => Ok(pipelines),
+            Err((pipelines, err)) => {
+                for pipeline in pipelines.into_iter().filter(|pipeline| *pipeline != vk::Pipeline::null()) {
+                    unsafe { device.vk().destroy_pipeline(pipeline, None) };
+                }
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Returns the create flags of the pipeline at `index` in a batch of `count` pipelines. Only the
+    /// base pipeline allows derivatives and only if there are other pipelines deriving from it.
+    fn batch_flags(index: usize, count: usize) -> vk::PipelineCreateFlags {
+        if index != 0 {
+            vk::PipelineCreateFlags::DERIVATIVE
+        } else if count > 1 {
+            vk::PipelineCreateFlags::ALLOW_DERIVATIVES
+        } else {
+            vk::PipelineCreateFlags::empty()
+        }
+    }
+
+    fn make_create_info(&self, layout: vk::PipelineLayout, flags: vk::PipelineCreateFlags) -> vk::ComputePipelineCreateInfo {
+        let stage = vk::PipelineShaderStageCreateInfo::builder()
+            .stage(vk::ShaderStageFlags::COMPUTE)
+            .module(self.compute_shader)
+            .name(CStr::from_bytes_with_nul(b"main\0").unwrap());
+
+        vk::ComputePipelineCreateInfo::builder()
+            .flags(flags)
+            .stage(*stage)
+            .layout(layout)
+            .base_pipeline_index(-1)
+            .build()
+    }
+
+    /// Records a dispatch of the shader. The push constants must match the size declared in the
+    /// compute context.
+    pub fn dispatch(&self, device: &DeviceContext, command_buffer: vk::CommandBuffer, pipeline: vk::Pipeline, layout: vk::PipelineLayout, push_constants: &[u8], group_count: [u32; 3]) -> Result<(), LayoutError> {
+        if push_constants.len() != self.compute_context.push_constant_size as usize {
+            return Err(LayoutError::PushConstantSizeMismatch { size: push_constants.len() as u32, expected: self.compute_context.push_constant_size });
+        }
+
+        unsafe {
+            device.vk().cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::COMPUTE, pipeline);
+            if !push_constants.is_empty() {
+                device.vk().cmd_push_constants(command_buffer, layout, vk::ShaderStageFlags::COMPUTE, 0, push_constants);
+            }
+            device.vk().cmd_dispatch(command_buffer, group_count[0], group_count[1], group_count[2]);
+        }
+        Ok(())
+    }
+
+    /// Records a dispatch covering a work domain of `domain` invocations. The group count in each
+    /// dimension is the domain size divided by the local size of the compute context rounded up so
+    /// the shader must discard out of bounds invocations in the last group.
+    pub fn dispatch_over(&self, device: &DeviceContext, command_buffer: vk::CommandBuffer, pipeline: vk::Pipeline, layout: vk::PipelineLayout, push_constants: &[u8], domain: [u32; 3]) -> Result<(), LayoutError> {
+        let local_size = self.compute_context.local_size;
+        let group_count = Self::group_count(domain, local_size).ok_or(LayoutError::InvalidLocalSize(local_size))?;
+        self.dispatch(device, command_buffer, pipeline, layout, push_constants, group_count)
+    }
+
+    /// Returns the number of groups needed to cover the domain or [`None`] if the local size is 0
+    /// in any dimension.
+    pub fn group_count(domain: [u32; 3], local_size: [u32; 3]) -> Option<[u32; 3]> {
+        if local_size.contains(&0) {
+            return None;
+        }
+        Some([domain[0].div_ceil(local_size[0]), domain[1].div_ceil(local_size[1]), domain[2].div_ceil(local_size[2])])
+    }
+}
+
 impl Drop for ComputeShader {
-    fn drop(&mut self) {}
+    fn drop(&mut self) {
+        unsafe {
+            self.device.vk().destroy_shader_module(self.compute_shader, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shader::vertex::{data_type, VertexFormatBuilder};
+
+    fn make_context(mutable_uniforms: Vec<Uniform>, push_uniforms: Vec<Uniform>) -> GraphicsContext {
+        GraphicsContext {
+            mutable_uniforms: mutable_uniforms.into_iter().collect(),
+            push_uniforms: push_uniforms.into_iter().collect(),
+            vertex_format: VertexFormatBuilder::new().element(data_type::FLOAT, 3).build(),
+        }
+    }
+
+    #[test]
+    fn validate_unique_bindings() {
+        let context = make_context(vec![
+            Uniform::new("albedo", 0, 0, UniformType::ImageSampler),
+            Uniform::new("normal", 0, 1, UniformType::ImageSampler),
+        ], vec![
+            Uniform::new("lights", 1, 0, UniformType::StorageBuffer),
+        ]);
+
+        assert!(context.validate().is_ok());
+        assert_eq!(context.set_count(), 2);
+    }
+
+    #[test]
+    fn validate_duplicate_bindings() {
+        let context = make_context(vec![
+            Uniform::new("albedo", 0, 0, UniformType::ImageSampler),
+        ], vec![
+            Uniform::new("lights", 0, 0, UniformType::StorageBuffer),
+        ]);
+
+        match context.validate() {
+            Err(LayoutError::DuplicateBinding { set, binding, first, second }) => {
+                assert_eq!(set, 0);
+                assert_eq!(binding, 0);
+                assert_eq!(first, "albedo");
+                assert_eq!(second, "lights");
+            }
+            result => panic!("Expected duplicate binding error but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn validate_variable_count() {
+        let context = make_context(vec![
+            Uniform::new("textures", 0, 0, UniformType::ImageSampler).with_variable_count(1024),
+            Uniform::new("lights", 1, 0, UniformType::StorageBuffer),
+        ], vec![]);
+        assert!(context.validate().is_ok());
+
+        let context = make_context(vec![
+            Uniform::new("textures", 0, 0, UniformType::ImageSampler).with_variable_count(1024),
+            Uniform::new("lights", 0, 1, UniformType::StorageBuffer),
+        ], vec![]);
+        assert!(matches!(context.validate(), Err(LayoutError::VariableCountNotLast(name)) if name == "textures"));
+    }
+
+    #[test]
+    fn validate_limits() {
+        let limits = vk::PhysicalDeviceLimits {
+            max_per_stage_descriptor_samplers: 16,
+            max_per_stage_descriptor_sampled_images: 16,
+            max_per_stage_descriptor_storage_buffers: 4,
+            ..Default::default()
+        };
+
+        let context = make_context(vec![
+            Uniform::new("textures", 0, 0, UniformType::ImageSampler).with_count(8),
+            Uniform::new("lights", 0, 1, UniformType::StorageBuffer).with_count(2),
+        ], vec![
+            Uniform::new("shadows", 1, 0, UniformType::ImageSampler).with_count(8),
+            Uniform::new("objects", 1, 1, UniformType::DynamicStorageBuffer).with_count(2),
+        ]);
+        assert!(context.validate_limits(&limits).is_ok());
+
+        let context = make_context(vec![
+            Uniform::new("textures", 0, 0, UniformType::ImageSampler).with_count(8),
+        ], vec![
+            Uniform::new("shadows", 1, 0, UniformType::SampledImage).with_count(9),
+        ]);
+        match context.validate_limits(&limits) {
+            Err(LayoutError::LimitExceeded { uniform_type, count, limit }) => {
+                assert_eq!(uniform_type, UniformType::SampledImage);
+                assert_eq!(count, 17);
+                assert_eq!(limit, 16);
+            }
+            result => panic!("Expected limit exceeded error but got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn compute_push_constants() {
+        let limits = vk::PhysicalDeviceLimits {
+            max_push_constants_size: 128,
+            max_compute_work_group_size: [1024, 1024, 64],
+            max_compute_work_group_invocations: 1024,
+            ..Default::default()
+        };
+
+        let context = ComputeContext { state: ShaderStage {}, push_constant_size: 0, local_size: [64, 1, 1] };
+        assert!(context.push_constant_range().is_none());
+
+        let context = ComputeContext { state: ShaderStage {}, push_constant_size: 64, local_size: [64, 1, 1] };
+        let range = context.push_constant_range().unwrap();
+        assert_eq!(range.stage_flags, vk::ShaderStageFlags::COMPUTE);
+        assert_eq!(range.size, 64);
+        assert!(context.validate_limits(&limits).is_ok());
+
+        let context = ComputeContext { state: ShaderStage {}, push_constant_size: 256, local_size: [64, 1, 1] };
+        assert!(matches!(context.validate_limits(&limits), Err(LayoutError::PushConstantSize { size: 256, limit: 128 })));
+    }
+
+    #[test]
+    fn compute_local_size_limits() {
+        let limits = vk::PhysicalDeviceLimits {
+            max_push_constants_size: 128,
+            max_compute_work_group_size: [1024, 1024, 64],
+            max_compute_work_group_invocations: 1024,
+            ..Default::default()
+        };
+        let context = |local_size| ComputeContext { state: ShaderStage {}, push_constant_size: 0, local_size };
+
+        assert!(context([16, 16, 4]).validate_limits(&limits).is_ok());
+        assert!(matches!(context([16, 0, 1]).validate_limits(&limits), Err(LayoutError::InvalidLocalSize([16, 0, 1]))));
+        assert!(matches!(context([1, 1, 128]).validate_limits(&limits), Err(LayoutError::InvalidLocalSize(_))));
+        assert!(matches!(context([64, 64, 1]).validate_limits(&limits), Err(LayoutError::InvalidLocalSize(_))));
+        assert!(matches!(context([u32::MAX, u32::MAX, 1]).validate_limits(&limits), Err(LayoutError::InvalidLocalSize(_))));
+    }
+
+    #[test]
+    fn pipeline_batch_flags() {
+        assert_eq!(ComputeShader::batch_flags(0, 1), vk::PipelineCreateFlags::empty());
+        assert_eq!(ComputeShader::batch_flags(0, 3), vk::PipelineCreateFlags::ALLOW_DERIVATIVES);
+        assert_eq!(ComputeShader::batch_flags(2, 3), vk::PipelineCreateFlags::DERIVATIVE);
+    }
+
+    #[test]
+    fn dispatch_group_count() {
+        assert_eq!(ComputeShader::group_count([1920, 1080, 1], [16, 16, 1]), Some([120, 68, 1]));
+        assert_eq!(ComputeShader::group_count([64, 0, 1], [64, 1, 1]), Some([1, 0, 1]));
+        assert_eq!(ComputeShader::group_count([u32::MAX, 1, 1], [2, 1, 1]), Some([1 << 31, 1, 1]));
+        assert_eq!(ComputeShader::group_count([64, 64, 1], [8, 0, 1]), None);
+    }
+
+    #[test]
+    fn descriptor_pool_sizes() {
+        let context = make_context(vec![
+            Uniform::new("input", 0, 0, UniformType::StorageImage),
+            Uniform::new("output", 0, 1, UniformType::StorageImage),
+        ], vec![]);
+
+        let sizes = context.descriptor_pool_sizes(3);
+        assert_eq!(sizes.len(), 1);
+        assert_eq!(sizes[0].ty, vk::DescriptorType::STORAGE_IMAGE);
+        assert_eq!(sizes[0].descriptor_count, 6);
+
+        let other = make_context(vec![
+            Uniform::new("textures", 0, 0, UniformType::ImageSampler).with_variable_count(16),
+        ], vec![
+            Uniform::new("target", 1, 0, UniformType::StorageImage),
+        ]);
+
+        let sizes = GraphicsContext::combined_descriptor_pool_sizes(&[&context, &other], 2);
+        assert_eq!(sizes.len(), 2);
+        assert_eq!(sizes[0].ty, vk::DescriptorType::COMBINED_IMAGE_SAMPLER);
+        assert_eq!(sizes[0].descriptor_count, 32);
+        assert_eq!(sizes[1].ty, vk::DescriptorType::STORAGE_IMAGE);
+        assert_eq!(sizes[1].descriptor_count, 6);
+    }
 }
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use ash::vk::{
     Format, PipelineVertexInputStateCreateInfo, VertexInputAttributeDescription,
     VertexInputBindingDescription, VertexInputRate,
@@ -15,9 +17,20 @@ pub mod data_type {
     pub const FLOAT: usize = size_of::<f32>();
 }
 
+/// A element added to a [`VertexFormatBuilder`] which has not been validated yet.
+enum BuilderElement {
+    Attribute {
+        data_type: usize,
+        components: i32,
+        columns: i32,
+        location: Option<u32>,
+    },
+    Padding(usize),
+}
+
 #[derive(Default)]
 pub struct VertexFormatBuilder {
-    elements: Vec<VertexFormatElement>,
+    elements: Vec<BuilderElement>,
 }
 
 impl VertexFormatBuilder {
@@ -27,32 +40,179 @@ impl VertexFormatBuilder {
         }
     }
 
-    pub fn element(mut self, data_type: usize, amount: i32) -> VertexFormatBuilder {
-        self.elements.push(VertexFormatElement {
-            vk_type: Some(match data_type {
-                data_type::FLOAT =>
-                    match amount {
-                        3 => Format::R32G32B32_SFLOAT,
-                        _ => panic!("Cannot Handle '{}' Floats", amount)
-                    }
+    /// Returns the vulkan format of a vector element or None if the combination is not supported.
+    fn vk_type_for(data_type: usize, amount: i32) -> Option<Format> {
+        match data_type {
+            data_type::FLOAT =>
+                match amount {
+                    1 => Some(Format::R32_SFLOAT),
+                    2 => Some(Format::R32G32_SFLOAT),
+                    3 => Some(Format::R32G32B32_SFLOAT),
+                    4 => Some(Format::R32G32B32A32_SFLOAT),
+                    _ => None,
+                }
 
-                _ => panic!("Cannot Handle DataType '{}'", data_type)
-            }),
-            byte_length: data_type * amount as usize,
-        });
+            _ => None,
+        }
+    }
 
+    fn push(mut self, element: BuilderElement) -> VertexFormatBuilder {
+        self.elements.push(element);
         self
     }
 
-    pub fn build(mut self) -> VertexFormat {
-        VertexFormat::new(self.elements)
+    /// Adds a vector element at the location following the previous element.
+    pub fn element(self, data_type: usize, amount: i32) -> VertexFormatBuilder {
+        self.push(BuilderElement::Attribute { data_type, components: amount, columns: 1, location: None })
+    }
+
+    /// Adds a vector element at a explicit shader location.
+    pub fn element_at(self, location: u32, data_type: usize, amount: i32) -> VertexFormatBuilder {
+        self.push(BuilderElement::Attribute { data_type, components: amount, columns: 1, location: Some(location) })
+    }
+
+    /// Adds a matrix element at the location following the previous element. Each column of the
+    /// matrix occupies its own location.
+    pub fn matrix_element(self, data_type: usize, columns: i32, rows: i32) -> VertexFormatBuilder {
+        self.push(BuilderElement::Attribute { data_type, components: rows, columns, location: None })
+    }
+
+    /// Adds a matrix element starting at a explicit shader location. Each column of the matrix
+    /// occupies its own location.
+    pub fn matrix_element_at(self, location: u32, data_type: usize, columns: i32, rows: i32) -> VertexFormatBuilder {
+        self.push(BuilderElement::Attribute { data_type, components: rows, columns, location: Some(location) })
+    }
+
+    /// Adds padding bytes which are skipped by the vertex input.
+    pub fn pad(self, bytes: usize) -> VertexFormatBuilder {
+        self.push(BuilderElement::Padding(bytes))
+    }
+
+    /// Builds the vertex format.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any element has less than 1 or more than 4 components or matrix columns, uses an
+    /// unsupported data type or if any location is used by more than one element.
+    pub fn build(self) -> VertexFormat {
+        let elements = self.elements.into_iter().enumerate().map(|(index, element)| {
+            match element {
+                BuilderElement::Attribute { data_type, components, columns, location } => {
+                    if !(1..=4).contains(&components) {
+                        panic!("Vertex element {} has {} components but must have between 1 and 4", index, components);
+                    }
+                    if !(1..=4).contains(&columns) {
+                        panic!("Vertex element {} has {} matrix columns but must have between 1 and 4", index, columns);
+                    }
+                    let vk_type = Self::vk_type_for(data_type, components)
+                        .unwrap_or_else(|| panic!("Vertex element {} uses unsupported data type {}", index, data_type));
+
+                    VertexFormatElement {
+                        vk_type: Some(vk_type),
+                        byte_length: data_type * (columns * components) as usize,
+                        offset: 0,
+                        location,
+                        location_count: columns as u32,
+                    }
+                }
+                BuilderElement::Padding(bytes) => VertexFormatElement {
+                    vk_type: None,
+                    byte_length: bytes,
+                    offset: 0,
+                    location: None,
+                    location_count: 0,
+                },
+            }
+        }).collect();
+
+        VertexFormat::new(elements)
+    }
+}
+
+/// Describes the memory layout of a `#[repr(C)]` vertex struct so that it can be checked against a
+/// [`VertexFormat`] before its data is uploaded.
+///
+/// Usually implemented using the [`crate::impl_vertex_attributes`] macro.
+pub trait VertexAttributes: bytemuck::Pod {
+    /// Returns the byte offset of every attribute of the struct in the order the elements are added
+    /// to the vertex format.
+    fn attribute_offsets() -> Vec<usize>;
+}
+
+/// Implements [`VertexAttributes`] for a struct. The fields must be listed in the order the
+/// elements are added to the vertex format.
+///
+/// # Examples
+///
+/// ```
+/// use rosella_rs::impl_vertex_attributes;
+///
+/// #[repr(C)]
+/// #[derive(Copy, Clone)]
+/// struct Vertex {
+///     position: [f32; 3],
+///     color: [f32; 3],
+/// }
+///
+/// unsafe impl bytemuck::Zeroable for Vertex {}
+/// unsafe impl bytemuck::Pod for Vertex {}
+///
+/// impl_vertex_attributes!(Vertex { position, color });
+/// ```
+#[macro_export]
+macro_rules! impl_vertex_attributes {
+    ($vertex:ty { $($field:ident),* }) => {
+        impl $crate::shader::vertex::VertexAttributes for $vertex {
+            fn attribute_offsets() -> Vec<usize> {
+                let vertex = std::mem::MaybeUninit::<$vertex>::uninit();
+                let base = vertex.as_ptr();
+                vec![$(unsafe { std::ptr::addr_of!((*base).$field) as usize - base as usize },)*]
+            }
+        }
     }
 }
 
 /// A raw Element of a VertexFormat.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct VertexFormatElement {
     vk_type: Option<Format>,
     byte_length: usize,
+    offset: u32,
+    location: Option<u32>,
+    location_count: u32,
+}
+
+impl VertexFormatElement {
+    /// Returns the vulkan format of the element or None if the element is just padding.
+    pub fn vk_type(&self) -> Option<Format> {
+        self.vk_type
+    }
+
+    /// Returns the byte offset of the element inside a vertex.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns the size of the element in bytes.
+    pub fn size(&self) -> u32 {
+        self.byte_length as u32
+    }
+
+    /// Returns the shader location of the element or None if the element is just padding.
+    ///
+    /// For matrix elements this is the location of the first column.
+    pub fn location(&self) -> Option<u32> {
+        if self.vk_type.is_some() {
+            self.location
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of shader locations used by the element.
+    pub fn location_count(&self) -> u32 {
+        self.location_count
+    }
 }
 
 /// The format in which vertex data is stored. For example if you where storing position and color per Vertex, You may store it as 2 vec3's
@@ -60,30 +220,40 @@ pub struct VertexFormat {
     pub elements: Vec<VertexFormatElement>,
     pub vertex_stage_pipeline_info: PipelineVertexInputStateCreateInfo,
     pub size: u32,
+
+    // Referenced by vertex_stage_pipeline_info and must therefore be kept alive
+    attributes: Vec<VertexInputAttributeDescription>,
+    bindings: Vec<VertexInputBindingDescription>,
 }
 
 impl VertexFormat {
-    pub fn new(elements: Vec<VertexFormatElement>) -> VertexFormat {
-        let mut corrected_length = 0;
-        for element in elements.iter() {
-            if element.vk_type.is_some() {
-                corrected_length += 1;
-            }
-        }
-
+    pub fn new(mut elements: Vec<VertexFormatElement>) -> VertexFormat {
         let mut attributes: Vec<VertexInputAttributeDescription> = vec![];
+        let mut used_locations: HashMap<u32, usize> = HashMap::new();
         let mut offset = 0;
-        let mut element_id = 0;
-        for element in elements.iter() {
+        let mut next_location = 0;
+        for (index, element) in elements.iter_mut().enumerate() {
+            element.offset = offset;
+
             // Check if the element is just padding.
-            if element.vk_type.is_some() {
-                let attribute = VertexInputAttributeDescription::builder()
-                    .binding(0)
-                    .location(element_id)
-                    .format(element.vk_type.unwrap())
-                    .offset(offset);
-                attributes.push(attribute.build()); // Build is done here so the compiler has a chance to warn about dropped items
-                element_id += 1;
+            if let Some(vk_type) = element.vk_type {
+                let location = element.location.unwrap_or(next_location);
+                element.location = Some(location);
+
+                let column_size = (element.byte_length as u32) / element.location_count;
+                for column in 0..element.location_count {
+                    if let Some(other) = used_locations.insert(location + column, index) {
+                        panic!("Vertex element {} uses location {} which is already used by element {}", index, location + column, other);
+                    }
+
+                    let attribute = VertexInputAttributeDescription::builder()
+                        .binding(0)
+                        .location(location + column)
+                        .format(vk_type)
+                        .offset(offset + column * column_size);
+                    attributes.push(attribute.build()); // Build is done here so the compiler has a chance to warn about dropped items
+                }
+                next_location = location + element.location_count;
             }
             offset += element.byte_length as u32;
         }
@@ -104,6 +274,230 @@ impl VertexFormat {
             elements,
             vertex_stage_pipeline_info: pipeline_create_info,
             size: offset,
+            attributes,
+            bindings,
+        }
+    }
+
+    /// Returns the number of bytes between the start of two consecutive vertices.
+    pub fn stride(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns all elements of the format including padding.
+    pub fn elements(&self) -> &[VertexFormatElement] {
+        self.elements.as_slice()
+    }
+
+    /// Returns the vertex input attribute descriptions generated for this format.
+    pub fn attribute_descriptions(&self) -> &[VertexInputAttributeDescription] {
+        self.attributes.as_slice()
+    }
+
+    /// Returns the vertex input binding descriptions generated for this format.
+    pub fn binding_descriptions(&self) -> &[VertexInputBindingDescription] {
+        self.bindings.as_slice()
+    }
+
+    /// Checks if the memory layout of `T` matches this format. The size of `T` must be equal to
+    /// the size of the format and every attribute must be located at the offset of the
+    /// corresponding element.
+    pub fn matches_layout<T: VertexAttributes>(&self) -> bool {
+        if std::mem::size_of::<T>() != self.size as usize {
+            return false;
         }
+
+        let offsets: Vec<_> = self.elements.iter()
+            .filter(|element| element.location.is_some())
+            .map(|element| element.offset as usize)
+            .collect();
+
+        offsets == T::attribute_offsets()
+    }
+
+    /// Reinterprets a slice of vertices as bytes ready to be uploaded into a vertex buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layout of `T` does not match this format.
+    pub fn cast_vertices<'a, T: VertexAttributes>(&self, vertices: &'a [T]) -> &'a [u8] {
+        if !self.matches_layout::<T>() {
+            panic!("Vertex type layout does not match vertex format");
+        }
+
+        bytemuck::cast_slice(vertices)
+    }
+}
+
+/// Formats are compared by their elements only since all other fields are derived from them.
+impl PartialEq for VertexFormat {
+    fn eq(&self, other: &Self) -> bool {
+        self.elements == other.elements
+    }
+}
+
+impl Eq for VertexFormat {
+}
+
+impl std::hash::Hash for VertexFormat {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.elements.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    struct PositionColorVertex {
+        position: [f32; 3],
+        color: [f32; 3],
+    }
+
+    unsafe impl bytemuck::Zeroable for PositionColorVertex {}
+    unsafe impl bytemuck::Pod for PositionColorVertex {}
+
+    crate::impl_vertex_attributes!(PositionColorVertex { position, color });
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    struct PositionVertex {
+        position: [f32; 3],
+    }
+
+    unsafe impl bytemuck::Zeroable for PositionVertex {}
+    unsafe impl bytemuck::Pod for PositionVertex {}
+
+    crate::impl_vertex_attributes!(PositionVertex { position });
+
+    fn position_color_format() -> VertexFormat {
+        VertexFormatBuilder::new()
+            .element(data_type::FLOAT, 3)
+            .element(data_type::FLOAT, 3)
+            .build()
+    }
+
+    #[test]
+    fn format_equality() {
+        use std::collections::HashSet;
+
+        let padded = VertexFormatBuilder::new()
+            .element(data_type::FLOAT, 3)
+            .pad(4)
+            .element(data_type::FLOAT, 3)
+            .build();
+
+        assert!(position_color_format() == position_color_format());
+        assert!(position_color_format() != padded);
+
+        let mut formats = HashSet::new();
+        formats.insert(position_color_format());
+        formats.insert(padded);
+        assert!(formats.contains(&position_color_format()));
+        assert!(!formats.insert(position_color_format()));
+    }
+
+    #[test]
+    fn element_offsets() {
+        let format = VertexFormatBuilder::new()
+            .element(data_type::FLOAT, 3) // Position
+            .element(data_type::FLOAT, 2) // Tex coord
+            .element(data_type::FLOAT, 4) // Color
+            .element(data_type::FLOAT, 3) // Normal
+            .build();
+
+        assert_eq!(format.stride(), 48);
+
+        let offsets: Vec<_> = format.elements().iter().map(|element| element.offset()).collect();
+        assert_eq!(offsets, vec![0, 12, 20, 36]);
+
+        let sizes: Vec<_> = format.elements().iter().map(|element| element.size()).collect();
+        assert_eq!(sizes, vec![12, 8, 16, 12]);
+
+        let locations: Vec<_> = format.elements().iter().map(|element| element.location()).collect();
+        assert_eq!(locations, vec![Some(0), Some(1), Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn explicit_locations() {
+        let format = VertexFormatBuilder::new()
+            .element(data_type::FLOAT, 3)
+            .pad(4)
+            .element_at(2, data_type::FLOAT, 2)
+            .matrix_element(data_type::FLOAT, 4, 4)
+            .element(data_type::FLOAT, 4)
+            .build();
+
+        assert_eq!(format.stride(), 12 + 4 + 8 + 64 + 16);
+
+        let locations: Vec<_> = format.elements().iter().map(|element| element.location()).collect();
+        assert_eq!(locations, vec![Some(0), None, Some(2), Some(3), Some(7)]);
+
+        let offsets: Vec<_> = format.elements().iter().map(|element| element.offset()).collect();
+        assert_eq!(offsets, vec![0, 12, 16, 24, 88]);
+
+        let attributes: Vec<_> = format.attribute_descriptions().iter().map(|attribute| (attribute.location, attribute.offset)).collect();
+        assert_eq!(attributes, vec![(0, 0), (2, 16), (3, 24), (4, 40), (5, 56), (6, 72), (7, 88)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "location 1 which is already used by element 0")]
+    fn colliding_locations() {
+        VertexFormatBuilder::new()
+            .matrix_element(data_type::FLOAT, 2, 2)
+            .element_at(1, data_type::FLOAT, 3)
+            .build();
+    }
+
+    #[test]
+    #[should_panic(expected = "Vertex element 1 has 0 components")]
+    fn zero_components() {
+        VertexFormatBuilder::new()
+            .element(data_type::FLOAT, 3)
+            .element(data_type::FLOAT, 0)
+            .build();
+    }
+
+    #[test]
+    #[should_panic(expected = "Vertex element 0 has 5 components")]
+    fn too_many_components() {
+        VertexFormatBuilder::new()
+            .element(data_type::FLOAT, 5)
+            .build();
+    }
+
+    #[test]
+    #[should_panic(expected = "Vertex element 0 has 5 matrix columns")]
+    fn too_many_matrix_columns() {
+        VertexFormatBuilder::new()
+            .matrix_element(data_type::FLOAT, 5, 4)
+            .build();
+    }
+
+    #[test]
+    fn matches_layout() {
+        let format = position_color_format();
+
+        assert!(format.matches_layout::<PositionColorVertex>());
+        assert!(!format.matches_layout::<PositionVertex>());
+    }
+
+    #[test]
+    fn cast_vertices() {
+        let format = position_color_format();
+        let vertices = [PositionColorVertex { position: [0.0; 3], color: [1.0; 3] }; 3];
+
+        assert_eq!(format.cast_vertices(&vertices).len(), 3 * 24);
+    }
+
+    #[test]
+    #[should_panic]
+    fn cast_vertices_mismatch() {
+        let format = position_color_format();
+        let vertices = [PositionVertex { position: [0.0; 3] }; 3];
+
+        format.cast_vertices(&vertices);
     }
 }
@@ -3,6 +3,7 @@ use ash::{Entry, Instance};
 use crate::NamedUUID;
 use paste::paste;
 use crate::util::id::UUID;
+use crate::instance::VulkanVersion;
 
 #[derive(Clone)]
 pub struct ExtensionFunctionSet {
@@ -35,6 +36,76 @@ pub trait VkExtensionInfo {
     const UUID: NamedUUID;
 }
 
+/// Maps extension names to the extensions that must be enabled alongside them. Only direct
+/// dependencies of the same level are listed, i.e. a device extension only lists device extensions.
+/// Instance dependencies of device extensions like VK_KHR_get_physical_device_properties2 must be
+/// enabled by instance features.
+const EXTENSION_DEPENDENCIES: &[(&str, &[&str])] = &[
+    ("VK_KHR_acceleration_structure", &["VK_KHR_deferred_host_operations", "VK_KHR_buffer_device_address", "VK_EXT_descriptor_indexing"]),
+    ("VK_KHR_ray_tracing_pipeline", &["VK_KHR_acceleration_structure", "VK_KHR_spirv_1_4"]),
+    ("VK_KHR_spirv_1_4", &["VK_KHR_shader_float_controls"]),
+    ("VK_EXT_descriptor_indexing", &["VK_KHR_maintenance3"]),
+    ("VK_KHR_dynamic_rendering", &["VK_KHR_depth_stencil_resolve"]),
+    ("VK_KHR_depth_stencil_resolve", &["VK_KHR_create_renderpass2"]),
+    ("VK_KHR_create_renderpass2", &["VK_KHR_multiview", "VK_KHR_maintenance2"]),
+];
+
+/// Maps extensions that have been promoted to core to the vulkan version they were promoted in.
+/// Only extensions listed as a dependency in [`EXTENSION_DEPENDENCIES`] need to be listed.
+const EXTENSION_CORE_VERSIONS: &[(&str, VulkanVersion)] = &[
+    ("VK_KHR_maintenance2", VulkanVersion::VK_1_1),
+    ("VK_KHR_maintenance3", VulkanVersion::VK_1_1),
+    ("VK_KHR_multiview", VulkanVersion::VK_1_1),
+    ("VK_KHR_buffer_device_address", VulkanVersion::VK_1_2),
+    ("VK_KHR_create_renderpass2", VulkanVersion::VK_1_2),
+    ("VK_KHR_depth_stencil_resolve", VulkanVersion::VK_1_2),
+    ("VK_EXT_descriptor_indexing", VulkanVersion::VK_1_2),
+    ("VK_KHR_shader_float_controls", VulkanVersion::VK_1_2),
+    ("VK_KHR_spirv_1_4", VulkanVersion::VK_1_2),
+];
+
+/// Returns the vulkan version an extension has been promoted to core in or [`None`] if the
+/// extension is not part of core vulkan.
+pub fn get_extension_core_version(name: &str) -> Option<VulkanVersion> {
+    EXTENSION_CORE_VERSIONS.iter().find(|(extension, _)| *extension == name).map(|(_, version)| *version)
+}
+
+/// Returns the direct dependencies of an extension together with the name of the extension from
+/// the dependency table.
+fn find_extension_dependencies(name: &str) -> Option<(&'static str, &'static [&'static str])> {
+    EXTENSION_DEPENDENCIES.iter().find(|(extension, _)| *extension == name).copied()
+}
+
+/// Returns the direct dependencies of an extension
+pub fn get_extension_dependencies(name: &str) -> &'static [&'static str] {
+    find_extension_dependencies(name).map(|(_, dependencies)| dependencies).unwrap_or(&[])
+}
+
+/// Returns all dependencies of an extension including dependencies of dependencies. Each entry
+/// contains the name of the dependency and the name of the extension that requires it. Every
+/// dependency is only listed once.
+///
+/// Dependencies which are part of core vulkan in `version` are skipped together with their own
+/// dependencies.
+pub fn resolve_extension_dependencies(name: &str, version: VulkanVersion) -> Vec<(&'static str, &'static str)> {
+    let mut resolved: Vec<(&'static str, &'static str)> = Vec::new();
+    let mut pending: Vec<_> = find_extension_dependencies(name).into_iter().collect();
+
+    while let Some((extension, dependencies)) = pending.pop() {
+        for dependency in dependencies {
+            if get_extension_core_version(dependency).is_some_and(|core| version.is_supported(core)) {
+                continue;
+            }
+            if *dependency != name && !resolved.iter().any(|(resolved, _)| resolved == dependency) {
+                resolved.push((dependency, extension));
+                pending.extend(find_extension_dependencies(dependency));
+            }
+        }
+    }
+
+    resolved
+}
+
 pub type InstanceExtensionLoaderFn = dyn Fn(&mut ExtensionFunctionSet, &ash::Entry, &ash::Instance);
 pub type DeviceExtensionLoaderFn = dyn Fn(&mut ExtensionFunctionSet, &ash::Entry, &ash::Instance, &ash::Device);
 
@@ -98,12 +169,25 @@ macro_rules! make_vk_extension_info {
 }
 
 make_vk_extension_info!(
-    ash::extensions::khr::Swapchain, VK_KHR_Swapchain;
+    ash::extensions::khr::Surface, VK_KHR_surface;
+    ash::extensions::khr::Swapchain, VK_KHR_swapchain;
     ash::extensions::khr::GetPhysicalDeviceProperties2, VK_KHR_get_physical_device_properties2;
     ash::extensions::khr::TimelineSemaphore, VK_KHR_timeline_semaphore;
+    ash::extensions::khr::Synchronization2, VK_KHR_synchronization2;
+    ash::extensions::khr::DeferredHostOperations, VK_KHR_deferred_host_operations;
+    ash::extensions::khr::AccelerationStructure, VK_KHR_acceleration_structure;
+    ash::extensions::khr::RayTracingPipeline, VK_KHR_ray_tracing_pipeline;
+    ash::extensions::khr::DynamicRendering, VK_KHR_dynamic_rendering;
+    ash::extensions::khr::PushDescriptor, VK_KHR_push_descriptor;
     ash::extensions::ext::DebugUtils, VK_EXT_debug_utils
 );
 
+impl InstanceExtensionLoader for ash::extensions::khr::Surface {
+    fn load_extension(function_set: &mut ExtensionFunctionSet, entry: &Entry, instance: &Instance) {
+        function_set.add(Box::new(ash::extensions::khr::Surface::new(entry, instance)))
+    }
+}
+
 impl InstanceExtensionLoader for ash::extensions::khr::GetPhysicalDeviceProperties2 {
     fn load_extension(function_set: &mut ExtensionFunctionSet, entry: &Entry, instance: &Instance) {
         function_set.add(Box::new(ash::extensions::khr::GetPhysicalDeviceProperties2::new(entry, instance)))
@@ -116,8 +200,93 @@ impl InstanceExtensionLoader for ash::extensions::ext::DebugUtils {
     }
 }
 
+impl DeviceExtensionLoader for ash::extensions::khr::Swapchain {
+    fn load_extension(function_set: &mut ExtensionFunctionSet, _: &Entry, instance: &Instance, device: &ash::Device) {
+        function_set.add(Box::new(ash::extensions::khr::Swapchain::new(instance, device)))
+    }
+}
+
 impl DeviceExtensionLoader for ash::extensions::khr::TimelineSemaphore {
     fn load_extension(function_set: &mut ExtensionFunctionSet, _: &Entry, instance: &Instance, device: &ash::Device) {
         function_set.add(Box::new(ash::extensions::khr::TimelineSemaphore::new(instance, device)))
     }
-}
\ No newline at end of file
+}
+
+impl DeviceExtensionLoader for ash::extensions::khr::Synchronization2 {
+    fn load_extension(function_set: &mut ExtensionFunctionSet, _: &Entry, instance: &Instance, device: &ash::Device) {
+        function_set.add(Box::new(ash::extensions::khr::Synchronization2::new(instance, device)))
+    }
+}
+
+impl DeviceExtensionLoader for ash::extensions::khr::DeferredHostOperations {
+    fn load_extension(function_set: &mut ExtensionFunctionSet, _: &Entry, instance: &Instance, device: &ash::Device) {
+        function_set.add(Box::new(ash::extensions::khr::DeferredHostOperations::new(instance, device)))
+    }
+}
+
+impl DeviceExtensionLoader for ash::extensions::khr::AccelerationStructure {
+    fn load_extension(function_set: &mut ExtensionFunctionSet, _: &Entry, instance: &Instance, device: &ash::Device) {
+        function_set.add(Box::new(ash::extensions::khr::AccelerationStructure::new(instance, device)))
+    }
+}
+
+impl DeviceExtensionLoader for ash::extensions::khr::RayTracingPipeline {
+    fn load_extension(function_set: &mut ExtensionFunctionSet, _: &Entry, instance: &Instance, device: &ash::Device) {
+        function_set.add(Box::new(ash::extensions::khr::RayTracingPipeline::new(instance, device)))
+    }
+}
+
+impl DeviceExtensionLoader for ash::extensions::khr::DynamicRendering {
+    fn load_extension(function_set: &mut ExtensionFunctionSet, _: &Entry, instance: &Instance, device: &ash::Device) {
+        function_set.add(Box::new(ash::extensions::khr::DynamicRendering::new(instance, device)))
+    }
+}
+
+impl DeviceExtensionLoader for ash::extensions::khr::PushDescriptor {
+    fn load_extension(function_set: &mut ExtensionFunctionSet, _: &Entry, instance: &Instance, device: &ash::Device) {
+        function_set.add(Box::new(ash::extensions::khr::PushDescriptor::new(instance, device)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transitive_dependencies() {
+        let dependencies = resolve_extension_dependencies("VK_KHR_ray_tracing_pipeline", VulkanVersion::VK_1_0);
+        for (dependency, source) in [
+            ("VK_KHR_acceleration_structure", "VK_KHR_ray_tracing_pipeline"),
+            ("VK_KHR_spirv_1_4", "VK_KHR_ray_tracing_pipeline"),
+            ("VK_KHR_shader_float_controls", "VK_KHR_spirv_1_4"),
+            ("VK_EXT_descriptor_indexing", "VK_KHR_acceleration_structure"),
+            ("VK_KHR_maintenance3", "VK_EXT_descriptor_indexing"),
+        ] {
+            assert!(dependencies.contains(&(dependency, source)), "Missing dependency {} of {}", dependency, source);
+        }
+
+        let dependencies = resolve_extension_dependencies("VK_KHR_dynamic_rendering", VulkanVersion::VK_1_0);
+        let names: Vec<_> = dependencies.iter().map(|(dependency, _)| *dependency).collect();
+        assert_eq!(names.len(), 4);
+        for name in ["VK_KHR_depth_stencil_resolve", "VK_KHR_create_renderpass2", "VK_KHR_multiview", "VK_KHR_maintenance2"] {
+            assert!(names.contains(&name), "Missing dependency {}", name);
+        }
+
+        assert!(resolve_extension_dependencies("VK_KHR_swapchain", VulkanVersion::VK_1_0).is_empty());
+    }
+
+    #[test]
+    fn core_dependencies_skipped() {
+        let dependencies = resolve_extension_dependencies("VK_KHR_dynamic_rendering", VulkanVersion::VK_1_1);
+        assert_eq!(dependencies, vec![("VK_KHR_depth_stencil_resolve", "VK_KHR_dynamic_rendering"), ("VK_KHR_create_renderpass2", "VK_KHR_depth_stencil_resolve")]);
+
+        assert!(resolve_extension_dependencies("VK_KHR_dynamic_rendering", VulkanVersion::VK_1_2).is_empty());
+
+        let dependencies = resolve_extension_dependencies("VK_KHR_ray_tracing_pipeline", VulkanVersion::VK_1_2);
+        let names: Vec<_> = dependencies.iter().map(|(dependency, _)| *dependency).collect();
+        assert_eq!(names.len(), 2);
+        for name in ["VK_KHR_acceleration_structure", "VK_KHR_deferred_host_operations"] {
+            assert!(names.contains(&name), "Missing dependency {}", name);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Null vulkan implementation used to create instance and device contexts without a physical device.
+//!
+//! Only the functions needed to create and destroy the contexts, create an object manager,
+//! create and destroy semaphores and create object sets containing buffers are implemented. The
+//! physical device reports a single host visible and device local memory type which is backed by
+//! host memory. Calling any other vulkan function on a mock context aborts the process since the
+//! panic of the missing function cannot unwind through the vulkan calling convention.
+
+use std::collections::BTreeMap;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_void};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use ash::vk;
+use ash::vk::Handle;
+
+unsafe extern "system" fn mock_destroy_instance(_: vk::Instance, _: *const vk::AllocationCallbacks) {
+}
+
+unsafe extern "system" fn mock_destroy_device(_: vk::Device, _: *const vk::AllocationCallbacks) {
+}
+
+unsafe extern "system" fn mock_device_wait_idle(_: vk::Device) -> vk::Result {
+    vk::Result::SUCCESS
+}
+
+unsafe extern "system" fn mock_get_physical_device_properties(_: vk::PhysicalDevice, properties: *mut vk::PhysicalDeviceProperties) {
+    *properties = vk::PhysicalDeviceProperties {
+        api_version: vk::API_VERSION_1_2,
+        limits: vk::PhysicalDeviceLimits {
+            buffer_image_granularity: 1,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+}
+
+unsafe extern "system" fn mock_get_physical_device_memory_properties(_: vk::PhysicalDevice, properties: *mut vk::PhysicalDeviceMemoryProperties) {
+    let mut memory_properties = vk::PhysicalDeviceMemoryProperties {
+        memory_type_count: 1,
+        memory_heap_count: 1,
+        ..Default::default()
+    };
+    memory_properties.memory_types[0] = vk::MemoryType {
+        property_flags: vk::MemoryPropertyFlags::DEVICE_LOCAL | vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        heap_index: 0,
+    };
+    memory_properties.memory_heaps[0] = vk::MemoryHeap {
+        size: 1 << 30,
+        flags: vk::MemoryHeapFlags::DEVICE_LOCAL,
+    };
+    *properties = memory_properties;
+}
+
+/// Source of unique non null handles returned by create functions
+static NEXT_HANDLE: AtomicU64 = AtomicU64::new(1);
+
+/// The size of every live buffer
+static BUFFERS: Mutex<BTreeMap<u64, vk::DeviceSize>> = Mutex::new(BTreeMap::new());
+
+/// The host memory backing every live device memory allocation
+static MEMORY: Mutex<BTreeMap<u64, Box<[u8]>>> = Mutex::new(BTreeMap::new());
+
+/// Alignment reported for all memory requirements
+const MOCK_ALIGNMENT: vk::DeviceSize = 256;
+
+unsafe extern "system" fn mock_create_semaphore(_: vk::Device, _: *const vk::SemaphoreCreateInfo, _: *const vk::AllocationCallbacks, semaphore: *mut vk::Semaphore) -> vk::Result {
+    *semaphore = vk::Semaphore::from_raw(NEXT_HANDLE.fetch_add(1, Ordering::Relaxed));
+    vk::Result::SUCCESS
+}
+
+unsafe extern "system" fn mock_destroy_semaphore(_: vk::Device, _: vk::Semaphore, _: *const vk::AllocationCallbacks) {
+}
+
+unsafe extern "system" fn mock_create_buffer(_: vk::Device, info: *const vk::BufferCreateInfo, _: *const vk::AllocationCallbacks, buffer: *mut vk::Buffer) -> vk::Result {
+    let handle = NEXT_HANDLE.fetch_add(1, Ordering::Relaxed);
+    BUFFERS.lock().unwrap().insert(handle, (*info).size);
+    *buffer = vk::Buffer::from_raw(handle);
+    vk::Result::SUCCESS
+}
+
+unsafe extern "system" fn mock_destroy_buffer(_: vk::Device, buffer: vk::Buffer, _: *const vk::AllocationCallbacks) {
+    BUFFERS.lock().unwrap().remove(&buffer.as_raw());
+}
+
+unsafe extern "system" fn mock_get_buffer_memory_requirements(_: vk::Device, buffer: vk::Buffer, requirements: *mut vk::MemoryRequirements) {
+    let size = *BUFFERS.lock().unwrap().get(&buffer.as_raw()).expect("Unknown mock buffer");
+    *requirements = vk::MemoryRequirements {
+        size,
+        alignment: MOCK_ALIGNMENT,
+        memory_type_bits: 1,
+    };
+}
+
+unsafe extern "system" fn mock_bind_buffer_memory(_: vk::Device, _: vk::Buffer, _: vk::DeviceMemory, _: vk::DeviceSize) -> vk::Result {
+    vk::Result::SUCCESS
+}
+
+unsafe extern "system" fn mock_allocate_memory(_: vk::Device, info: *const vk::MemoryAllocateInfo, _: *const vk::AllocationCallbacks, memory: *mut vk::DeviceMemory) -> vk::Result {
+    let handle = NEXT_HANDLE.fetch_add(1, Ordering::Relaxed);
+    MEMORY.lock().unwrap().insert(handle, vec![0u8; (*info).allocation_size as usize].into_boxed_slice());
+    *memory = vk::DeviceMemory::from_raw(handle);
+    vk::Result::SUCCESS
+}
+
+unsafe extern "system" fn mock_free_memory(_: vk::Device, memory: vk::DeviceMemory, _: *const vk::AllocationCallbacks) {
+    MEMORY.lock().unwrap().remove(&memory.as_raw());
+}
+
+unsafe extern "system" fn mock_map_memory(_: vk::Device, memory: vk::DeviceMemory, offset: vk::DeviceSize, _: vk::DeviceSize, _: vk::MemoryMapFlags, data: *mut *mut c_void) -> vk::Result {
+    let mut allocations = MEMORY.lock().unwrap();
+    let allocation = allocations.get_mut(&memory.as_raw()).expect("Unknown mock memory");
+    *data = allocation.as_mut_ptr().add(offset as usize) as *mut c_void;
+    vk::Result::SUCCESS
+}
+
+unsafe extern "system" fn mock_unmap_memory(_: vk::Device, _: vk::DeviceMemory) {
+}
+
+/// Converts a mock function to a void function pointer. The function is coerced to `$pfn` first so
+/// the signature is checked against the vulkan function it replaces.
+macro_rules! void_function {
+    ($function:expr, $pfn:ty) => {
+        Some(std::mem::transmute::<$pfn, unsafe extern "system" fn()>($function))
+    };
+}
+
+unsafe extern "system" fn mock_get_device_proc_addr(_: vk::Device, name: *const c_char) -> vk::PFN_vkVoidFunction {
+    let function: vk::PFN_vkVoidFunction = match CStr::from_ptr(name).to_bytes() {
+        b"vkDestroyDevice" => void_function!(mock_destroy_device, vk::PFN_vkDestroyDevice),
+        b"vkDeviceWaitIdle" => void_function!(mock_device_wait_idle, vk::PFN_vkDeviceWaitIdle),
+        b"vkCreateSemaphore" => void_function!(mock_create_semaphore, vk::PFN_vkCreateSemaphore),
+        b"vkDestroySemaphore" => void_function!(mock_destroy_semaphore, vk::PFN_vkDestroySemaphore),
+        b"vkCreateBuffer" => void_function!(mock_create_buffer, vk::PFN_vkCreateBuffer),
+        b"vkDestroyBuffer" => void_function!(mock_destroy_buffer, vk::PFN_vkDestroyBuffer),
+        b"vkGetBufferMemoryRequirements" => void_function!(mock_get_buffer_memory_requirements, vk::PFN_vkGetBufferMemoryRequirements),
+        b"vkBindBufferMemory" => void_function!(mock_bind_buffer_memory, vk::PFN_vkBindBufferMemory),
+        b"vkAllocateMemory" => void_function!(mock_allocate_memory, vk::PFN_vkAllocateMemory),
+        b"vkFreeMemory" => void_function!(mock_free_memory, vk::PFN_vkFreeMemory),
+        b"vkMapMemory" => void_function!(mock_map_memory, vk::PFN_vkMapMemory),
+        b"vkUnmapMemory" => void_function!(mock_unmap_memory, vk::PFN_vkUnmapMemory),
+        _ => None,
+    };
+    function
+}
+
+unsafe extern "system" fn mock_get_instance_proc_addr(_: vk::Instance, name: *const c_char) -> vk::PFN_vkVoidFunction {
+    let function: vk::PFN_vkVoidFunction = match CStr::from_ptr(name).to_bytes() {
+        b"vkGetDeviceProcAddr" => void_function!(mock_get_device_proc_addr, vk::PFN_vkGetDeviceProcAddr),
+        b"vkDestroyInstance" => void_function!(mock_destroy_instance, vk::PFN_vkDestroyInstance),
+        b"vkGetPhysicalDeviceProperties" => void_function!(mock_get_physical_device_properties, vk::PFN_vkGetPhysicalDeviceProperties),
+        b"vkGetPhysicalDeviceMemoryProperties" => void_function!(mock_get_physical_device_memory_properties, vk::PFN_vkGetPhysicalDeviceMemoryProperties),
+        _ => None,
+    };
+    function
+}
+
+/// Creates a mock entry, instance and device. The returned handles are not null but do not refer to
+/// any real vulkan object.
+pub(crate) fn make_mock_vk() -> (ash::Entry, ash::Instance, ash::Device) {
+    let static_fn = vk::StaticFn { get_instance_proc_addr: mock_get_instance_proc_addr };
+
+    unsafe {
+        let entry = ash::Entry::from_static_fn(static_fn.clone());
+        let instance = ash::Instance::load(&static_fn, vk::Instance::from_raw(1));
+        let device = ash::Device::load(instance.fp_v1_0(), vk::Device::from_raw(1));
+
+        (entry, instance, device)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ash::vk;
+
+    use crate::objects::{BufferCreateDesc, ObjectManager};
+    use crate::rosella::DeviceContext;
+
+    #[test]
+    fn mock_device() {
+        let device = DeviceContext::new_mock();
+        let clone = device.clone();
+        drop(device);
+
+        assert!(clone.wait_idle().is_ok());
+        assert!(!clone.get_enabled_features().is_feature_enabled(&crate::NamedUUID::uuid_for("rosella:device_base")));
+    }
+
+    #[test]
+    fn mock_semaphores() {
+        let device = DeviceContext::new_mock();
+        let info = ash::vk::SemaphoreCreateInfo::default();
+
+        let first = unsafe { device.vk().create_semaphore(&info, None) }.unwrap();
+        let second = unsafe { device.vk().create_semaphore(&info, None) }.unwrap();
+        assert_ne!(first, second);
+
+        unsafe {
+            device.vk().destroy_semaphore(first, None);
+            device.vk().destroy_semaphore(second, None);
+        }
+    }
+
+    #[test]
+    fn mock_object_set() {
+        let manager = ObjectManager::new(DeviceContext::new_mock()).unwrap();
+
+        let mut builder = manager.create_object_set(manager.create_synchronization_group());
+        let gpu_only = builder.add_default_gpu_only_buffer(BufferCreateDesc::new_simple(1024, vk::BufferUsageFlags::TRANSFER_DST));
+        let gpu_cpu = builder.add_default_gpu_cpu_buffer(BufferCreateDesc::new_simple(512, vk::BufferUsageFlags::TRANSFER_SRC));
+        let set = builder.build().unwrap();
+
+        assert_eq!(set.len(), 2);
+        assert!(set.get_buffer_handle(gpu_only).is_some());
+        assert!(set.get_buffer_handle(gpu_cpu).is_some());
+        assert_ne!(set.get_buffer_handle(gpu_only), set.get_buffer_handle(gpu_cpu));
+
+        // Dropping the set must destroy the buffers
+        let buffer = set.get_buffer_handle(gpu_only).unwrap();
+        drop(set);
+        assert!(!super::BUFFERS.lock().unwrap().contains_key(&vk::Handle::as_raw(buffer)));
+    }
+}
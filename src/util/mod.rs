@@ -2,5 +2,8 @@ pub mod id;
 pub mod extensions;
 pub mod slice_splitter;
 
+#[cfg(any(test, feature = "testing"))]
+pub(crate) mod mock;
+
 #[cfg(test)]
 pub mod test;
@@ -10,7 +10,7 @@ pub fn make_headless_instance() -> InstanceContext {
     register_rosella_headless(&mut registry);
     register_rosella_debug(&mut registry, false);
 
-    create_instance(&mut registry, "RosellaUnitTests", 1).unwrap()
+    create_instance(&registry, "RosellaUnitTests", 1).unwrap()
 }
 
 pub fn make_headless_instance_device() -> (InstanceContext, DeviceContext) {
@@ -19,8 +19,8 @@ pub fn make_headless_instance_device() -> (InstanceContext, DeviceContext) {
     register_rosella_headless(&mut registry);
     register_rosella_debug(&mut registry, false);
 
-    let instance = create_instance(&mut registry, "RosellaUnitTests", 1).unwrap();
-    let device = create_device(&mut registry, instance.clone()).unwrap();
+    let instance = create_instance(&registry, "RosellaUnitTests", 1).unwrap();
+    let device = create_device(&registry, instance.clone()).unwrap();
 
     (instance, device)
 }
\ No newline at end of file
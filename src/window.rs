@@ -1,4 +1,5 @@
 use ash::extensions::khr::Surface;
+use ash::prelude::VkResult;
 use ash::vk::SurfaceKHR;
 use ash::{Entry, Instance};
 use winit::dpi::LogicalSize;
@@ -17,12 +18,11 @@ pub struct RosellaWindow {
 }
 
 impl RosellaSurface {
-    pub fn new(instance: &Instance, vk: &Entry, window: &RosellaWindow) -> Self {
-        RosellaSurface {
+    pub fn new(instance: &Instance, vk: &Entry, window: &RosellaWindow) -> VkResult<Self> {
+        Ok(RosellaSurface {
             ash_surface: Surface::new(vk, instance),
-            khr_surface: unsafe { ash_window::create_surface(vk, instance, &window.handle, None) }
-                .expect("Failed to create window surface."),
-        }
+            khr_surface: unsafe { ash_window::create_surface(vk, instance, &window.handle, None) }?,
+        })
     }
 }
 
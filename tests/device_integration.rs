@@ -0,0 +1,97 @@
+use ash::vk;
+
+use rosella_rs::init::device::{create_device_with_index, enumerate_devices};
+use rosella_rs::init::InitializationRegistry;
+use rosella_rs::init::rosella_features::register_rosella_headless;
+use rosella_rs::objects::{BufferCreateDesc, ObjectManager};
+
+#[macro_use]
+mod test_common;
+
+#[test]
+fn create_object_set_buffer() {
+    let (_, device) = require_headless_device!("create_object_set_buffer");
+
+    let manager = ObjectManager::new(device.clone()).unwrap();
+    let mut builder = manager.create_object_set(manager.create_synchronization_group());
+    let id = builder.add_default_gpu_only_buffer(BufferCreateDesc::new_simple(1024, vk::BufferUsageFlags::TRANSFER_DST));
+    let set = builder.build().unwrap();
+
+    assert!(set.get_buffer_handle(id).is_some());
+}
+
+#[test]
+fn submit_and_wait() {
+    let (_, device) = require_headless_device!("submit_and_wait");
+
+    let queue = device.get_graphics_queue()
+        .or_else(|| device.get_compute_queue())
+        .expect("Device has neither a graphics nor a compute queue");
+
+    let fence = unsafe { device.vk().create_fence(&vk::FenceCreateInfo::default(), None) }.unwrap();
+    queue.queue_submit(device.vk().clone(), &[], fence).unwrap();
+    unsafe {
+        device.vk().wait_for_fences(&[fence], true, u64::MAX).unwrap();
+        device.vk().destroy_fence(fence, None);
+    }
+}
+
+#[test]
+fn enumerate_and_select_device() {
+    let (instance, _) = require_headless_device!("enumerate_and_select_device");
+
+    let mut registry = InitializationRegistry::new();
+    register_rosella_headless(&mut registry);
+
+    let devices = enumerate_devices(&registry, instance.clone()).unwrap();
+    let selected = devices.iter().find(|device| device.is_suitable()).expect("No suitable device enumerated");
+    assert!(!selected.get_enabled_features().is_empty());
+
+    create_device_with_index(&registry, instance, selected.get_index()).unwrap();
+}
+
+#[test]
+fn wait_signaled_fence() {
+    let (_, device) = require_headless_device!("wait_signaled_fence");
+
+    let fence = device.create_fence_handle(true).unwrap();
+    assert!(fence.is_signaled().unwrap());
+    assert!(fence.wait(0).unwrap());
+
+    fence.reset().unwrap();
+    assert!(!fence.is_signaled().unwrap());
+    assert!(!fence.wait(0).unwrap());
+}
+
+#[test]
+fn immediate_submit() {
+    let (_, device) = require_headless_device!("immediate_submit");
+
+    let queue = device.get_transfer_queue().expect("Device has no transfer queue");
+    let mut recorded = false;
+    device.immediate_submit(&queue, |_| recorded = true).unwrap();
+
+    assert!(recorded);
+}
+
+#[test]
+fn download_filled_buffer() {
+    let (_, device) = require_headless_device!("download_filled_buffer");
+
+    let manager = ObjectManager::new(device.clone()).unwrap();
+    let group = manager.create_synchronization_group();
+    let mut builder = manager.create_object_set(group.clone());
+    let id = builder.add_default_gpu_only_buffer(BufferCreateDesc::new_simple(256, vk::BufferUsageFlags::TRANSFER_SRC | vk::BufferUsageFlags::TRANSFER_DST));
+    let set = builder.build().unwrap();
+    let buffer = set.get_buffer_handle(id).unwrap();
+
+    let queue = device.get_transfer_queue().expect("Device has no transfer queue");
+    let access = group.enqueue_access(1).unwrap();
+    device.immediate_submit_synchronized(&queue, &access, |command_buffer| unsafe {
+        device.vk().cmd_fill_buffer(command_buffer, buffer, 0, 256, 0x01020304);
+    }).unwrap();
+
+    let access = group.enqueue_access(1).unwrap();
+    let data: Vec<u32> = device.download_from_buffer_typed(&queue, buffer, 16, 4, Some(&access)).unwrap();
+    assert_eq!(data, vec![0x01020304u32; 4]);
+}
@@ -7,8 +7,8 @@ mod test_common;
 
 #[test]
 fn init_no_feature() {
-    let mut registry = InitializationRegistry::new();
-    let instance_context = match create_instance(&mut registry, "Rosella Test", 1) {
+    let registry = InitializationRegistry::new();
+    let instance_context = match create_instance(&registry, "Rosella Test", 1) {
         Ok(res) => res,
         Err(err) => {
             panic!("Failed to create instance {:?}", err);
@@ -16,7 +16,7 @@ fn init_no_feature() {
     };
 
     #[allow(unused)]
-    let device_context = match create_device(&mut registry, instance_context.clone()) {
+    let device_context = match create_device(&registry, instance_context.clone()) {
         Ok(res) => res,
         Err(err) => {
             panic!("Failed to create device {:?}", err);
@@ -29,7 +29,7 @@ fn init_rosella() {
     let mut registry = InitializationRegistry::new();
     register_rosella_headless(&mut registry);
 
-    let instance_context = match create_instance(&mut registry, "Rosella Test", 1) {
+    let instance_context = match create_instance(&registry, "Rosella Test", 1) {
         Ok(res) => res,
         Err(err) => {
             panic!("Failed to create instance {:?}", err);
@@ -37,7 +37,7 @@ fn init_rosella() {
     };
 
     #[allow(unused)]
-    let device_context = match create_device(&mut registry, instance_context.clone()) {
+    let device_context = match create_device(&registry, instance_context.clone()) {
         Ok(res) => res,
         Err(err) => {
             panic!("Failed to create device {:?}", err);
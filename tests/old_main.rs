@@ -10,7 +10,7 @@ use rosella_rs::init::initialization_registry::InitializationRegistry;
 use rosella_rs::init::rosella_features::{register_rosella_debug, register_rosella_headless};
 use rosella_rs::rosella::Rosella;
 use rosella_rs::window::RosellaWindow;
-use rosella_rs::shader::{GraphicsContext, GraphicsShader};
+use rosella_rs::shader::{GraphicsContext, GraphicsShader, ShaderCompileConfig};
 use rosella_rs::shader::vertex::VertexFormatBuilder;
 use rosella_rs::shader::vertex::data_type;
 
@@ -22,7 +22,7 @@ fn setup_rosella(window: &RosellaWindow) -> Rosella {
 
     match Rosella::new(registry, window, "new_new_rosella_example_scene_1") {
         Ok(rosella) => rosella,
-        Err(err) => panic!("Failed to create Rosella {:?}", err)
+        Err(err) => panic!("Failed to create Rosella: {}", err)
     }
 }
 
@@ -41,7 +41,7 @@ fn main() {
         mutable_uniforms: Default::default(),
         push_uniforms: Default::default(),
         vertex_format: basic_vertex_format,
-    });
+    }, &ShaderCompileConfig::default());
     println!("Successfully created shaders.");
 
     /*window.event_loop.run(move |event, _, control_flow| {
@@ -0,0 +1,53 @@
+//! Utilities shared by integration tests.
+//!
+//! Tests which need a vulkan device should use [`require_headless_device`]. If no device can be
+//! created (for example in a CI run without a gpu) the test is skipped with a message. To run these
+//! tests without a gpu a software implementation like lavapipe can be selected by setting
+//! `VK_ICD_FILENAMES`. Setting `ROSELLA_REQUIRE_DEVICE` turns a skipped test into a failure.
+#![allow(dead_code)]
+
+use rosella_rs::init::device::create_device;
+use rosella_rs::init::InitializationRegistry;
+use rosella_rs::init::instance::create_instance;
+use rosella_rs::init::rosella_features::{register_rosella_debug, register_rosella_headless};
+use rosella_rs::rosella::{DeviceContext, InstanceContext};
+
+/// Attempts to create a headless rosella instance and device.
+///
+/// Returns [`None`] and prints the reason if this fails unless `ROSELLA_REQUIRE_DEVICE` is set in
+/// which case this function panics.
+pub fn try_make_headless_device(test_name: &str) -> Option<(InstanceContext, DeviceContext)> {
+    let mut registry = InitializationRegistry::new();
+    register_rosella_headless(&mut registry);
+    register_rosella_debug(&mut registry, false);
+
+    let result = create_instance(&registry, "RosellaIntegrationTests", 1)
+        .map_err(|err| format!("Failed to create instance {:?}", err))
+        .and_then(|instance| {
+            create_device(&registry, instance.clone())
+                .map(|device| (instance, device))
+                .map_err(|err| format!("Failed to create device {:?}", err))
+        });
+
+    match result {
+        Ok(result) => Some(result),
+        Err(err) => {
+            if std::env::var_os("ROSELLA_REQUIRE_DEVICE").is_some() {
+                panic!("{}", err);
+            }
+            eprintln!("Skipping {}: no vulkan device available ({})", test_name, err);
+            None
+        }
+    }
+}
+
+/// Creates a headless instance and device or returns from the calling test if that is not possible.
+#[macro_export]
+macro_rules! require_headless_device {
+    ($test_name:literal) => {
+        match $crate::test_common::try_make_headless_device($test_name) {
+            Some(result) => result,
+            None => return,
+        }
+    }
+}